@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use aptos_move_graphql_scalars::Address;
+use entities::{canvas, canvas_account};
+use metadata_storage::PostgresMetadataStorage;
+use poem::{
+    get, handler,
+    http::StatusCode,
+    web::{Data, Json, Path},
+    Endpoint, EndpointExt, Route,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const BASE: &str = "/canvases";
+
+/// Whether an account can draw on a canvas, and if not, when it can.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CanvasAccountStatus {
+    pub canvas_address: Address,
+    pub account_address: Address,
+    /// The owner of the canvas is the super admin.
+    pub is_super_admin: bool,
+    pub is_admin: bool,
+    pub is_unlimited_artist: bool,
+    /// Whether the account can draw right now.
+    pub can_draw_now: bool,
+    /// When the account can next draw, in seconds since the unix epoch. If this is
+    /// None the account can't draw until an admin enables drawing for non admins.
+    pub next_draw_available_at_s: Option<u64>,
+    /// When the account last drew, if the canvas tracks that.
+    pub last_contribution_s: Option<u64>,
+    /// How many pixels the account can draw at once. If this is None there is no limit.
+    pub max_number_of_pixels_per_draw: Option<u64>,
+}
+
+/// This mirrors the checks in `draw` in the canvas_token module, namely
+/// `assert_canvas_enabled_for_non_unlimited_drawers` and
+/// `assert_timeout_and_update_last_contribution_time`.
+fn get_canvas_account_status(
+    canvas: &canvas::Model,
+    canvas_account: Option<&canvas_account::Model>,
+    account_address: Address,
+    now_s: u64,
+) -> Result<CanvasAccountStatus> {
+    let canvas_address =
+        Address::from_str(&canvas.canvas_address).context("Invalid canvas address")?;
+    let is_super_admin = canvas.owner_address.as_deref() == Some(&account_address.to_string());
+    let is_admin = is_super_admin || canvas_account.map_or(false, |a| a.is_admin);
+    let is_unlimited_artist = canvas_account.map_or(false, |a| a.is_unlimited_artist);
+    let last_contribution_s = canvas_account
+        .and_then(|a| a.last_contribution_s)
+        .map(|s| s as u64);
+    let can_draw_unlimited = is_admin || is_unlimited_artist;
+
+    let next_draw_available_at_s = if can_draw_unlimited {
+        Some(now_s)
+    } else if !canvas.draw_enabled_for_non_admin {
+        None
+    } else {
+        match (canvas.per_account_timeout_s as u64, last_contribution_s) {
+            (0, _) | (_, None) => Some(now_s),
+            (timeout_s, Some(last_contribution_s)) => {
+                Some(std::cmp::max(now_s, last_contribution_s + timeout_s))
+            },
+        }
+    };
+
+    Ok(CanvasAccountStatus {
+        canvas_address,
+        account_address,
+        is_super_admin,
+        is_admin,
+        is_unlimited_artist,
+        can_draw_now: next_draw_available_at_s == Some(now_s),
+        next_draw_available_at_s,
+        last_contribution_s,
+        max_number_of_pixels_per_draw: if can_draw_unlimited {
+            None
+        } else {
+            Some(canvas.max_number_of_pixels_per_draw as u64)
+        },
+    })
+}
+
+#[handler]
+async fn get_account(
+    metadata_storage: Data<&Arc<PostgresMetadataStorage>>,
+    Path((canvas_address, account_address)): Path<(String, String)>,
+) -> poem::Result<Json<CanvasAccountStatus>> {
+    let canvas_address = Address::from_str(&canvas_address)
+        .map_err(|_| poem::Error::from_string("Invalid canvas address", StatusCode::BAD_REQUEST))?;
+    let account_address = Address::from_str(&account_address).map_err(|_| {
+        poem::Error::from_string("Invalid account address", StatusCode::BAD_REQUEST)
+    })?;
+    let canvas = metadata_storage
+        .read_canvas(&canvas_address)
+        .await?
+        .ok_or_else(|| {
+            poem::Error::from_string(
+                format!("Canvas {} not found", canvas_address),
+                StatusCode::NOT_FOUND,
+            )
+        })?;
+    let canvas_account = metadata_storage
+        .read_canvas_account(&canvas_address, &account_address)
+        .await?;
+    // We use the local time rather than the time on chain, so this could be slightly
+    // off from what the contract would say.
+    let now_s = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("Time went backwards")?
+        .as_secs();
+    let status =
+        get_canvas_account_status(&canvas, canvas_account.as_ref(), account_address, now_s)?;
+    Ok(Json(status))
+}
+
+#[handler]
+async fn root() -> String {
+    "Hello from the canvases API!!".to_string()
+}
+
+pub struct CanvasApi {
+    metadata_storage: Arc<PostgresMetadataStorage>,
+}
+
+impl CanvasApi {
+    pub fn new(metadata_storage: Arc<PostgresMetadataStorage>) -> Self {
+        Self { metadata_storage }
+    }
+
+    pub fn get_route(self) -> Result<impl Endpoint> {
+        Ok(Route::new()
+            .at("/", get(root))
+            .at("/:address/accounts/:account", get(get_account))
+            .data(self.metadata_storage.clone()))
+    }
+}
@@ -1,14 +1,17 @@
-use crate::{MetadataApi, PixelApi};
+use crate::{
+    health::{healthz, readyz},
+    CanvasApi, HealthChecks, HealthConfig, MetadataApi, PixelApi, ReplicationApi,
+};
 use anyhow::{Context, Result};
 use metadata_storage::PostgresMetadataStorage;
-use pixel_storage::PixelStorageTrait;
 use poem::{
     get, handler,
-    http::Method,
+    http::{Method, StatusCode},
     listener::TcpListener,
     middleware::{Cors, Tracing},
-    EndpointExt, Route, Server,
+    EndpointExt, Response, Route, Server,
 };
+use prometheus::{Encoder, TextEncoder};
 use serde::{Deserialize, Serialize};
 use std::{
     net::{Ipv4Addr, SocketAddrV4},
@@ -21,12 +24,14 @@ use tracing::info;
 #[serde(default)]
 pub struct ApiConfig {
     pub listen_address: SocketAddrV4,
+    pub health_config: HealthConfig,
 }
 
 impl Default for ApiConfig {
     fn default() -> Self {
         Self {
             listen_address: SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 7645),
+            health_config: HealthConfig::default(),
         }
     }
 }
@@ -44,24 +49,38 @@ pub async fn start_api(config: ApiConfig, route: Route) -> Result<()> {
 }
 
 /// This convenience method helps with building an API with the desired routes based
-/// on the APIs the user has built.
+/// on the APIs the user has built. The health endpoints run the given checks.
 pub fn build_full_route(
-    pixel_storage: Option<Arc<dyn PixelStorageTrait>>,
+    pixel_api: Option<PixelApi>,
     metadata_storage: Option<Arc<PostgresMetadataStorage>>,
+    replication_api: Option<ReplicationApi>,
+    health_checks: Arc<HealthChecks>,
 ) -> Result<Route> {
     let mut route = Route::new().nest("/", get(v1_root));
-    if let Some(pixel_storage) = pixel_storage {
-        let pixel_api = PixelApi::new(pixel_storage);
+    if let Some(pixel_api) = pixel_api {
         let pixel_route = pixel_api.get_route()?;
         route = route.nest(crate::pixel_api::BASE, pixel_route);
     }
     if let Some(metadata_storage) = metadata_storage {
+        let canvas_api = CanvasApi::new(metadata_storage.clone());
+        let canvas_route = canvas_api.get_route()?;
+        route = route.nest(crate::canvas_api::BASE, canvas_route);
         let metadata_api = MetadataApi::new(metadata_storage);
         let metadata_route = metadata_api.get_route()?;
         route = route.nest(crate::metadata_api::BASE, metadata_route);
     }
-    // Nest everything under /v1
-    Ok(Route::new().at("/", get(root)).nest("/v1", route))
+    if let Some(replication_api) = replication_api {
+        let replication_route = replication_api.get_route()?;
+        route = route.nest(crate::replication::BASE, replication_route);
+    }
+    // Nest everything under /v1, other than the metrics and health checks, which are
+    // where Prometheus and k8s expect them to be.
+    Ok(Route::new()
+        .at("/", get(root))
+        .at("/metrics", get(metrics))
+        .at("/healthz", get(healthz).data(health_checks.clone()))
+        .at("/readyz", get(readyz).data(health_checks))
+        .nest("/v1", route))
 }
 
 #[handler]
@@ -73,3 +92,22 @@ async fn root() -> String {
 async fn v1_root() -> String {
     "Hello from the root at /v1!!".to_string()
 }
+
+/// Serve the metrics from every component running in this process (the processor,
+/// storage, flushers, etc.) in the Prometheus text format.
+#[handler]
+async fn metrics() -> poem::Result<Response> {
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    encoder
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|e| {
+            poem::Error::from_string(
+                format!("Failed to encode metrics: {}", e),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        })?;
+    Ok(Response::builder()
+        .content_type(encoder.format_type())
+        .body(buffer))
+}
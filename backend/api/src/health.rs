@@ -0,0 +1,259 @@
+//! `/healthz` says whether the service is alive, i.e. whether restarting it might
+//! help. `/readyz` says whether it is doing its job, e.g. whether the processor is
+//! keeping up with the chain. Each runs a set of checks and responds with the result
+//! of each as JSON, with a 503 if any of them failed.
+
+use anyhow::{bail, Context, Result};
+use metadata_storage::PostgresMetadataStorage;
+use pixel_storage::PixelStorageTrait;
+use poem::{
+    handler,
+    http::StatusCode,
+    web::{Data, Json},
+    IntoResponse, Response,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+use tracing::error;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// If a check takes longer than this it counts as failed.
+    pub check_timeout_ms: u64,
+
+    /// We're not ready if the last txn a processor processed is older than this.
+    pub max_processor_lag_secs: u64,
+
+    /// We're not ready if a flusher hasn't flushed successfully for this long.
+    pub max_flush_age_secs: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            check_timeout_ms: 2000,
+            max_processor_lag_secs: 300,
+            max_flush_age_secs: 300,
+        }
+    }
+}
+
+/// Whether a failing check means the service is unhealthy or just not ready.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HealthCheckKind {
+    /// Checked by both `/healthz` and `/readyz`.
+    Liveness,
+    /// Only checked by `/readyz`.
+    Readiness,
+}
+
+#[async_trait::async_trait]
+pub trait HealthCheck: Debug + Send + Sync + 'static {
+    fn name(&self) -> String;
+
+    fn kind(&self) -> HealthCheckKind;
+
+    /// Returns a description of the state of the thing being checked if it is
+    /// healthy, or an error saying what is wrong if not.
+    async fn check(&self) -> Result<String>;
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HealthCheckResult {
+    pub name: String,
+    pub healthy: bool,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub checks: Vec<HealthCheckResult>,
+}
+
+/// The checks the health endpoints run.
+#[derive(Debug)]
+pub struct HealthChecks {
+    config: HealthConfig,
+    checks: Vec<Arc<dyn HealthCheck>>,
+}
+
+impl HealthChecks {
+    pub fn new(config: HealthConfig) -> Self {
+        Self {
+            config,
+            checks: vec![],
+        }
+    }
+
+    pub fn add(&mut self, check: Arc<dyn HealthCheck>) {
+        self.checks.push(check);
+    }
+
+    /// Run the checks concurrently. If `include_readiness` is false we only run the
+    /// liveness checks.
+    pub async fn run(&self, include_readiness: bool) -> HealthReport {
+        let timeout = Duration::from_millis(self.config.check_timeout_ms);
+        let futures = self
+            .checks
+            .iter()
+            .filter(|check| include_readiness || check.kind() == HealthCheckKind::Liveness)
+            .map(|check| async move {
+                let result = match tokio::time::timeout(timeout, check.check()).await {
+                    Ok(result) => result,
+                    Err(_) => Err(anyhow::anyhow!(
+                        "Check timed out after {}ms",
+                        timeout.as_millis()
+                    )),
+                };
+                match result {
+                    Ok(message) => HealthCheckResult {
+                        name: check.name(),
+                        healthy: true,
+                        message,
+                    },
+                    Err(error) => HealthCheckResult {
+                        name: check.name(),
+                        healthy: false,
+                        message: format!("{:#}", error),
+                    },
+                }
+            });
+        let checks = futures::future::join_all(futures).await;
+        HealthReport {
+            healthy: checks.iter().all(|check| check.healthy),
+            checks,
+        }
+    }
+}
+
+fn build_response(report: HealthReport) -> Response {
+    let status = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Json(report).with_status(status).into_response()
+}
+
+#[handler]
+pub(crate) async fn healthz(health_checks: Data<&Arc<HealthChecks>>) -> Response {
+    build_response(health_checks.run(false).await)
+}
+
+#[handler]
+pub(crate) async fn readyz(health_checks: Data<&Arc<HealthChecks>>) -> Response {
+    build_response(health_checks.run(true).await)
+}
+
+/// Keeps track of whether the long running tasks of the service are still running.
+/// Tasks are added with `watch`, which can be done after the checks are built.
+#[derive(Debug, Default)]
+pub struct TaskHealthCheck {
+    tasks: Mutex<Vec<(String, Arc<AtomicBool>)>>,
+}
+
+impl TaskHealthCheck {
+    /// Watch the given task. This returns a handle to use in place of the original,
+    /// which finishes when the original does.
+    pub fn watch(&self, name: &str, task: JoinHandle<()>) -> JoinHandle<()> {
+        let running = Arc::new(AtomicBool::new(true));
+        self.tasks
+            .lock()
+            .unwrap()
+            .push((name.to_string(), running.clone()));
+        let name = name.to_string();
+        tokio::spawn(async move {
+            let result = task.await;
+            running.store(false, Ordering::SeqCst);
+            if let Err(error) = result {
+                error!("Task {} panicked or was cancelled: {:?}", name, error);
+            }
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for TaskHealthCheck {
+    fn name(&self) -> String {
+        "tasks".to_string()
+    }
+
+    fn kind(&self) -> HealthCheckKind {
+        HealthCheckKind::Liveness
+    }
+
+    async fn check(&self) -> Result<String> {
+        let tasks = self.tasks.lock().unwrap();
+        let stopped: Vec<&str> = tasks
+            .iter()
+            .filter(|(_, running)| !running.load(Ordering::SeqCst))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if !stopped.is_empty() {
+            bail!("Tasks stopped: {}", stopped.join(", "));
+        }
+        Ok(format!("{} tasks running", tasks.len()))
+    }
+}
+
+/// Checks we can reach the DB. We can't do anything without it but restarting won't
+/// fix it either, so this only affects readiness.
+#[derive(Debug)]
+pub struct MetadataStorageHealthCheck {
+    pub metadata_storage: Arc<PostgresMetadataStorage>,
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for MetadataStorageHealthCheck {
+    fn name(&self) -> String {
+        "metadata_storage".to_string()
+    }
+
+    fn kind(&self) -> HealthCheckKind {
+        HealthCheckKind::Readiness
+    }
+
+    async fn check(&self) -> Result<String> {
+        self.metadata_storage
+            .ping()
+            .await
+            .context("Failed to reach DB")?;
+        Ok("Connected".to_string())
+    }
+}
+
+/// Checks the pixel storage is usable, e.g. the disk is still mounted.
+#[derive(Debug)]
+pub struct PixelStorageHealthCheck {
+    pub pixel_storage: Arc<dyn PixelStorageTrait>,
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for PixelStorageHealthCheck {
+    fn name(&self) -> String {
+        "pixel_storage".to_string()
+    }
+
+    fn kind(&self) -> HealthCheckKind {
+        HealthCheckKind::Liveness
+    }
+
+    async fn check(&self) -> Result<String> {
+        self.pixel_storage
+            .check_health()
+            .await
+            .context("Pixel storage is unavailable")?;
+        Ok("Available".to_string())
+    }
+}
@@ -1,7 +1,22 @@
+mod canvas_api;
 mod common;
+mod health;
 mod metadata_api;
 mod pixel_api;
+mod replication;
 
+pub use canvas_api::{CanvasAccountStatus, CanvasApi};
 pub use common::{build_full_route, start_api, ApiConfig};
+pub use health::{
+    HealthCheck, HealthCheckKind, HealthCheckResult, HealthChecks, HealthConfig, HealthReport,
+    MetadataStorageHealthCheck, PixelStorageHealthCheck, TaskHealthCheck,
+};
 pub use metadata_api::MetadataApi;
-pub use pixel_api::PixelApi;
+pub use pixel_api::{
+    ObjectStoreConfig, ObjectStorePixelApiConfig, ObjectStorePixelApiMode, ObjectStorePixelSource,
+    PixelApi,
+};
+pub use replication::{
+    ReplicaConfig, ReplicaFollower, ReplicaHealthCheck, ReplicationApi, ReplicationFeedConfig,
+    ReplicationMessage, ReplicationRole, ReplicationStatus,
+};
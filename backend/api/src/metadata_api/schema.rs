@@ -18,8 +18,14 @@ pub fn build_schema(
 ) -> Result<Schema, SchemaError> {
     let mut builder = Builder::new(&BUILDER_CONTEXT);
     seaography::register_entities!(builder, [
+        canvas,
+        canvas_account,
+        canvas_snapshot,
         chain_id,
+        collection,
         last_processed_version,
+        paint_activity,
+        paint_balance,
         pixel_attribution,
     ]);
     let schema = builder.schema_builder();
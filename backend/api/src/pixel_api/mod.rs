@@ -1,39 +1,79 @@
+mod object_store;
+
 use anyhow::{Context, Result};
 use aptos_move_graphql_scalars::Address;
-use pixel_storage::PixelStorageTrait;
+pub use object_store::{
+    ObjectStoreConfig, ObjectStorePixelApiConfig, ObjectStorePixelApiMode, ObjectStorePixelSource,
+};
+use pixel_storage::{PixelStorageError, PixelStorageTrait};
 use poem::{
     get, handler,
+    http::StatusCode,
     web::{Data, Path},
-    Endpoint, EndpointExt, Response, Route,
+    EndpointExt, Response, Route,
 };
 use std::{str::FromStr, sync::Arc};
+use tracing::error;
 
 pub const BASE: &str = "/pixels";
 
+/// The version of the last txn reflected in the image, where known.
+const CANVAS_VERSION_HEADER: &str = "x-canvas-version";
+
+fn strip_png_extension(address: String) -> String {
+    if address.ends_with(".png") {
+        address[..address.len() - 4].to_string()
+    } else {
+        address
+    }
+}
+
 #[handler]
 async fn get_image(
     pixel_storage: Data<&Arc<dyn PixelStorageTrait>>,
     Path(address): Path<String>,
 ) -> poem::Result<Response> {
-    let address = if address.ends_with(".png") {
-        address[..address.len() - 4].to_string()
-    } else {
-        address
-    };
+    let address = strip_png_extension(address);
     let address = Address::from_str(&address).context("Invalid address")?;
-    let png_data = pixel_storage
-        .get_canvas_as_png(&address)
-        .await
-        .with_context(|| {
-            format!(
-                "Failed to get image for address {}",
-                address.to_canonical_string()
-            )
-        })
-        .unwrap();
-    Ok(Response::builder()
-        .body(png_data)
-        .set_content_type("image/png"))
+    let png = match pixel_storage.get_canvas_as_png_with_version(&address).await {
+        Ok(png) => png,
+        Err(error) => {
+            if let Some(PixelStorageError::CanvasNotFound { .. }) =
+                error.downcast_ref::<PixelStorageError>()
+            {
+                return Err(poem::Error::from_string(
+                    "Canvas not found",
+                    StatusCode::NOT_FOUND,
+                ));
+            }
+            error!(
+                "Failed to get image for address {}: {:#}",
+                address.to_canonical_string(),
+                error
+            );
+            return Err(poem::Error::from_string(
+                "Failed to get image",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ));
+        },
+    };
+    // Let clients tell how fresh the image is, e.g. when it comes from a replica.
+    let mut builder = Response::builder();
+    if let Some(version) = png.version {
+        builder = builder.header(CANVAS_VERSION_HEADER, version.to_string());
+    }
+    Ok(builder.body(png.png_data).set_content_type("image/png"))
+}
+
+#[handler]
+async fn get_image_from_object_store(
+    source: Data<&Arc<ObjectStorePixelSource>>,
+    Path(address): Path<String>,
+) -> poem::Result<Response> {
+    let address = strip_png_extension(address);
+    let address = Address::from_str(&address)
+        .map_err(|_| poem::Error::from_string("Invalid address", StatusCode::BAD_REQUEST))?;
+    source.get_image_response(&address).await
 }
 
 #[handler]
@@ -41,19 +81,42 @@ async fn root() -> String {
     "Hello from the pixels API!!".to_string()
 }
 
+/// Where the pixel API gets the images from.
+enum PixelSource {
+    /// Render the images from the pixel storage in this process.
+    Local(Arc<dyn PixelStorageTrait>),
+    /// Serve the images the flusher wrote to an object store, for when there is no
+    /// pixel storage in this process.
+    ObjectStore(Arc<ObjectStorePixelSource>),
+}
+
 pub struct PixelApi {
-    pixel_storage: Arc<dyn PixelStorageTrait>,
+    source: PixelSource,
 }
 
 impl PixelApi {
     pub fn new(pixel_storage: Arc<dyn PixelStorageTrait>) -> Self {
-        Self { pixel_storage }
+        Self {
+            source: PixelSource::Local(pixel_storage),
+        }
+    }
+
+    pub async fn from_object_store(config: ObjectStorePixelApiConfig) -> Result<Self> {
+        let source = ObjectStorePixelSource::new(config).await?;
+        Ok(Self {
+            source: PixelSource::ObjectStore(Arc::new(source)),
+        })
     }
 
-    pub fn get_route(self) -> Result<impl Endpoint> {
-        Ok(Route::new()
-            .at("/", get(root))
-            .at("/:address", get(get_image))
-            .data(self.pixel_storage.clone()))
+    pub fn get_route(self) -> Result<Route> {
+        let route = Route::new().at("/", get(root));
+        Ok(match self.source {
+            PixelSource::Local(pixel_storage) => {
+                route.at("/:address", get(get_image).data(pixel_storage))
+            },
+            PixelSource::ObjectStore(source) => {
+                route.at("/:address", get(get_image_from_object_store).data(source))
+            },
+        })
     }
 }
@@ -0,0 +1,268 @@
+//! In split deployments the processor and flusher run separately from the API, so the
+//! API has no local pixel storage to serve images from. Instead we serve
+//! `/v1/pixels/:address` from wherever the flusher put the images, either by
+//! redirecting to them or by proxying them, so clients use the same API regardless of
+//! how the service is deployed.
+
+use anyhow::{Context, Result};
+use aptos_move_graphql_scalars::Address;
+use flusher::{get_overwritten_image_path, Manifest, MANIFEST_PATH};
+use google_cloud_storage::{
+    client::{Client, ClientConfig},
+    sign::{SignedURLMethod, SignedURLOptions},
+};
+use poem::{
+    http::{header, StatusCode},
+    Response,
+};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectStorePixelApiConfig {
+    /// Where the flusher writes the images.
+    pub store: ObjectStoreConfig,
+
+    /// Whether clients are redirected to the images or we fetch them ourselves.
+    #[serde(default)]
+    pub mode: ObjectStorePixelApiMode,
+
+    /// This must match `content_addressed` in the config of the flusher. If set, we
+    /// look up the latest image for each canvas in the manifest rather than using
+    /// `images/<address>.png`.
+    #[serde(default)]
+    pub content_addressed: bool,
+
+    /// How long we cache the manifest for, if `content_addressed` is set.
+    #[serde(default = "ObjectStorePixelApiConfig::default_manifest_cache_secs")]
+    pub manifest_cache_secs: u64,
+}
+
+impl ObjectStorePixelApiConfig {
+    pub fn default_manifest_cache_secs() -> u64 {
+        10
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectStorePixelApiMode {
+    /// Respond with a redirect to the image. This is cheapest for us, and lets
+    /// clients use any CDN in front of the store.
+    #[default]
+    Redirect,
+    /// Fetch the image and return it in the response. This is useful if clients
+    /// can't follow redirects or can't reach the store directly.
+    Proxy,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub enum ObjectStoreConfig {
+    /// The objects are publicly readable under this URL, e.g.
+    /// `https://storage.googleapis.com/<bucket>` or a CDN in front of it.
+    Public { base_url: String },
+    /// The objects are in a private GCS bucket, so we use signed URLs. Like the
+    /// GcsFlusher, this only works when running inside GCP.
+    Gcs {
+        bucket_name: String,
+        #[serde(default = "ObjectStoreConfig::default_signed_url_expiry_secs")]
+        signed_url_expiry_secs: u64,
+    },
+}
+
+impl ObjectStoreConfig {
+    pub fn default_signed_url_expiry_secs() -> u64 {
+        300
+    }
+}
+
+enum ObjectStore {
+    Public {
+        base_url: String,
+    },
+    Gcs {
+        client: Client,
+        bucket_name: String,
+        signed_url_expiry: Duration,
+    },
+}
+
+impl ObjectStore {
+    /// Get a URL that the object at the given path can be read from.
+    async fn get_url(&self, path: &str) -> Result<String> {
+        match self {
+            ObjectStore::Public { base_url } => {
+                Ok(format!("{}/{}", base_url.trim_end_matches('/'), path))
+            },
+            ObjectStore::Gcs {
+                client,
+                bucket_name,
+                signed_url_expiry,
+            } => client
+                .signed_url(bucket_name, path, None, None, SignedURLOptions {
+                    method: SignedURLMethod::GET,
+                    expires: *signed_url_expiry,
+                    ..Default::default()
+                })
+                .await
+                .with_context(|| format!("Failed to sign URL for {}", path)),
+        }
+    }
+}
+
+pub struct ObjectStorePixelSource {
+    config: ObjectStorePixelApiConfig,
+    store: ObjectStore,
+    http_client: reqwest::Client,
+    /// The manifest and when we read it, if `content_addressed` is set.
+    manifest: Mutex<Option<(Instant, Manifest)>>,
+}
+
+impl ObjectStorePixelSource {
+    pub async fn new(config: ObjectStorePixelApiConfig) -> Result<Self> {
+        let store = match &config.store {
+            ObjectStoreConfig::Public { base_url } => ObjectStore::Public {
+                base_url: base_url.clone(),
+            },
+            ObjectStoreConfig::Gcs {
+                bucket_name,
+                signed_url_expiry_secs,
+            } => {
+                let client_config = ClientConfig::default()
+                    .with_auth()
+                    .await
+                    .context("Failed to create GCP GCS client config")?;
+                ObjectStore::Gcs {
+                    client: Client::new(client_config),
+                    bucket_name: bucket_name.clone(),
+                    signed_url_expiry: Duration::from_secs(*signed_url_expiry_secs),
+                }
+            },
+        };
+        let http_client = reqwest::Client::builder()
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Self {
+            config,
+            store,
+            http_client,
+            manifest: Mutex::new(None),
+        })
+    }
+
+    /// Read the manifest from the store, or from the cache if we read it recently.
+    /// If there isn't one yet, no canvases have been flushed.
+    async fn read_manifest(&self) -> Result<Manifest> {
+        let mut cached = self.manifest.lock().await;
+        let max_age = Duration::from_secs(self.config.manifest_cache_secs);
+        if let Some((read_at, manifest)) = cached.as_ref() {
+            if read_at.elapsed() < max_age {
+                return Ok(manifest.clone());
+            }
+        }
+
+        let url = self.store.get_url(MANIFEST_PATH).await?;
+        let response = self
+            .http_client
+            .get(url)
+            .send()
+            .await
+            .context("Failed to fetch manifest")?;
+        let manifest = if response.status() == reqwest::StatusCode::NOT_FOUND {
+            Manifest::default()
+        } else {
+            let data = response
+                .error_for_status()
+                .context("Failed to fetch manifest")?
+                .bytes()
+                .await
+                .context("Failed to read manifest")?;
+            serde_json::from_slice(&data).context("Failed to parse manifest")?
+        };
+        *cached = Some((Instant::now(), manifest.clone()));
+        Ok(manifest)
+    }
+
+    /// Get where the latest image of the canvas is in the store. Returns None if the
+    /// canvas isn't in the manifest.
+    async fn get_image_path(&self, canvas_address: &Address) -> Result<Option<String>> {
+        if !self.config.content_addressed {
+            return Ok(Some(get_overwritten_image_path(canvas_address)));
+        }
+        let manifest = self.read_manifest().await?;
+        Ok(manifest
+            .canvases
+            .get(&canvas_address.to_string())
+            .map(|entry| entry.path.clone()))
+    }
+
+    pub async fn get_image_response(&self, canvas_address: &Address) -> poem::Result<Response> {
+        let path = match self
+            .get_image_path(canvas_address)
+            .await
+            .map_err(internal_error)?
+        {
+            Some(path) => path,
+            None => return Err(not_found(canvas_address)),
+        };
+        let url = self.store.get_url(&path).await.map_err(internal_error)?;
+
+        match self.config.mode {
+            ObjectStorePixelApiMode::Redirect => Ok(Response::builder()
+                .status(StatusCode::FOUND)
+                .header(header::LOCATION, url)
+                .finish()),
+            ObjectStorePixelApiMode::Proxy => {
+                let response = self
+                    .http_client
+                    .get(url)
+                    .send()
+                    .await
+                    .with_context(|| format!("Failed to fetch {}", path))
+                    .map_err(bad_gateway)?;
+                if response.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Err(not_found(canvas_address));
+                }
+                let response = response
+                    .error_for_status()
+                    .with_context(|| format!("Failed to fetch {}", path))
+                    .map_err(bad_gateway)?;
+                // Pass on the caching headers the flusher set on the object.
+                let cache_control = response
+                    .headers()
+                    .get(reqwest::header::CACHE_CONTROL)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| value.to_string());
+                let png_data = response
+                    .bytes()
+                    .await
+                    .with_context(|| format!("Failed to read {}", path))
+                    .map_err(bad_gateway)?;
+                let mut builder = Response::builder().content_type("image/png");
+                if let Some(cache_control) = cache_control {
+                    builder = builder.header(header::CACHE_CONTROL, cache_control);
+                }
+                Ok(builder.body(png_data.to_vec()))
+            },
+        }
+    }
+}
+
+fn not_found(canvas_address: &Address) -> poem::Error {
+    poem::Error::from_string(
+        format!("No image for canvas {}", canvas_address),
+        StatusCode::NOT_FOUND,
+    )
+}
+
+fn internal_error(error: anyhow::Error) -> poem::Error {
+    poem::Error::from_string(format!("{:#}", error), StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn bad_gateway(error: anyhow::Error) -> poem::Error {
+    poem::Error::from_string(format!("{:#}", error), StatusCode::BAD_GATEWAY)
+}
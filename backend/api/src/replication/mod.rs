@@ -0,0 +1,253 @@
+//! Only the processor holds the canvases, so on its own the pixel API can't scale
+//! out. Instead, the processor (the primary) serves a feed of every change it makes
+//! to the canvases at `/v1/replication/feed`, and any number of replicas follow it,
+//! each keeping its own copy of the canvases in memory and serving the pixel API from
+//! that.
+//!
+//! The feed is newline delimited JSON. It starts with the current state of every
+//! canvas, then a message saying the snapshot is done, then every delta from when the
+//! replica connected onwards. The primary sends heartbeats with its latest version
+//! so replicas can tell how far behind they are, which they report at
+//! `/v1/replication/status`.
+
+mod replica;
+
+use anyhow::{Context, Result};
+use futures::stream;
+use pixel_storage::{MmapPixelStorage, PixelDelta, PixelStorageTrait};
+use poem::{
+    get, handler,
+    web::{Data, Json},
+    Body, EndpointExt, Response, Route,
+};
+use replica::ReplicaState;
+pub use replica::{ReplicaConfig, ReplicaFollower, ReplicaHealthCheck};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tracing::{info, warn};
+
+pub const BASE: &str = "/replication";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplicationFeedConfig {
+    /// How often we tell replicas our latest version when nothing has changed.
+    #[serde(default = "ReplicationFeedConfig::default_heartbeat_interval")]
+    pub heartbeat_interval: Duration,
+}
+
+impl ReplicationFeedConfig {
+    pub fn default_heartbeat_interval() -> Duration {
+        Duration::from_secs(5)
+    }
+}
+
+/// A line of the feed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplicationMessage {
+    /// The current state of a canvas, sent for every canvas when a replica connects.
+    /// The PNG is hex encoded.
+    Canvas {
+        canvas_address: String,
+        png: String,
+        version: Option<u64>,
+    },
+    /// Every canvas has been sent, everything after this is a delta or a heartbeat.
+    SnapshotDone {
+        latest_version: Option<u64>,
+    },
+    Delta {
+        delta: PixelDelta,
+    },
+    Heartbeat {
+        latest_version: Option<u64>,
+    },
+}
+
+impl ReplicationMessage {
+    fn to_line(&self) -> Result<Vec<u8>> {
+        let mut line = serde_json::to_vec(self).context("Failed to serialize message")?;
+        line.push(b'\n');
+        Ok(line)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicationRole {
+    Primary,
+    Replica,
+}
+
+/// What `/v1/replication/status` returns, so clients can tell how fresh the canvases
+/// served by this instance are.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplicationStatus {
+    pub role: ReplicationRole,
+    /// Whether the canvases here are a complete copy of those of the primary. This
+    /// is always true for the primary.
+    pub synced: bool,
+    /// The version of the last txn reflected in the canvases here.
+    pub latest_version: Option<u64>,
+    /// The latest version the primary has told us about. For the primary this is
+    /// the same as `latest_version`.
+    pub primary_latest_version: Option<u64>,
+    /// How many versions behind the primary we are, where known.
+    pub lag_versions: Option<u64>,
+    /// How long ago we last heard from the primary, for replicas.
+    pub secs_since_last_message: Option<u64>,
+}
+
+/// The version of the last txn reflected in any canvas.
+async fn get_latest_version(pixel_storage: &dyn PixelStorageTrait) -> Result<Option<u64>> {
+    Ok(pixel_storage
+        .get_canvas_versions()
+        .await?
+        .into_values()
+        .max())
+}
+
+/// Send the current state of every canvas, then every delta from when we subscribed,
+/// until the replica goes away or falls too far behind.
+async fn send_feed(
+    pixel_storage: Arc<MmapPixelStorage>,
+    config: ReplicationFeedConfig,
+    sender: mpsc::Sender<Result<Vec<u8>, std::io::Error>>,
+) -> Result<()> {
+    // Subscribe before reading the canvases, so we don't miss anything written
+    // while we read them.
+    let mut deltas = pixel_storage.subscribe_to_deltas();
+
+    let versions = pixel_storage.get_canvas_versions().await?;
+    let canvas_addresses = pixel_storage.get_canvas_addresses().await?;
+    for canvas_address in &canvas_addresses {
+        let png_data = pixel_storage.get_canvas_as_png(canvas_address).await?;
+        let message = ReplicationMessage::Canvas {
+            canvas_address: canvas_address.to_string(),
+            png: hex::encode(png_data),
+            version: versions.get(canvas_address).copied(),
+        };
+        if sender.send(Ok(message.to_line()?)).await.is_err() {
+            return Ok(());
+        }
+    }
+    let mut latest_version = versions.into_values().max();
+    let message = ReplicationMessage::SnapshotDone { latest_version };
+    if sender.send(Ok(message.to_line()?)).await.is_err() {
+        return Ok(());
+    }
+    info!(
+        "Sent snapshot of {} canvases to replica",
+        canvas_addresses.len()
+    );
+
+    let mut heartbeat = tokio::time::interval(config.heartbeat_interval);
+    loop {
+        let message = tokio::select! {
+            delta = deltas.recv() => match delta {
+                Ok(delta) => {
+                    latest_version = latest_version.max(delta.version());
+                    ReplicationMessage::Delta { delta }
+                },
+                // The replica will reconnect and start again from a fresh snapshot.
+                Err(RecvError::Lagged(num_missed)) => {
+                    anyhow::bail!("Replica fell {} deltas behind", num_missed)
+                },
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            _ = heartbeat.tick() => ReplicationMessage::Heartbeat { latest_version },
+        };
+        if sender.send(Ok(message.to_line()?)).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[handler]
+async fn feed(
+    pixel_storage: Data<&Arc<MmapPixelStorage>>,
+    config: Data<&ReplicationFeedConfig>,
+) -> Response {
+    let (sender, receiver) = mpsc::channel(16);
+    let pixel_storage = pixel_storage.0.clone();
+    let config = config.0.clone();
+    tokio::spawn(async move {
+        // Dropping the sender ends the response, so the replica knows to reconnect.
+        if let Err(error) = send_feed(pixel_storage, config, sender).await {
+            warn!("Replication feed ended: {:#}", error);
+        }
+    });
+    let stream = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|line| (line, receiver))
+    });
+    Response::builder()
+        .content_type("application/x-ndjson")
+        .body(Body::from_bytes_stream(stream))
+}
+
+#[handler]
+async fn primary_status(
+    pixel_storage: Data<&Arc<MmapPixelStorage>>,
+) -> poem::Result<Json<ReplicationStatus>> {
+    let latest_version = get_latest_version(pixel_storage.0.as_ref()).await?;
+    Ok(Json(ReplicationStatus {
+        role: ReplicationRole::Primary,
+        synced: true,
+        latest_version,
+        primary_latest_version: latest_version,
+        lag_versions: latest_version.map(|_| 0),
+        secs_since_last_message: None,
+    }))
+}
+
+#[handler]
+async fn replica_status(state: Data<&Arc<ReplicaState>>) -> Json<ReplicationStatus> {
+    Json(state.get_status())
+}
+
+enum ReplicationSource {
+    Primary {
+        pixel_storage: Arc<MmapPixelStorage>,
+        config: ReplicationFeedConfig,
+    },
+    Replica(Arc<ReplicaState>),
+}
+
+pub struct ReplicationApi {
+    source: ReplicationSource,
+}
+
+impl ReplicationApi {
+    /// Serve the feed of changes to the given storage, for replicas to follow.
+    pub fn primary(pixel_storage: Arc<MmapPixelStorage>, config: ReplicationFeedConfig) -> Self {
+        Self {
+            source: ReplicationSource::Primary {
+                pixel_storage,
+                config,
+            },
+        }
+    }
+
+    /// Serve the status of the given replica.
+    pub fn replica(follower: &ReplicaFollower) -> Self {
+        Self {
+            source: ReplicationSource::Replica(follower.get_state()),
+        }
+    }
+
+    pub fn get_route(self) -> Result<Route> {
+        Ok(match self.source {
+            ReplicationSource::Primary {
+                pixel_storage,
+                config,
+            } => Route::new()
+                .at("/feed", get(feed).data(pixel_storage.clone()).data(config))
+                .at("/status", get(primary_status).data(pixel_storage)),
+            ReplicationSource::Replica(state) => {
+                Route::new().at("/status", get(replica_status).data(state))
+            },
+        })
+    }
+}
@@ -0,0 +1,278 @@
+use super::{ReplicationMessage, ReplicationRole, ReplicationStatus};
+use crate::{HealthCheck, HealthCheckKind};
+use anyhow::{bail, Context, Result};
+use aptos_move_graphql_scalars::Address;
+use pixel_storage::MemoryPixelStorage;
+use serde::{Deserialize, Serialize};
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{info, warn};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplicaConfig {
+    /// The base URL of the API of the primary, e.g. `http://graffio-processor:7645`.
+    /// Its `replication_feed_config` must be set.
+    pub primary_url: String,
+
+    /// How long to wait before reconnecting after losing the primary.
+    #[serde(default = "ReplicaConfig::default_reconnect_delay")]
+    pub reconnect_delay: Duration,
+
+    /// If we don't hear from the primary for this long we reconnect. This should be
+    /// a few times its heartbeat interval. We're also not ready while this is the case.
+    #[serde(default = "ReplicaConfig::default_timeout")]
+    pub timeout: Duration,
+}
+
+impl ReplicaConfig {
+    pub fn default_reconnect_delay() -> Duration {
+        Duration::from_secs(5)
+    }
+
+    pub fn default_timeout() -> Duration {
+        Duration::from_secs(30)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReplicaStateInner {
+    synced: bool,
+    latest_version: Option<u64>,
+    primary_latest_version: Option<u64>,
+    last_message_at: Option<Instant>,
+}
+
+/// How up to date the replica is, shared between the follower, the status endpoint
+/// and the health check.
+#[derive(Debug, Default)]
+pub struct ReplicaState {
+    inner: Mutex<ReplicaStateInner>,
+}
+
+impl ReplicaState {
+    pub fn get_status(&self) -> ReplicationStatus {
+        let inner = self.inner.lock().unwrap();
+        let lag_versions = match (inner.latest_version, inner.primary_latest_version) {
+            (Some(latest), Some(primary_latest)) => Some(primary_latest.saturating_sub(latest)),
+            _ => None,
+        };
+        ReplicationStatus {
+            role: ReplicationRole::Replica,
+            synced: inner.synced,
+            latest_version: inner.latest_version,
+            primary_latest_version: inner.primary_latest_version,
+            lag_versions,
+            secs_since_last_message: inner
+                .last_message_at
+                .map(|last_message_at| last_message_at.elapsed().as_secs()),
+        }
+    }
+
+    fn record_message(&self, latest_version: Option<u64>, primary_latest_version: Option<u64>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.last_message_at = Some(Instant::now());
+        inner.latest_version = inner.latest_version.max(latest_version);
+        inner.primary_latest_version = inner.primary_latest_version.max(primary_latest_version);
+    }
+
+    fn set_synced(&self, latest_version: Option<u64>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.synced = true;
+        // A new snapshot replaces everything, so this can go backwards, e.g. if the
+        // primary was reset.
+        inner.latest_version = latest_version;
+        inner.primary_latest_version = latest_version;
+    }
+}
+
+/// Follows the feed of the primary, keeping a copy of its canvases in memory.
+pub struct ReplicaFollower {
+    config: ReplicaConfig,
+    pixel_storage: Arc<MemoryPixelStorage>,
+    state: Arc<ReplicaState>,
+    http_client: reqwest::Client,
+}
+
+impl ReplicaFollower {
+    pub fn new(config: ReplicaConfig) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .connect_timeout(config.timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Self {
+            config,
+            pixel_storage: Arc::new(MemoryPixelStorage::new()),
+            state: Arc::new(ReplicaState::default()),
+            http_client,
+        })
+    }
+
+    /// The canvases, for the pixel API to serve. These are empty until the first
+    /// snapshot from the primary arrives.
+    pub fn get_pixel_storage(&self) -> Arc<MemoryPixelStorage> {
+        self.pixel_storage.clone()
+    }
+
+    pub fn health_check(&self) -> ReplicaHealthCheck {
+        ReplicaHealthCheck {
+            state: self.state.clone(),
+            timeout: self.config.timeout,
+        }
+    }
+
+    pub(crate) fn get_state(&self) -> Arc<ReplicaState> {
+        self.state.clone()
+    }
+
+    /// Consume the follower to create a task that follows the primary, reconnecting
+    /// whenever the feed ends, until `shutdown` is set.
+    pub fn run(self, mut shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.follow(&mut shutdown).await {
+                    Ok(()) => {
+                        info!("Replica finished for shutdown");
+                        return;
+                    },
+                    Err(error) => warn!(
+                        "Lost the primary, reconnecting in {:?}: {:#}",
+                        self.config.reconnect_delay, error
+                    ),
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(self.config.reconnect_delay) => {},
+                    _ = shutdown.changed() => {
+                        info!("Replica finished for shutdown");
+                        return;
+                    },
+                }
+            }
+        })
+    }
+
+    /// Connect to the primary and apply everything it sends. This only returns Ok
+    /// when `shutdown` is set. We keep serving the canvases we have until the new
+    /// snapshot is complete, then swap them out in one go.
+    async fn follow(&self, shutdown: &mut watch::Receiver<bool>) -> Result<()> {
+        let url = format!(
+            "{}/v1/replication/feed",
+            self.config.primary_url.trim_end_matches('/')
+        );
+        info!("Connecting to primary at {}", url);
+        let mut response = self
+            .http_client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to connect to primary")?
+            .error_for_status()
+            .context("Primary rejected the request for its feed")?;
+
+        let mut snapshot = Some(MemoryPixelStorage::new());
+        let mut buffer = vec![];
+        loop {
+            let chunk = tokio::select! {
+                chunk = tokio::time::timeout(self.config.timeout, response.chunk()) => chunk,
+                _ = shutdown.changed() => return Ok(()),
+            };
+            let chunk = chunk
+                .with_context(|| {
+                    format!("Heard nothing from primary for {:?}", self.config.timeout)
+                })?
+                .context("Failed to read from primary")?
+                .context("Primary ended the feed")?;
+            buffer.extend_from_slice(&chunk);
+            while let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=position).collect();
+                let message: ReplicationMessage =
+                    serde_json::from_slice(&line).context("Failed to parse message")?;
+                self.handle_message(message, &mut snapshot).await?;
+            }
+        }
+    }
+
+    async fn handle_message(
+        &self,
+        message: ReplicationMessage,
+        snapshot: &mut Option<MemoryPixelStorage>,
+    ) -> Result<()> {
+        match message {
+            ReplicationMessage::Canvas {
+                canvas_address,
+                png,
+                version,
+            } => {
+                let snapshot = snapshot
+                    .as_ref()
+                    .context("Primary sent a canvas after the snapshot")?;
+                let canvas_address = Address::from_str(&canvas_address)
+                    .with_context(|| format!("Invalid canvas address {}", canvas_address))?;
+                let png_data = hex::decode(png).context("Invalid image")?;
+                snapshot
+                    .insert_canvas_from_png(canvas_address, &png_data, version)
+                    .await?;
+                self.state.record_message(None, None);
+            },
+            ReplicationMessage::SnapshotDone { latest_version } => {
+                let snapshot = snapshot.take().context("Primary sent a second snapshot")?;
+                self.pixel_storage.replace_with(snapshot).await;
+                self.state.set_synced(latest_version);
+                self.state.record_message(None, None);
+                info!("Synced with primary at version {:?}", latest_version);
+            },
+            ReplicationMessage::Delta { delta } => {
+                if snapshot.is_some() {
+                    bail!("Primary sent a delta before the snapshot was done");
+                }
+                delta.apply(self.pixel_storage.as_ref()).await?;
+                self.state.record_message(delta.version(), delta.version());
+            },
+            ReplicationMessage::Heartbeat { latest_version } => {
+                self.state.record_message(None, latest_version);
+            },
+        }
+        Ok(())
+    }
+}
+
+/// We're not ready until we've received a complete snapshot from the primary, or if
+/// we haven't heard from it recently.
+#[derive(Debug)]
+pub struct ReplicaHealthCheck {
+    state: Arc<ReplicaState>,
+    timeout: Duration,
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for ReplicaHealthCheck {
+    fn name(&self) -> String {
+        "replica".to_string()
+    }
+
+    fn kind(&self) -> HealthCheckKind {
+        HealthCheckKind::Readiness
+    }
+
+    async fn check(&self) -> Result<String> {
+        let status = self.state.get_status();
+        if !status.synced {
+            bail!("Waiting for a snapshot from the primary");
+        }
+        let secs_since_last_message = status.secs_since_last_message.unwrap_or_default();
+        if secs_since_last_message > self.timeout.as_secs() {
+            bail!(
+                "Heard nothing from the primary for {}s",
+                secs_since_last_message
+            );
+        }
+        Ok(format!(
+            "At version {:?}, {:?} versions behind the primary",
+            status.latest_version, status.lag_versions
+        ))
+    }
+}
@@ -2,6 +2,15 @@
 
 pub mod prelude;
 
+pub mod canvas;
+pub mod canvas_account;
+pub mod canvas_snapshot;
+pub mod canvas_table_handle;
 pub mod chain_id;
+pub mod collection;
+pub mod contract_layout;
+pub mod dead_letter_transaction;
 pub mod last_processed_version;
+pub mod paint_activity;
+pub mod paint_balance;
 pub mod pixel_attribution;
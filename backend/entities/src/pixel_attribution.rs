@@ -11,6 +11,7 @@ pub struct Model {
     pub canvas_address: String,
     pub artist_address: String,
     pub drawn_at_secs: i64,
+    pub contract_address: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,6 +1,12 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.2
 
 pub use super::{
-    chain_id::Entity as ChainId, last_processed_version::Entity as LastProcessedVersion,
+    canvas::Entity as Canvas, canvas_account::Entity as CanvasAccount,
+    canvas_snapshot::Entity as CanvasSnapshot, canvas_table_handle::Entity as CanvasTableHandle,
+    chain_id::Entity as ChainId, collection::Entity as Collection,
+    contract_layout::Entity as ContractLayout,
+    dead_letter_transaction::Entity as DeadLetterTransaction,
+    last_processed_version::Entity as LastProcessedVersion,
+    paint_activity::Entity as PaintActivity, paint_balance::Entity as PaintBalance,
     pixel_attribution::Entity as PixelAttribution,
 };
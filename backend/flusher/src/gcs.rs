@@ -1,29 +1,59 @@
-use super::FlusherTrait;
+use super::{
+    flush_canvases,
+    manifest::{
+        get_image_path, get_overwritten_image_path, hash_image, Manifest, ManifestEntry,
+        MANIFEST_PATH,
+    },
+    FlusherTrait, RetryConfig,
+};
 use anyhow::{Context, Result};
 use aptos_move_graphql_scalars::Address;
 use async_trait::async_trait;
 use google_cloud_storage::{
     client::{Client, ClientConfig},
-    http::objects::{
-        upload::{UploadObjectRequest, UploadType},
-        Object,
+    http::{
+        objects::{
+            download::Range,
+            get::GetObjectRequest,
+            upload::{UploadObjectRequest, UploadType},
+            Object,
+        },
+        Error as GcsError,
     },
 };
 use pixel_storage::PixelStorageTrait;
 use serde::{Deserialize, Serialize};
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+/// Content addressed images never change, so they can be cached forever.
+pub(crate) const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GcsFlusherConfig {
     bucket_name: String,
     #[serde(default = "GcsFlusherConfig::default_flush_interval")]
     flush_interval: Duration,
+    #[serde(default)]
+    retry_config: RetryConfig,
+    /// If set, rather than overwriting `images/<address>.png` with caching disabled,
+    /// each new image is uploaded to `images/<address>/<hash>.png` with a long
+    /// max-age, and `images/manifest.json` says which is the latest for each canvas.
+    #[serde(default)]
+    content_addressed: bool,
+    /// How long the manifest can be cached for, if `content_addressed` is set.
+    #[serde(default = "GcsFlusherConfig::default_manifest_max_age_secs")]
+    manifest_max_age_secs: u64,
 }
 
 impl GcsFlusherConfig {
     pub fn default_flush_interval() -> Duration {
         Duration::from_millis(1200)
     }
+
+    pub fn default_manifest_max_age_secs() -> u64 {
+        10
+    }
 }
 
 /// This assumes that we're running inside GCP. If we're not then this won't work
@@ -33,6 +63,9 @@ pub struct GcsFlusher {
     config: GcsFlusherConfig,
     pixel_storage: Arc<dyn PixelStorageTrait>,
     client: Client,
+    /// The manifest as we last uploaded it, if `content_addressed` is set. This is
+    /// read from GCS on the first flush.
+    manifest: Arc<Mutex<Option<Manifest>>>,
 }
 
 impl GcsFlusher {
@@ -40,15 +73,12 @@ impl GcsFlusher {
         config: GcsFlusherConfig,
         pixel_storage: Arc<dyn PixelStorageTrait>,
     ) -> Result<Self> {
-        let client_config = ClientConfig::default()
-            .with_auth()
-            .await
-            .context("Failed to create GCP GCS client config")?;
-        let client = Client::new(client_config);
+        let client = create_client().await?;
         Ok(Self {
             config,
             pixel_storage,
             client,
+            manifest: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -57,47 +87,208 @@ impl GcsFlusher {
         canvas_address: Address,
         png_data: Vec<u8>,
     ) -> Result<()> {
-        let extension = "png";
-
-        let filename = format!("images/{}.{}", canvas_address, extension);
-        // We can't use uploadType::Simple because it doesn't allow us to set the cache
-        // control parameters.
-        let upload_type = UploadType::Multipart(Box::new(Object {
-            name: filename.clone(),
-            content_type: format!("image/{}", extension).into(),
-            size: png_data.len() as i64,
-            // Don't let the content be cached anywhere.
-            cache_control: Some("no-cache, no-store, max-age=0".to_string()),
-            ..Default::default()
-        }));
-        self.client
-            .upload_object(
-                &UploadObjectRequest {
+        let filename = get_overwritten_image_path(&canvas_address);
+        // Don't let the content be cached anywhere.
+        self.upload_object(
+            filename,
+            "image/png",
+            "no-cache, no-store, max-age=0",
+            png_data,
+        )
+        .await
+        .with_context(|| format!("Failed to image for address {} to GCS", canvas_address))
+    }
+
+    async fn upload_object(
+        &self,
+        name: String,
+        content_type: &str,
+        cache_control: &str,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        upload_object(
+            &self.client,
+            &self.config.bucket_name,
+            name,
+            content_type,
+            cache_control,
+            data,
+        )
+        .await
+    }
+
+    /// Read the manifest from GCS. If there isn't one yet we start a new one.
+    async fn read_manifest(&self) -> Result<Manifest> {
+        let result = self
+            .client
+            .download_object(
+                &GetObjectRequest {
                     bucket: self.config.bucket_name.clone(),
+                    object: MANIFEST_PATH.to_string(),
                     ..Default::default()
                 },
-                png_data.clone(),
-                &upload_type,
+                &Range::default(),
             )
-            .await
-            .with_context(|| format!("Failed to image for address {} to GCS", canvas_address))?;
+            .await;
+        match result {
+            Ok(data) => serde_json::from_slice(&data).context("Failed to parse manifest"),
+            Err(GcsError::Response(error)) if error.code == 404 => Ok(Manifest::default()),
+            Err(error) => Err(error).context("Failed to read manifest from GCS"),
+        }
+    }
+
+    async fn write_manifest(&self, manifest: &Manifest) -> Result<()> {
+        let data = serde_json::to_vec(manifest).context("Failed to serialize manifest")?;
+        self.upload_object(
+            MANIFEST_PATH.to_string(),
+            "application/json",
+            &format!("public, max-age={}", self.config.manifest_max_age_secs),
+            data,
+        )
+        .await
+    }
+
+    /// Upload the images that changed since the last flush under content addressed
+    /// names, then update the manifest to point to them. The manifest is updated
+    /// for the images that uploaded successfully even if others failed.
+    async fn flush_content_addressed(&self) -> Result<()> {
+        // Each image is read along with its version, so the version in the manifest
+        // is exactly that of the last txn reflected in the image.
+        let mut versions = HashMap::new();
+        let mut pngs = HashMap::new();
+        for (canvas_address, png) in self
+            .pixel_storage
+            .get_canvases_as_pngs_with_versions()
+            .await?
+        {
+            if let Some(version) = png.version {
+                versions.insert(canvas_address, version);
+            }
+            pngs.insert(canvas_address, png.png_data);
+        }
+        let mut manifest = self.manifest.lock().await;
+        if manifest.is_none() {
+            *manifest = Some(self.read_manifest().await?);
+        }
+        let manifest = manifest.as_mut().context("Manifest wasn't read")?;
+
+        // Only upload the images that changed since they were last uploaded.
+        let mut hashes = HashMap::new();
+        let mut changed_pngs = HashMap::new();
+        for (canvas_address, png_data) in pngs {
+            let hash = hash_image(&png_data);
+            let unchanged = manifest
+                .canvases
+                .get(&canvas_address.to_string())
+                .map_or(false, |entry| entry.hash == hash);
+            if !unchanged {
+                changed_pngs.insert(canvas_address, png_data);
+            }
+            hashes.insert(canvas_address, hash);
+        }
+        if changed_pngs.is_empty() {
+            return Ok(());
+        }
+
+        let uploaded = std::sync::Mutex::new(vec![]);
+        let result = flush_canvases(self.name(), changed_pngs, |canvas_address, png_data| {
+            let path = get_image_path(&canvas_address, &hashes[&canvas_address]);
+            let uploaded = &uploaded;
+            async move {
+                self.upload_object(path, "image/png", IMMUTABLE_CACHE_CONTROL, png_data)
+                    .await?;
+                uploaded.lock().unwrap().push(canvas_address);
+                Ok(())
+            }
+        })
+        .await;
 
-        Ok(())
+        // Only replace the manifest we have in memory once the new one is uploaded,
+        // otherwise we'd think the images it points to are already published.
+        let uploaded = uploaded.into_inner().unwrap();
+        if !uploaded.is_empty() {
+            let mut new_manifest = manifest.clone();
+            for canvas_address in uploaded {
+                let hash = hashes[&canvas_address].clone();
+                new_manifest
+                    .canvases
+                    .insert(canvas_address.to_string(), ManifestEntry {
+                        path: get_image_path(&canvas_address, &hash),
+                        hash,
+                        version: versions.get(&canvas_address).copied(),
+                    });
+            }
+            self.write_manifest(&new_manifest).await?;
+            *manifest = new_manifest;
+        }
+
+        result
     }
 }
 
 #[async_trait]
 impl FlusherTrait for GcsFlusher {
+    fn name(&self) -> &'static str {
+        "gcs"
+    }
+
     fn get_interval(&self) -> Duration {
         self.config.flush_interval
     }
 
+    fn get_retry_config(&self) -> &RetryConfig {
+        &self.config.retry_config
+    }
+
     async fn flush(&self) -> Result<()> {
-        let pngs = self.pixel_storage.get_canvases_as_pngs().await?;
-        for (canvas_address, png_data) in pngs {
-            // This could be optimized by only writing images that have changed.
-            self.write_image_to_gcs(canvas_address, png_data).await?;
+        if self.config.content_addressed {
+            return self.flush_content_addressed().await;
         }
-        Ok(())
+        let pngs = self.pixel_storage.get_canvases_as_pngs().await?;
+        // This could be optimized by only writing images that have changed.
+        flush_canvases(self.name(), pngs, |canvas_address, png_data| {
+            self.write_image_to_gcs(canvas_address, png_data)
+        })
+        .await
     }
 }
+
+/// This uses ClientConfig::default().with_auth(), so it only works inside GCP.
+pub(crate) async fn create_client() -> Result<Client> {
+    let client_config = ClientConfig::default()
+        .with_auth()
+        .await
+        .context("Failed to create GCP GCS client config")?;
+    Ok(Client::new(client_config))
+}
+
+pub(crate) async fn upload_object(
+    client: &Client,
+    bucket_name: &str,
+    name: String,
+    content_type: &str,
+    cache_control: &str,
+    data: Vec<u8>,
+) -> Result<()> {
+    // We can't use uploadType::Simple because it doesn't allow us to set the cache
+    // control parameters.
+    let upload_type = UploadType::Multipart(Box::new(Object {
+        name: name.clone(),
+        content_type: Some(content_type.to_string()),
+        size: data.len() as i64,
+        cache_control: Some(cache_control.to_string()),
+        ..Default::default()
+    }));
+    client
+        .upload_object(
+            &UploadObjectRequest {
+                bucket: bucket_name.to_string(),
+                ..Default::default()
+            },
+            data,
+            &upload_type,
+        )
+        .await
+        .with_context(|| format!("Failed to upload {} to GCS", name))?;
+    Ok(())
+}
@@ -1,52 +1,169 @@
 mod gcs;
 mod local;
+mod manifest;
+mod metrics;
+mod retry;
+mod snapshot;
 
 use anyhow::Result;
+use aptos_move_graphql_scalars::Address;
 pub use gcs::{GcsFlusher, GcsFlusherConfig};
 pub use local::{LocalFlusher, LocalFlusherConfig};
-use std::time::Duration;
-use tokio::task::JoinHandle;
-use tracing::{debug, error, info};
+pub use manifest::{
+    get_image_path, get_overwritten_image_path, Manifest, ManifestEntry, MANIFEST_PATH,
+};
+pub use metrics::{get_degraded_flushers, get_last_successful_flush_timestamps};
+use metrics::{
+    CONSECUTIVE_FLUSH_FAILURES, FLUSHER_DEGRADED, FLUSH_CANVAS_FAILURE_COUNT, FLUSH_FAILURE_COUNT,
+    FLUSH_LATENCY, LAST_SUCCESSFUL_FLUSH_TIMESTAMP_SECS,
+};
+pub use retry::RetryConfig;
+pub use snapshot::{
+    SnapshotMetadata, SnapshotScheduler, SnapshotSchedulerConfig, SnapshotStorageConfig,
+};
+use std::{
+    collections::HashMap,
+    future::Future,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{debug, error, info, warn};
 
 /// A flusher is something that can periodically flush local data to remote storage.
 #[async_trait::async_trait]
 pub trait FlusherTrait: Send + Sync + 'static {
+    /// The name of the flusher, used to label its metrics.
+    fn name(&self) -> &'static str;
+
     /// Flush the data just once.
     async fn flush(&self) -> Result<()>;
 
     /// Get the interval at which we should flush.
     fn get_interval(&self) -> Duration;
 
+    /// Get how we should retry after a failed flush.
+    fn get_retry_config(&self) -> &RetryConfig;
+
     /// Consume the flusher to create a task in which we periodically flush the data.
-    /// This returns a handle for the task. This task should never end.
-    fn run(self) -> JoinHandle<()>
+    /// This returns a handle for the task. When a flush fails we retry with backoff
+    /// for as long as it takes. This task only ends when `shutdown` is set, after
+    /// flushing one last time.
+    fn run(self, mut shutdown: watch::Receiver<bool>) -> JoinHandle<()>
     where
         Self: Sized,
     {
         tokio::spawn(async move {
-            let mut num_consecutive_failures = 0;
+            let retry_config = self.get_retry_config().clone();
+            let mut num_consecutive_failures: u32 = 0;
             loop {
                 debug!("Flushing data");
-                match self.flush().await {
-                    Ok(_) => {
-                        info!("Flushed data succesfully");
-                    },
-                    Err(e) => {
-                        error!("Failed to flush data: {:?}", e);
-                        num_consecutive_failures += 1;
-                        if num_consecutive_failures >= 5 {
-                            error!(
-                                "Failed to flush data too many times ({}), bailing out!",
-                                num_consecutive_failures
-                            );
-                            return;
+                let delay = if flush_and_record(&self).await {
+                    info!("Flushed data succesfully");
+                    if num_consecutive_failures >= retry_config.degraded_after_failures {
+                        info!(
+                            "Flusher {} recovered after {} consecutive failures",
+                            self.name(),
+                            num_consecutive_failures
+                        );
+                    }
+                    num_consecutive_failures = 0;
+                    self.get_interval()
+                } else {
+                    num_consecutive_failures = num_consecutive_failures.saturating_add(1);
+                    let backoff = retry_config.get_backoff(num_consecutive_failures);
+                    if num_consecutive_failures >= retry_config.degraded_after_failures {
+                        warn!(
+                            "Flusher {} is degraded after {} failures in a row, retrying in {:?}",
+                            self.name(),
+                            num_consecutive_failures,
+                            backoff
+                        );
+                    }
+                    backoff
+                };
+                CONSECUTIVE_FLUSH_FAILURES
+                    .with_label_values(&[self.name()])
+                    .set(num_consecutive_failures as i64);
+                let degraded = num_consecutive_failures >= retry_config.degraded_after_failures;
+                FLUSHER_DEGRADED
+                    .with_label_values(&[self.name()])
+                    .set(degraded as i64);
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {},
+                    // This also fires if the sender is dropped, in which case we're
+                    // shutting down anyway.
+                    _ = shutdown.changed() => {
+                        info!("Shutting down, flushing data one last time");
+                        if flush_and_record(&self).await {
+                            info!("Final flush succeeded");
                         }
+                        return;
                     },
                 }
-                tokio::time::sleep(self.get_interval()).await;
             }
         })
-        // This ^ task is never meant to end. If it does, we should move past the
-        // select_all we use on the tasks and ultimately shut down.
+        // This ^ task is only meant to end when we shut down. If it ends otherwise,
+        // we should move past the select_all we use on the tasks and ultimately shut
+        // down.
+    }
+}
+
+/// Flush each canvas with `flush_canvas`. If one fails we carry on with the rest,
+/// so one bad canvas doesn't stop the others from being flushed, and then return an
+/// error listing the canvases that failed.
+async fn flush_canvases<F, Fut>(
+    flusher_name: &'static str,
+    pngs: HashMap<Address, Vec<u8>>,
+    flush_canvas: F,
+) -> Result<()>
+where
+    F: Fn(Address, Vec<u8>) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let num_canvases = pngs.len();
+    let mut failed = vec![];
+    for (canvas_address, png_data) in pngs {
+        if let Err(e) = flush_canvas(canvas_address, png_data).await {
+            error!("Failed to flush canvas {}: {:#}", canvas_address, e);
+            FLUSH_CANVAS_FAILURE_COUNT
+                .with_label_values(&[flusher_name])
+                .inc();
+            failed.push(canvas_address.to_string());
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "Failed to flush {} of {} canvases: {}",
+            failed.len(),
+            num_canvases,
+            failed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Flush once, recording metrics for it. Returns whether the flush succeeded.
+async fn flush_and_record<F: FlusherTrait>(flusher: &F) -> bool {
+    let timer = FLUSH_LATENCY
+        .with_label_values(&[flusher.name()])
+        .start_timer();
+    let result = flusher.flush().await;
+    timer.observe_duration();
+    match result {
+        Ok(_) => {
+            if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+                LAST_SUCCESSFUL_FLUSH_TIMESTAMP_SECS
+                    .with_label_values(&[flusher.name()])
+                    .set(now.as_secs_f64());
+            }
+            true
+        },
+        Err(e) => {
+            error!("Failed to flush data: {:?}", e);
+            FLUSH_FAILURE_COUNT
+                .with_label_values(&[flusher.name()])
+                .inc();
+            false
+        },
     }
 }
@@ -1,4 +1,4 @@
-use super::FlusherTrait;
+use super::{flush_canvases, FlusherTrait, RetryConfig};
 use anyhow::{Context, Result};
 use aptos_move_graphql_scalars::Address;
 use async_trait::async_trait;
@@ -11,6 +11,8 @@ pub struct LocalFlusherConfig {
     flush_dir: PathBuf,
     #[serde(default = "LocalFlusherConfig::default_flush_interval")]
     flush_interval: Duration,
+    #[serde(default)]
+    retry_config: RetryConfig,
 }
 
 impl LocalFlusherConfig {
@@ -53,15 +55,23 @@ impl LocalFlusher {
 
 #[async_trait]
 impl FlusherTrait for LocalFlusher {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
     fn get_interval(&self) -> Duration {
         self.config.flush_interval
     }
 
+    fn get_retry_config(&self) -> &RetryConfig {
+        &self.config.retry_config
+    }
+
     async fn flush(&self) -> Result<()> {
         let pngs = self.pixel_storage.get_canvases_as_pngs().await?;
-        for (canvas_address, png_data) in pngs {
-            self.write_image(canvas_address, png_data).await?;
-        }
-        Ok(())
+        flush_canvases(self.name(), pngs, |canvas_address, png_data| {
+            self.write_image(canvas_address, png_data)
+        })
+        .await
     }
 }
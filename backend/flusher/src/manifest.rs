@@ -0,0 +1,45 @@
+//! When images are uploaded under content addressed names, clients can't know the
+//! name of the latest image for a canvas, so we also upload a manifest pointing to
+//! it. Images never change once uploaded so they can be cached forever, only the
+//! manifest needs a short TTL.
+
+use aptos_move_graphql_scalars::Address;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Where the manifest lives in the bucket.
+pub const MANIFEST_PATH: &str = "images/manifest.json";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    /// Keyed by canvas address.
+    pub canvases: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    /// The hex encoded SHA-256 hash of the image.
+    pub hash: String,
+    /// Where the image lives in the bucket.
+    pub path: String,
+    /// The version of the last txn reflected in the image. This is only unset if the
+    /// canvas changed without the processor writing to it since startup, e.g. if it
+    /// was rebuilt.
+    pub version: Option<u64>,
+}
+
+pub fn hash_image(png_data: &[u8]) -> String {
+    hex::encode(Sha256::digest(png_data))
+}
+
+/// Where the image of a canvas lives when images aren't content addressed. It is
+/// overwritten on every flush.
+pub fn get_overwritten_image_path(canvas_address: &Address) -> String {
+    format!("images/{}.png", canvas_address)
+}
+
+/// Where a content addressed image of a canvas lives.
+pub fn get_image_path(canvas_address: &Address, hash: &str) -> String {
+    format!("images/{}/{}.png", canvas_address, hash)
+}
@@ -0,0 +1,100 @@
+use once_cell::sync::Lazy;
+use prometheus::{
+    core::Collector, register_gauge_vec, register_histogram_vec, register_int_counter_vec,
+    register_int_gauge_vec, GaugeVec, HistogramVec, IntCounterVec, IntGaugeVec,
+};
+use std::collections::HashMap;
+
+pub static FLUSH_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "flusher_flush_latency_secs",
+        "Time taken to flush all the canvases, including failed flushes",
+        &["flusher"]
+    )
+    .unwrap()
+});
+
+pub static FLUSH_FAILURE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "flusher_flush_failure_count",
+        "Number of flushes that failed",
+        &["flusher"]
+    )
+    .unwrap()
+});
+
+pub static FLUSH_CANVAS_FAILURE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "flusher_flush_canvas_failure_count",
+        "Number of times flushing a single canvas failed",
+        &["flusher"]
+    )
+    .unwrap()
+});
+
+pub static CONSECUTIVE_FLUSH_FAILURES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "flusher_consecutive_flush_failures",
+        "Number of flushes that have failed in a row, 0 after a successful flush",
+        &["flusher"]
+    )
+    .unwrap()
+});
+
+pub static FLUSHER_DEGRADED: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "flusher_degraded",
+        "1 if the flusher has failed too many times in a row, 0 otherwise",
+        &["flusher"]
+    )
+    .unwrap()
+});
+
+pub static LAST_SUCCESSFUL_FLUSH_TIMESTAMP_SECS: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        "flusher_last_successful_flush_timestamp_secs",
+        "Unix timestamp of the last successful flush",
+        &["flusher"]
+    )
+    .unwrap()
+});
+
+/// When each flusher last flushed successfully since startup, as a unix timestamp,
+/// keyed by flusher name.
+pub fn get_last_successful_flush_timestamps() -> HashMap<String, f64> {
+    let mut timestamps = HashMap::new();
+    for metric_family in LAST_SUCCESSFUL_FLUSH_TIMESTAMP_SECS.collect() {
+        for metric in metric_family.get_metric() {
+            let flusher = metric
+                .get_label()
+                .iter()
+                .find(|label| label.get_name() == "flusher")
+                .map(|label| label.get_value().to_string());
+            if let Some(flusher) = flusher {
+                timestamps.insert(flusher, metric.get_gauge().get_value());
+            }
+        }
+    }
+    timestamps
+}
+
+/// The names of the flushers that are currently degraded, i.e. have failed too many
+/// times in a row.
+pub fn get_degraded_flushers() -> Vec<String> {
+    let mut flushers = vec![];
+    for metric_family in FLUSHER_DEGRADED.collect() {
+        for metric in metric_family.get_metric() {
+            if metric.get_gauge().get_value() < 1.0 {
+                continue;
+            }
+            let flusher = metric
+                .get_label()
+                .iter()
+                .find(|label| label.get_name() == "flusher")
+                .map(|label| label.get_value().to_string());
+            flushers.extend(flusher);
+        }
+    }
+    flushers.sort();
+    flushers
+}
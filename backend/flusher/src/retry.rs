@@ -0,0 +1,83 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How a flusher retries after a failed flush. Rather than waiting for the usual
+/// flush interval we back off exponentially, with jitter, until a flush succeeds.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    /// How long to wait after the first failure.
+    #[serde(default = "RetryConfig::default_initial_backoff")]
+    pub initial_backoff: Duration,
+
+    /// The backoff is multiplied by this after each consecutive failure.
+    #[serde(default = "RetryConfig::default_multiplier")]
+    pub multiplier: f64,
+
+    /// The backoff never goes above this.
+    #[serde(default = "RetryConfig::default_max_backoff")]
+    pub max_backoff: Duration,
+
+    /// Each backoff is randomly adjusted by up to this fraction of itself in either
+    /// direction, so flushers that failed at the same time don't retry in lockstep.
+    #[serde(default = "RetryConfig::default_jitter")]
+    pub jitter: f64,
+
+    /// After this many consecutive failures the flusher is considered degraded,
+    /// which is reported in the metrics and fails the readiness check. It keeps
+    /// retrying regardless.
+    #[serde(default = "RetryConfig::default_degraded_after_failures")]
+    pub degraded_after_failures: u32,
+}
+
+impl RetryConfig {
+    pub fn default_initial_backoff() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    pub fn default_multiplier() -> f64 {
+        2.0
+    }
+
+    pub fn default_max_backoff() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    pub fn default_jitter() -> f64 {
+        0.2
+    }
+
+    pub fn default_degraded_after_failures() -> u32 {
+        5
+    }
+
+    /// How long to wait before retrying after the given number of consecutive
+    /// failures, which must be at least 1.
+    pub fn get_backoff(&self, num_consecutive_failures: u32) -> Duration {
+        let exponent = num_consecutive_failures
+            .saturating_sub(1)
+            .min(i32::MAX as u32) as i32;
+        let backoff_secs = (self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+        Duration::from_secs_f64((backoff_secs * factor).max(0.0))
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Self::default_initial_backoff(),
+            multiplier: Self::default_multiplier(),
+            max_backoff: Self::default_max_backoff(),
+            jitter: Self::default_jitter(),
+            degraded_after_failures: Self::default_degraded_after_failures(),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! Archives images of the canvases on a schedule, e.g. so a gallery can show what the
+//! canvases looked like at the end of each day of an event. Each snapshot is written
+//! to the snapshot storage as a PNG alongside a JSON file describing it, and recorded
+//! in the `canvas_snapshot` table so it can be found through the GraphQL API.
+//!
+//! This runs like any other flusher: each flush checks whether any snapshots are due
+//! and takes them if so. Canvases whose snapshot failed are retried on the next flush.
+
+use super::{
+    flush_canvases,
+    gcs::{create_client, upload_object, IMMUTABLE_CACHE_CONTROL},
+    FlusherTrait, RetryConfig,
+};
+use anyhow::{Context, Result};
+use aptos_move_graphql_scalars::Address;
+use async_trait::async_trait;
+use google_cloud_storage::client::Client;
+use metadata_storage::{CreateCanvasSnapshotIntent, MetadataStorageTrait};
+use pixel_storage::PixelStorageTrait;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotSchedulerConfig {
+    /// Where to write the snapshots.
+    storage: SnapshotStorageConfig,
+    /// If set, we snapshot every canvas each time the unix timestamp, minus
+    /// `offset_secs`, crosses a multiple of this. For example 86400 snapshots every
+    /// canvas at the end of every UTC day, and with an `offset_secs` of 3600 it's
+    /// an hour later instead.
+    #[serde(default)]
+    every_secs: Option<u64>,
+    #[serde(default)]
+    offset_secs: u64,
+    /// If set, we snapshot a canvas each time the version of the last txn written to
+    /// it crosses a multiple of this.
+    #[serde(default)]
+    every_versions: Option<u64>,
+    /// How often we check whether any snapshots are due.
+    #[serde(default = "SnapshotSchedulerConfig::default_check_interval")]
+    check_interval: Duration,
+    #[serde(default)]
+    retry_config: RetryConfig,
+}
+
+impl SnapshotSchedulerConfig {
+    pub fn default_check_interval() -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub enum SnapshotStorageConfig {
+    /// Write the snapshots to a directory on local disk.
+    Local { dir: PathBuf },
+    /// Upload the snapshots to a GCS bucket. Like the GcsFlusher, this only works
+    /// when running inside GCP.
+    Gcs { bucket_name: String },
+}
+
+#[derive(Clone)]
+enum SnapshotStorage {
+    Local { dir: PathBuf },
+    Gcs { client: Client, bucket_name: String },
+}
+
+impl SnapshotStorage {
+    async fn write(&self, path: &str, content_type: &str, data: Vec<u8>) -> Result<()> {
+        match self {
+            SnapshotStorage::Local { dir } => {
+                let path = dir.join(path);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create directory {}", parent.display())
+                    })?;
+                }
+                std::fs::write(&path, data)
+                    .with_context(|| format!("Failed to write {}", path.display()))
+            },
+            // Snapshots are never overwritten, so they can be cached forever.
+            SnapshotStorage::Gcs {
+                client,
+                bucket_name,
+            } => {
+                upload_object(
+                    client,
+                    bucket_name,
+                    path.to_string(),
+                    content_type,
+                    IMMUTABLE_CACHE_CONTROL,
+                    data,
+                )
+                .await
+            },
+        }
+    }
+}
+
+/// What we write next to each snapshot PNG.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SnapshotMetadata {
+    pub canvas_address: String,
+    /// The version of the last txn reflected in the image. This is only unset if the
+    /// processor hasn't written to the canvas since startup.
+    pub version: Option<u64>,
+    pub taken_at_secs: u64,
+    pub num_pixels: u64,
+    /// Where the PNG is in the snapshot storage.
+    pub path: String,
+}
+
+#[derive(Debug, Default)]
+struct SnapshotState {
+    /// Which multiple of `every_secs` we were in at the last flush.
+    last_time_slot: Option<u64>,
+    /// Which multiple of `every_versions` the version of each canvas was in at the
+    /// last flush.
+    last_version_slots: HashMap<Address, u64>,
+    /// Canvases that are due a snapshot, including ones whose snapshot failed.
+    pending: HashSet<Address>,
+}
+
+#[derive(Clone)]
+pub struct SnapshotScheduler {
+    config: SnapshotSchedulerConfig,
+    pixel_storage: Arc<dyn PixelStorageTrait>,
+    metadata_storage: Arc<dyn MetadataStorageTrait>,
+    storage: SnapshotStorage,
+    state: Arc<Mutex<SnapshotState>>,
+}
+
+impl SnapshotScheduler {
+    pub async fn new(
+        config: SnapshotSchedulerConfig,
+        pixel_storage: Arc<dyn PixelStorageTrait>,
+        metadata_storage: Arc<dyn MetadataStorageTrait>,
+    ) -> Result<Self> {
+        if config.every_secs.is_none() && config.every_versions.is_none() {
+            anyhow::bail!("At least one of every_secs and every_versions must be set");
+        }
+        if config.every_secs == Some(0) || config.every_versions == Some(0) {
+            anyhow::bail!("every_secs and every_versions must be greater than zero");
+        }
+        let storage = match &config.storage {
+            SnapshotStorageConfig::Local { dir } => SnapshotStorage::Local { dir: dir.clone() },
+            SnapshotStorageConfig::Gcs { bucket_name } => SnapshotStorage::Gcs {
+                client: create_client().await?,
+                bucket_name: bucket_name.clone(),
+            },
+        };
+        Ok(Self {
+            config,
+            pixel_storage,
+            metadata_storage,
+            storage,
+            state: Arc::new(Mutex::new(SnapshotState::default())),
+        })
+    }
+
+    /// Work out which canvases are now due a snapshot and add them to `pending`.
+    /// Returns whether every canvas is due. We only take snapshots when a boundary
+    /// is crossed while we're running, not when we first see where we are, so
+    /// restarting doesn't take a snapshot of everything.
+    fn update_pending(
+        &self,
+        state: &mut SnapshotState,
+        now_secs: u64,
+        versions: &HashMap<Address, u64>,
+    ) -> bool {
+        let mut all_due = false;
+        if let Some(every_secs) = self.config.every_secs {
+            let time_slot = now_secs.saturating_sub(self.config.offset_secs) / every_secs;
+            all_due = matches!(state.last_time_slot, Some(last) if time_slot > last);
+            state.last_time_slot = Some(time_slot);
+        }
+        if let Some(every_versions) = self.config.every_versions {
+            for (canvas_address, version) in versions {
+                let version_slot = version / every_versions;
+                let last = state
+                    .last_version_slots
+                    .insert(*canvas_address, version_slot);
+                if matches!(last, Some(last) if version_slot > last) {
+                    state.pending.insert(*canvas_address);
+                }
+            }
+        }
+        all_due
+    }
+
+    async fn write_snapshot(
+        &self,
+        canvas_address: Address,
+        version: Option<u64>,
+        taken_at_secs: u64,
+        png_data: Vec<u8>,
+    ) -> Result<()> {
+        let (width, height) = read_png_dimensions(&png_data)?;
+        let name = match version {
+            Some(version) => format!("{}_{}", taken_at_secs, version),
+            None => taken_at_secs.to_string(),
+        };
+        let path = format!("snapshots/{}/{}.png", canvas_address, name);
+        let metadata = SnapshotMetadata {
+            canvas_address: canvas_address.to_string(),
+            version,
+            taken_at_secs,
+            num_pixels: width as u64 * height as u64,
+            path: path.clone(),
+        };
+        let metadata_data =
+            serde_json::to_vec(&metadata).context("Failed to serialize snapshot metadata")?;
+
+        self.storage.write(&path, "image/png", png_data).await?;
+        self.storage
+            .write(
+                &format!("snapshots/{}/{}.json", canvas_address, name),
+                "application/json",
+                metadata_data,
+            )
+            .await?;
+        self.metadata_storage
+            .create_canvas_snapshot(CreateCanvasSnapshotIntent {
+                canvas_address,
+                taken_at_secs,
+                version,
+                num_pixels: metadata.num_pixels,
+                path,
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl FlusherTrait for SnapshotScheduler {
+    fn name(&self) -> &'static str {
+        "snapshot"
+    }
+
+    fn get_interval(&self) -> Duration {
+        self.config.check_interval
+    }
+
+    fn get_retry_config(&self) -> &RetryConfig {
+        &self.config.retry_config
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("Time went backwards")?
+            .as_secs();
+        // These are only used to work out which canvases are due, the versions we
+        // record are read along with the images below.
+        let versions = self.pixel_storage.get_canvas_versions().await?;
+        let mut state = self.state.lock().await;
+        let all_due = self.update_pending(&mut state, now_secs, &versions);
+        if !all_due && state.pending.is_empty() {
+            return Ok(());
+        }
+
+        let canvas_pngs = self
+            .pixel_storage
+            .get_canvases_as_pngs_with_versions()
+            .await?;
+        let mut image_versions = HashMap::new();
+        let mut pngs = HashMap::new();
+        for (canvas_address, png) in canvas_pngs {
+            if let Some(version) = png.version {
+                image_versions.insert(canvas_address, version);
+            }
+            pngs.insert(canvas_address, png.png_data);
+        }
+        if all_due {
+            state.pending.extend(pngs.keys());
+        }
+        // Forget about canvases that have been deleted since they became due.
+        state
+            .pending
+            .retain(|canvas_address| pngs.contains_key(canvas_address));
+        let pngs: HashMap<Address, Vec<u8>> = pngs
+            .into_iter()
+            .filter(|(canvas_address, _)| state.pending.contains(canvas_address))
+            .collect();
+
+        let taken = std::sync::Mutex::new(vec![]);
+        let result = flush_canvases(self.name(), pngs, |canvas_address, png_data| {
+            let version = image_versions.get(&canvas_address).copied();
+            let taken = &taken;
+            async move {
+                self.write_snapshot(canvas_address, version, now_secs, png_data)
+                    .await?;
+                taken.lock().unwrap().push(canvas_address);
+                Ok(())
+            }
+        })
+        .await;
+        for canvas_address in taken.into_inner().unwrap() {
+            state.pending.remove(&canvas_address);
+        }
+        result
+    }
+}
+
+/// Read the width and height from the IHDR chunk of a PNG, which always comes first,
+/// right after the 8 byte signature and the chunk length and type.
+fn read_png_dimensions(png_data: &[u8]) -> Result<(u32, u32)> {
+    if png_data.len() < 24 || &png_data[12..16] != b"IHDR" {
+        anyhow::bail!("Image is not a valid PNG");
+    }
+    let width = u32::from_be_bytes(png_data[16..20].try_into()?);
+    let height = u32::from_be_bytes(png_data[20..24].try_into()?);
+    Ok((width, height))
+}
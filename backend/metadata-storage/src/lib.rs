@@ -5,12 +5,57 @@ use anyhow::Result;
 use aptos_move_graphql_scalars::Address;
 pub use memory::MemoryMetadataStorage;
 pub use postgres::{PostgresMetadataStorage, PostgresMetadataStorageConfig};
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug, str::FromStr};
 
 /// Handles creating, updating, and reading canvases.
 #[async_trait::async_trait]
 pub trait MetadataStorageTrait: Debug + Send + Sync + 'static {
     async fn update_attribution(&self, intent: UpdateAttributionIntent) -> Result<()>;
+    async fn update_canvas(&self, intent: UpdateCanvasIntent) -> Result<()>;
+    async fn update_collection(&self, intent: UpdateCollectionIntent) -> Result<()>;
+    async fn update_paint_balance(&self, intent: UpdatePaintBalanceIntent) -> Result<()>;
+    /// The owner of a PAINT store, from its balance, if we've seen it before. The
+    /// PaintProcessor uses this when a txn changes the balance of a store without
+    /// writing its ObjectCore, e.g. a transfer into an existing store.
+    async fn read_paint_store_owner(&self, store_address: &Address) -> Result<Option<Address>>;
+    async fn create_paint_activity(&self, intent: CreatePaintActivityIntent) -> Result<()>;
+    async fn update_canvas_account(&self, intent: UpdateCanvasAccountIntent) -> Result<()>;
+
+    /// The width and height of each canvas. The processor uses these to work out
+    /// which pixel index a draw at (x, y) refers to and to check indices are in bounds.
+    async fn read_canvas_dimensions(&self) -> Result<HashMap<Address, (u16, u16)>>;
+
+    /// The processor uses these to keep track of which canvas a table item belongs to.
+    /// Unlike the rest of the functions, these are used regardless of whether
+    /// metadata processing is enabled, since the processor depends on them.
+    async fn write_canvas_table_handle(&self, table_handle: CanvasTableHandle) -> Result<()>;
+    async fn read_canvas_table_handles(&self) -> Result<Vec<CanvasTableHandle>>;
+
+    /// The processor records txns it skips because of errors here. Like the table
+    /// handles, these are used regardless of whether metadata processing is enabled.
+    async fn create_dead_letter_transaction(
+        &self,
+        intent: CreateDeadLetterTransactionIntent,
+    ) -> Result<()>;
+    async fn read_quarantined_canvases(&self, processor_name: &str) -> Result<Vec<Address>>;
+
+    /// The processor uses these to know how to decode the resources of each contract
+    /// deployment. Like the table handles, these are used regardless of whether
+    /// metadata processing is enabled.
+    async fn update_contract_layout(&self, intent: UpdateContractLayoutIntent) -> Result<()>;
+    async fn read_contract_layout(&self, contract_address: &Address) -> Result<Option<u16>>;
+
+    /// The snapshot scheduler records the images of canvases it archives here.
+    async fn create_canvas_snapshot(&self, intent: CreateCanvasSnapshotIntent) -> Result<()>;
+
+    /// Delete the attribution and per account state of a canvas, so it can be
+    /// rebuilt by reprocessing its txns. The canvas itself is left in place, since
+    /// reprocessing the txn that created it overwrites it.
+    async fn delete_canvas_metadata(&self, canvas_address: &Address) -> Result<()>;
+
+    /// Delete everything derived from txns, including the checkpoints and the chain
+    /// ID, e.g. so the processor can start again against a different chain.
+    async fn reset(&self) -> Result<()>;
 }
 
 /// All the information necessary to update attribution in storage.
@@ -24,4 +69,246 @@ pub struct UpdateAttributionIntent {
     pub index: u32,
     /// When the pixel was written.
     pub drawn_at_secs: u64,
+    /// The address of the contract deployment the canvas belongs to.
+    pub contract_address: Address,
+}
+
+/// All the information necessary to create or update a canvas in storage. This
+/// mirrors the Canvas resource (and its CanvasConfig) in the canvas_token module.
+#[derive(Clone, Debug)]
+pub struct UpdateCanvasIntent {
+    /// The address of the object containing the canvas.
+    pub canvas_address: Address,
+    pub width: u16,
+    pub height: u16,
+    /// How long artists have to wait between contributions. If zero, there is no
+    /// timeout.
+    pub per_account_timeout_s: u64,
+    pub default_color: u8,
+    /// The max number of pixels a non-admin artist can draw in a single txn.
+    pub max_number_of_pixels_per_draw: u64,
+    /// Whether artists who are not admins / unlimited artists can draw at all.
+    pub draw_enabled_for_non_admin: bool,
+    /// When the canvas was created.
+    pub created_at_s: u64,
+    /// The version of the txn that last wrote the Canvas resource.
+    pub last_updated_version: u64,
+    /// The owner of the canvas object, aka the super admin. This is only set if the
+    /// txn wrote the ObjectCore at the canvas address.
+    pub owner_address: Option<Address>,
+    /// The address of the contract deployment the canvas belongs to.
+    pub contract_address: Address,
+}
+
+/// All the information necessary to create or update the collection in storage. A
+/// txn generally only writes some of the resources at the collection address, so
+/// anything that is None here is left as is in storage.
+#[derive(Clone, Debug)]
+pub struct UpdateCollectionIntent {
+    /// The address of the object containing the collection.
+    pub collection_address: Address,
+    /// From the Collection resource.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub uri: Option<String>,
+    pub creator_address: Option<Address>,
+    /// From the ObjectCore resource.
+    pub owner_address: Option<Address>,
+    /// From the CollectionConfig resource.
+    pub max_width: Option<u16>,
+    pub max_height: Option<u16>,
+    /// The version of the txn that last wrote any of these resources.
+    pub last_updated_version: u64,
+}
+
+/// All the information necessary to update the PAINT balance of an account.
+#[derive(Clone, Debug)]
+pub struct UpdatePaintBalanceIntent {
+    /// The owner of the primary PAINT store.
+    pub account_address: Address,
+    /// The address of the object containing the FungibleStore.
+    pub store_address: Address,
+    pub balance: u64,
+    /// The version of the txn that last wrote the FungibleStore.
+    pub last_updated_version: u64,
+}
+
+/// What kind of change to a PAINT store a `CreatePaintActivityIntent` represents.
+#[derive(Clone, Copy, Debug)]
+pub enum PaintActivityType {
+    /// A deposit that came from the mint entry function.
+    Mint,
+    Deposit,
+    Withdraw,
+}
+
+impl PaintActivityType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaintActivityType::Mint => "mint",
+            PaintActivityType::Deposit => "deposit",
+            PaintActivityType::Withdraw => "withdraw",
+        }
+    }
+}
+
+/// All the information necessary to record a single deposit to / withdrawal from a
+/// PAINT store.
+#[derive(Clone, Debug)]
+pub struct CreatePaintActivityIntent {
+    pub transaction_version: u64,
+    /// The index of the event within the txn.
+    pub event_index: u64,
+    /// The owner of the primary PAINT store.
+    pub account_address: Address,
+    /// The address of the object containing the FungibleStore.
+    pub store_address: Address,
+    pub activity_type: PaintActivityType,
+    pub amount: u64,
+}
+
+/// All the information necessary to update the per account state of a canvas.
+#[derive(Clone, Debug)]
+pub struct UpdateCanvasAccountIntent {
+    /// The address of the object containing the canvas.
+    pub canvas_address: Address,
+    pub change: CanvasAccountChange,
+    /// The version of the txn that made the change.
+    pub last_updated_version: u64,
+}
+
+/// A change to one of the per account tables in the Canvas resource.
+#[derive(Clone, Debug)]
+pub enum CanvasAccountChange {
+    /// The account drew, from the `last_contribution_s` table.
+    SetLastContribution {
+        account_address: Address,
+        last_contribution_s: u64,
+    },
+    /// The account was added to / removed from the `admins` table.
+    SetAdmin {
+        account_address: Address,
+        is_admin: bool,
+    },
+    /// The account was added to / removed from the `unlimited_artists` table.
+    SetUnlimitedArtist {
+        account_address: Address,
+        is_unlimited_artist: bool,
+    },
+    /// The `last_contribution_s` table was replaced with an empty one, see
+    /// `clear_contribution_timeouts`.
+    ClearLastContributions,
+    /// The `pixels` table was replaced with an empty one, see `clear`. Every pixel is
+    /// back to the default color, so none of them are attributed to anyone.
+    ClearAttribution,
+}
+
+/// The tables in the Canvas resource.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CanvasTableName {
+    LastContributionS,
+    UnlimitedArtists,
+    Admins,
+    /// The buckets table inside the `pixels` SmartTable.
+    Pixels,
+}
+
+impl CanvasTableName {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CanvasTableName::LastContributionS => "last_contribution_s",
+            CanvasTableName::UnlimitedArtists => "unlimited_artists",
+            CanvasTableName::Admins => "admins",
+            CanvasTableName::Pixels => "pixels",
+        }
+    }
+}
+
+impl FromStr for CanvasTableName {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "last_contribution_s" => Ok(CanvasTableName::LastContributionS),
+            "unlimited_artists" => Ok(CanvasTableName::UnlimitedArtists),
+            "admins" => Ok(CanvasTableName::Admins),
+            "pixels" => Ok(CanvasTableName::Pixels),
+            _ => anyhow::bail!("Unknown canvas table name: {}", s),
+        }
+    }
+}
+
+/// Which canvas, and which table within it, a table handle belongs to.
+#[derive(Clone, Debug)]
+pub struct CanvasTableHandle {
+    pub handle: Address,
+    /// The address of the object containing the canvas.
+    pub canvas_address: Address,
+    pub table_name: CanvasTableName,
+    /// The version of the txn in which we first saw this handle.
+    pub created_version: u64,
+    /// Whether we first saw this handle in the txn that created the canvas, in which
+    /// case `created_version` is when the canvas was created. We also learn about
+    /// handles from any other txn that writes the Canvas resource, e.g. a draw. This
+    /// is None for handles recorded before we kept track of this.
+    pub created_with_canvas: Option<bool>,
+    /// Whether we learned about this handle from a state snapshot rather than a txn.
+    /// If so, `created_version` is the version of the snapshot, which is usually long
+    /// after the canvas was actually created.
+    pub bootstrapped: bool,
+}
+
+/// What the processor did with a txn it failed to process.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeadLetterAction {
+    /// The txn was skipped.
+    Skipped,
+    /// The txn was skipped and so will every later txn for the same canvas.
+    Quarantined,
+}
+
+impl DeadLetterAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeadLetterAction::Skipped => "skipped",
+            DeadLetterAction::Quarantined => "quarantined",
+        }
+    }
+}
+
+/// All the information necessary to record a txn the processor skipped.
+#[derive(Clone, Debug)]
+pub struct CreateDeadLetterTransactionIntent {
+    pub processor_name: String,
+    pub transaction_version: u64,
+    /// The address of the object containing the canvas, if we know it.
+    pub canvas_address: Option<Address>,
+    /// What kind of error it was, e.g. `unknown_color`.
+    pub error_kind: String,
+    pub error_message: String,
+    pub action: DeadLetterAction,
+}
+
+/// All the information necessary to record an archived image of a canvas.
+#[derive(Clone, Debug)]
+pub struct CreateCanvasSnapshotIntent {
+    /// The address of the object containing the canvas.
+    pub canvas_address: Address,
+    /// When the snapshot was taken, as a unix timestamp.
+    pub taken_at_secs: u64,
+    /// The version of the last txn reflected in the image, if we know it.
+    pub version: Option<u64>,
+    pub num_pixels: u64,
+    /// Where the image was written to in the snapshot storage.
+    pub path: String,
+}
+
+/// All the information necessary to record which layout of the contract's structs is
+/// active for a contract deployment.
+#[derive(Clone, Debug)]
+pub struct UpdateContractLayoutIntent {
+    pub contract_address: Address,
+    pub layout_version: u16,
+    /// The version of the txn that published the module.
+    pub last_updated_version: u64,
 }
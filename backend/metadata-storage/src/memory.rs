@@ -1,14 +1,25 @@
-use super::{MetadataStorageTrait, UpdateAttributionIntent};
+use super::{
+    CanvasTableHandle, CreateCanvasSnapshotIntent, CreateDeadLetterTransactionIntent,
+    CreatePaintActivityIntent, DeadLetterAction, MetadataStorageTrait, UpdateAttributionIntent,
+    UpdateCanvasAccountIntent, UpdateCanvasIntent, UpdateCollectionIntent,
+    UpdateContractLayoutIntent, UpdatePaintBalanceIntent,
+};
 use anyhow::Result;
+use aptos_move_graphql_scalars::Address;
 use aptos_processor_framework::StorageTrait;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::Mutex;
 
 /// An in-memory, transient storage implementation.
 #[derive(Debug)]
 pub struct MemoryMetadataStorage {
     chain_id: Arc<Mutex<Option<u8>>>,
-    last_processed_version: Arc<Mutex<Option<u64>>>,
+    /// Keyed by processor name, like the checkpoints in the DB.
+    last_processed_versions: Arc<Mutex<HashMap<String, u64>>>,
+    canvas_table_handles: Arc<Mutex<Vec<CanvasTableHandle>>>,
+    dead_letter_transactions: Arc<Mutex<Vec<CreateDeadLetterTransactionIntent>>>,
+    contract_layouts: Arc<Mutex<HashMap<Address, u16>>>,
+    paint_store_owners: Arc<Mutex<HashMap<Address, Address>>>,
 }
 
 impl MemoryMetadataStorage {
@@ -16,7 +27,11 @@ impl MemoryMetadataStorage {
     pub fn new() -> Self {
         Self {
             chain_id: Arc::new(Mutex::new(None)),
-            last_processed_version: Arc::new(Mutex::new(None)),
+            last_processed_versions: Arc::new(Mutex::new(HashMap::new())),
+            canvas_table_handles: Arc::new(Mutex::new(Vec::new())),
+            dead_letter_transactions: Arc::new(Mutex::new(Vec::new())),
+            contract_layouts: Arc::new(Mutex::new(HashMap::new())),
+            paint_store_owners: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -38,16 +53,20 @@ impl StorageTrait for MemoryMetadataStorage {
         Ok(())
     }
 
-    async fn read_last_processed_version(&self, _processor_name: &str) -> Result<Option<u64>> {
-        Ok(*self.last_processed_version.lock().await)
+    async fn read_last_processed_version(&self, processor_name: &str) -> Result<Option<u64>> {
+        Ok(self
+            .last_processed_versions
+            .lock()
+            .await
+            .get(processor_name)
+            .copied())
     }
 
-    async fn write_last_processed_version(
-        &self,
-        _processor_name: &str,
-        version: u64,
-    ) -> Result<()> {
-        *self.last_processed_version.lock().await = Some(version);
+    async fn write_last_processed_version(&self, processor_name: &str, version: u64) -> Result<()> {
+        self.last_processed_versions
+            .lock()
+            .await
+            .insert(processor_name.to_string(), version);
         Ok(())
     }
 }
@@ -57,4 +76,114 @@ impl MetadataStorageTrait for MemoryMetadataStorage {
     async fn update_attribution(&self, _intent: UpdateAttributionIntent) -> Result<()> {
         Ok(())
     }
+
+    async fn update_canvas(&self, _intent: UpdateCanvasIntent) -> Result<()> {
+        Ok(())
+    }
+
+    async fn update_collection(&self, _intent: UpdateCollectionIntent) -> Result<()> {
+        Ok(())
+    }
+
+    async fn update_paint_balance(&self, intent: UpdatePaintBalanceIntent) -> Result<()> {
+        self.paint_store_owners
+            .lock()
+            .await
+            .insert(intent.store_address, intent.account_address);
+        Ok(())
+    }
+
+    async fn read_paint_store_owner(&self, store_address: &Address) -> Result<Option<Address>> {
+        Ok(self
+            .paint_store_owners
+            .lock()
+            .await
+            .get(store_address)
+            .copied())
+    }
+
+    async fn create_paint_activity(&self, _intent: CreatePaintActivityIntent) -> Result<()> {
+        Ok(())
+    }
+
+    async fn update_canvas_account(&self, _intent: UpdateCanvasAccountIntent) -> Result<()> {
+        Ok(())
+    }
+
+    async fn read_canvas_dimensions(&self) -> Result<HashMap<Address, (u16, u16)>> {
+        Ok(HashMap::new())
+    }
+
+    /// Like the Postgres storage, we keep the first record of each handle.
+    async fn write_canvas_table_handle(&self, table_handle: CanvasTableHandle) -> Result<()> {
+        let mut canvas_table_handles = self.canvas_table_handles.lock().await;
+        if !canvas_table_handles
+            .iter()
+            .any(|existing| existing.handle == table_handle.handle)
+        {
+            canvas_table_handles.push(table_handle);
+        }
+        Ok(())
+    }
+
+    async fn read_canvas_table_handles(&self) -> Result<Vec<CanvasTableHandle>> {
+        Ok(self.canvas_table_handles.lock().await.clone())
+    }
+
+    async fn create_dead_letter_transaction(
+        &self,
+        intent: CreateDeadLetterTransactionIntent,
+    ) -> Result<()> {
+        self.dead_letter_transactions.lock().await.push(intent);
+        Ok(())
+    }
+
+    async fn read_quarantined_canvases(&self, processor_name: &str) -> Result<Vec<Address>> {
+        Ok(self
+            .dead_letter_transactions
+            .lock()
+            .await
+            .iter()
+            .filter(|intent| {
+                intent.processor_name == processor_name
+                    && intent.action == DeadLetterAction::Quarantined
+            })
+            .filter_map(|intent| intent.canvas_address)
+            .collect())
+    }
+
+    async fn update_contract_layout(&self, intent: UpdateContractLayoutIntent) -> Result<()> {
+        self.contract_layouts
+            .lock()
+            .await
+            .insert(intent.contract_address, intent.layout_version);
+        Ok(())
+    }
+
+    async fn read_contract_layout(&self, contract_address: &Address) -> Result<Option<u16>> {
+        Ok(self
+            .contract_layouts
+            .lock()
+            .await
+            .get(contract_address)
+            .copied())
+    }
+
+    async fn create_canvas_snapshot(&self, _intent: CreateCanvasSnapshotIntent) -> Result<()> {
+        Ok(())
+    }
+
+    async fn delete_canvas_metadata(&self, _canvas_address: &Address) -> Result<()> {
+        Ok(())
+    }
+
+    async fn reset(&self) -> Result<()> {
+        *self.chain_id.lock().await = None;
+        self.last_processed_versions.lock().await.clear();
+        self.canvas_table_handles.lock().await.clear();
+        self.dead_letter_transactions.lock().await.clear();
+        self.contract_layouts.lock().await.clear();
+        self.paint_store_owners.lock().await.clear();
+        Ok(())
+    }
 }
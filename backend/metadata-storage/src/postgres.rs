@@ -1,13 +1,25 @@
-use super::{MetadataStorageTrait, UpdateAttributionIntent};
+use super::{
+    CanvasAccountChange, CanvasTableHandle, CreateCanvasSnapshotIntent,
+    CreateDeadLetterTransactionIntent, CreatePaintActivityIntent, DeadLetterAction,
+    MetadataStorageTrait, UpdateAttributionIntent, UpdateCanvasAccountIntent, UpdateCanvasIntent,
+    UpdateCollectionIntent, UpdateContractLayoutIntent, UpdatePaintBalanceIntent,
+};
 use anyhow::{Context, Result};
+use aptos_move_graphql_scalars::Address;
 use aptos_processor_framework::StorageTrait;
-use entities::{chain_id, last_processed_version, pixel_attribution};
+use entities::{
+    canvas, canvas_account, canvas_snapshot, canvas_table_handle, chain_id, collection,
+    contract_layout, dead_letter_transaction, last_processed_version, paint_activity,
+    paint_balance, pixel_attribution,
+};
 use migrations::{Migrator, MigratorTrait};
 use sea_orm::{
-    sea_query::OnConflict, ConnectionTrait, Database, DatabaseConnection, DbBackend, EntityTrait,
-    QueryTrait,
+    sea_query::{Expr, OnConflict},
+    ColumnTrait, ConnectionTrait, Database, DatabaseConnection, DbBackend, EntityTrait,
+    QueryFilter, QueryTrait, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr};
 use tracing::info;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -37,6 +49,42 @@ impl PostgresMetadataStorage {
 
         Ok(Self { connection })
     }
+
+    /// Check we can reach the DB.
+    pub async fn ping(&self) -> Result<()> {
+        self.connection.ping().await.context("Failed to ping DB")
+    }
+
+    /// Close the connection pool, e.g. when shutting down. Any other use of the
+    /// storage after this fails.
+    pub async fn close(&self) -> Result<()> {
+        self.connection
+            .clone()
+            .close()
+            .await
+            .context("Failed to close DB connection")
+    }
+
+    pub async fn read_canvas(&self, canvas_address: &Address) -> Result<Option<canvas::Model>> {
+        canvas::Entity::find_by_id(canvas_address.to_string())
+            .one(&self.connection)
+            .await
+            .context("Failed to read canvas")
+    }
+
+    pub async fn read_canvas_account(
+        &self,
+        canvas_address: &Address,
+        account_address: &Address,
+    ) -> Result<Option<canvas_account::Model>> {
+        canvas_account::Entity::find_by_id((
+            canvas_address.to_string(),
+            account_address.to_string(),
+        ))
+        .one(&self.connection)
+        .await
+        .context("Failed to read canvas account")
+    }
 }
 
 #[async_trait::async_trait]
@@ -47,6 +95,7 @@ impl MetadataStorageTrait for PostgresMetadataStorage {
             canvas_address: sea_orm::Set(intent.canvas_address.to_string()),
             artist_address: sea_orm::Set(intent.artist_address.to_string()),
             drawn_at_secs: sea_orm::Set(intent.drawn_at_secs as i64),
+            contract_address: sea_orm::Set(Some(intent.contract_address.to_string())),
         };
 
         let query = pixel_attribution::Entity::insert(new_attribution)
@@ -68,6 +117,10 @@ impl MetadataStorageTrait for PostgresMetadataStorage {
                     pixel_attribution::Column::DrawnAtSecs,
                     intent.drawn_at_secs as i64,
                 )
+                .value(
+                    pixel_attribution::Column::ContractAddress,
+                    intent.contract_address.to_string(),
+                )
                 .to_owned(),
             )
             .build(DbBackend::Postgres);
@@ -79,6 +132,564 @@ impl MetadataStorageTrait for PostgresMetadataStorage {
 
         Ok(())
     }
+
+    async fn update_canvas(&self, intent: UpdateCanvasIntent) -> Result<()> {
+        let new_canvas = canvas::ActiveModel {
+            canvas_address: sea_orm::Set(intent.canvas_address.to_string()),
+            width: sea_orm::Set(intent.width as i32),
+            height: sea_orm::Set(intent.height as i32),
+            per_account_timeout_s: sea_orm::Set(intent.per_account_timeout_s as i64),
+            default_color: sea_orm::Set(intent.default_color as i16),
+            max_number_of_pixels_per_draw: sea_orm::Set(
+                intent.max_number_of_pixels_per_draw as i64,
+            ),
+            draw_enabled_for_non_admin: sea_orm::Set(intent.draw_enabled_for_non_admin),
+            created_at_s: sea_orm::Set(intent.created_at_s as i64),
+            last_updated_version: sea_orm::Set(intent.last_updated_version as i64),
+            owner_address: match &intent.owner_address {
+                Some(owner_address) => sea_orm::Set(Some(owner_address.to_string())),
+                None => sea_orm::NotSet,
+            },
+            contract_address: sea_orm::Set(Some(intent.contract_address.to_string())),
+        };
+
+        // The address and creation time never change, so we only update the config
+        // (and the owner, if we know it) on conflict. We update the contract address
+        // too, to fill it in for canvases written before we recorded it.
+        let mut update_columns = vec![
+            canvas::Column::Width,
+            canvas::Column::Height,
+            canvas::Column::PerAccountTimeoutS,
+            canvas::Column::DefaultColor,
+            canvas::Column::MaxNumberOfPixelsPerDraw,
+            canvas::Column::DrawEnabledForNonAdmin,
+            canvas::Column::LastUpdatedVersion,
+            canvas::Column::ContractAddress,
+        ];
+        if intent.owner_address.is_some() {
+            update_columns.push(canvas::Column::OwnerAddress);
+        }
+        let query = canvas::Entity::insert(new_canvas)
+            .on_conflict(
+                OnConflict::column(canvas::Column::CanvasAddress)
+                    .update_columns(update_columns)
+                    .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to update canvas")?;
+
+        Ok(())
+    }
+
+    async fn update_collection(&self, intent: UpdateCollectionIntent) -> Result<()> {
+        // Only set (and on conflict, update) the columns we have values for.
+        let mut update_columns = vec![collection::Column::LastUpdatedVersion];
+        let mut set_if_some = |column, value: Option<_>| match value {
+            Some(value) => {
+                update_columns.push(column);
+                sea_orm::Set(Some(value))
+            },
+            None => sea_orm::NotSet,
+        };
+        let name = set_if_some(collection::Column::Name, intent.name);
+        let description = set_if_some(collection::Column::Description, intent.description);
+        let uri = set_if_some(collection::Column::Uri, intent.uri);
+        let creator_address = set_if_some(
+            collection::Column::CreatorAddress,
+            intent.creator_address.map(|a| a.to_string()),
+        );
+        let owner_address = set_if_some(
+            collection::Column::OwnerAddress,
+            intent.owner_address.map(|a| a.to_string()),
+        );
+        let mut set_if_some = |column, value: Option<u16>| match value {
+            Some(value) => {
+                update_columns.push(column);
+                sea_orm::Set(Some(value as i32))
+            },
+            None => sea_orm::NotSet,
+        };
+        let max_width = set_if_some(collection::Column::MaxWidth, intent.max_width);
+        let max_height = set_if_some(collection::Column::MaxHeight, intent.max_height);
+
+        let new_collection = collection::ActiveModel {
+            collection_address: sea_orm::Set(intent.collection_address.to_string()),
+            name,
+            description,
+            uri,
+            creator_address,
+            owner_address,
+            max_width,
+            max_height,
+            last_updated_version: sea_orm::Set(intent.last_updated_version as i64),
+        };
+
+        let query = collection::Entity::insert(new_collection)
+            .on_conflict(
+                OnConflict::column(collection::Column::CollectionAddress)
+                    .update_columns(update_columns)
+                    .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to update collection")?;
+
+        Ok(())
+    }
+
+    async fn update_paint_balance(&self, intent: UpdatePaintBalanceIntent) -> Result<()> {
+        let new_balance = paint_balance::ActiveModel {
+            account_address: sea_orm::Set(intent.account_address.to_string()),
+            store_address: sea_orm::Set(intent.store_address.to_string()),
+            balance: sea_orm::Set(intent.balance as i64),
+            last_updated_version: sea_orm::Set(intent.last_updated_version as i64),
+        };
+
+        let query = paint_balance::Entity::insert(new_balance)
+            .on_conflict(
+                OnConflict::column(paint_balance::Column::AccountAddress)
+                    .update_columns(vec![
+                        paint_balance::Column::StoreAddress,
+                        paint_balance::Column::Balance,
+                        paint_balance::Column::LastUpdatedVersion,
+                    ])
+                    .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to update paint balance")?;
+
+        Ok(())
+    }
+
+    async fn read_paint_store_owner(&self, store_address: &Address) -> Result<Option<Address>> {
+        let model = paint_balance::Entity::find()
+            .filter(paint_balance::Column::StoreAddress.eq(store_address.to_string()))
+            .one(&self.connection)
+            .await
+            .context("Failed to read paint balance")?;
+        model
+            .map(|model| {
+                Address::from_str(&model.account_address).context("Failed to parse account address")
+            })
+            .transpose()
+    }
+
+    async fn create_paint_activity(&self, intent: CreatePaintActivityIntent) -> Result<()> {
+        let new_activity = paint_activity::ActiveModel {
+            transaction_version: sea_orm::Set(intent.transaction_version as i64),
+            event_index: sea_orm::Set(intent.event_index as i64),
+            account_address: sea_orm::Set(intent.account_address.to_string()),
+            store_address: sea_orm::Set(intent.store_address.to_string()),
+            activity_type: sea_orm::Set(intent.activity_type.as_str().to_string()),
+            amount: sea_orm::Set(intent.amount as i64),
+        };
+
+        // If we reprocess a txn the activity will be identical, so we just skip it.
+        let query = paint_activity::Entity::insert(new_activity)
+            .on_conflict(
+                OnConflict::columns(vec![
+                    paint_activity::Column::TransactionVersion,
+                    paint_activity::Column::EventIndex,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to create paint activity")?;
+
+        Ok(())
+    }
+
+    async fn update_canvas_account(&self, intent: UpdateCanvasAccountIntent) -> Result<()> {
+        let canvas_address = intent.canvas_address.to_string();
+        let last_updated_version = intent.last_updated_version as i64;
+
+        // Build the row we'd insert if there is nothing there yet, along with the
+        // column we want to update if there is.
+        let (account_address, last_contribution_s, is_admin, is_unlimited_artist, column) =
+            match intent.change {
+                CanvasAccountChange::SetLastContribution {
+                    account_address,
+                    last_contribution_s,
+                } => (
+                    account_address,
+                    Some(last_contribution_s as i64),
+                    false,
+                    false,
+                    canvas_account::Column::LastContributionS,
+                ),
+                CanvasAccountChange::SetAdmin {
+                    account_address,
+                    is_admin,
+                } => (
+                    account_address,
+                    None,
+                    is_admin,
+                    false,
+                    canvas_account::Column::IsAdmin,
+                ),
+                CanvasAccountChange::SetUnlimitedArtist {
+                    account_address,
+                    is_unlimited_artist,
+                } => (
+                    account_address,
+                    None,
+                    false,
+                    is_unlimited_artist,
+                    canvas_account::Column::IsUnlimitedArtist,
+                ),
+                CanvasAccountChange::ClearLastContributions => {
+                    // This affects every account for the canvas, so we just update
+                    // them all in place.
+                    canvas_account::Entity::update_many()
+                        .col_expr(
+                            canvas_account::Column::LastContributionS,
+                            Expr::value(Option::<i64>::None),
+                        )
+                        .col_expr(
+                            canvas_account::Column::LastUpdatedVersion,
+                            Expr::value(last_updated_version),
+                        )
+                        .filter(canvas_account::Column::CanvasAddress.eq(canvas_address))
+                        .exec(&self.connection)
+                        .await
+                        .context("Failed to clear last contributions")?;
+                    return Ok(());
+                },
+                CanvasAccountChange::ClearAttribution => {
+                    pixel_attribution::Entity::delete_many()
+                        .filter(pixel_attribution::Column::CanvasAddress.eq(canvas_address))
+                        .exec(&self.connection)
+                        .await
+                        .context("Failed to clear attribution")?;
+                    return Ok(());
+                },
+            };
+
+        let new_canvas_account = canvas_account::ActiveModel {
+            canvas_address: sea_orm::Set(canvas_address),
+            account_address: sea_orm::Set(account_address.to_string()),
+            last_contribution_s: sea_orm::Set(last_contribution_s),
+            is_admin: sea_orm::Set(is_admin),
+            is_unlimited_artist: sea_orm::Set(is_unlimited_artist),
+            last_updated_version: sea_orm::Set(last_updated_version),
+        };
+
+        let query = canvas_account::Entity::insert(new_canvas_account)
+            .on_conflict(
+                OnConflict::columns(vec![
+                    canvas_account::Column::CanvasAddress,
+                    canvas_account::Column::AccountAddress,
+                ])
+                .update_columns(vec![column, canvas_account::Column::LastUpdatedVersion])
+                .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to update canvas account")?;
+
+        Ok(())
+    }
+
+    async fn write_canvas_table_handle(&self, table_handle: CanvasTableHandle) -> Result<()> {
+        let new_table_handle = canvas_table_handle::ActiveModel {
+            handle: sea_orm::Set(table_handle.handle.to_string()),
+            canvas_address: sea_orm::Set(table_handle.canvas_address.to_string()),
+            table_name: sea_orm::Set(table_handle.table_name.as_str().to_string()),
+            created_version: sea_orm::Set(table_handle.created_version as i64),
+            bootstrapped: sea_orm::Set(table_handle.bootstrapped),
+            created_with_canvas: sea_orm::Set(table_handle.created_with_canvas),
+        };
+
+        // Handles are unique and never change owner, so if we've seen it already
+        // there is nothing to do.
+        let query = canvas_table_handle::Entity::insert(new_table_handle)
+            .on_conflict(
+                OnConflict::column(canvas_table_handle::Column::Handle)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to write canvas table handle")?;
+
+        Ok(())
+    }
+
+    async fn read_canvas_dimensions(&self) -> Result<HashMap<Address, (u16, u16)>> {
+        let models = canvas::Entity::find()
+            .all(&self.connection)
+            .await
+            .context("Failed to read canvases")?;
+        models
+            .into_iter()
+            .map(|model| {
+                Ok((
+                    Address::from_str(&model.canvas_address)
+                        .context("Failed to parse canvas address")?,
+                    (model.width as u16, model.height as u16),
+                ))
+            })
+            .collect()
+    }
+
+    async fn read_canvas_table_handles(&self) -> Result<Vec<CanvasTableHandle>> {
+        let models = canvas_table_handle::Entity::find()
+            .all(&self.connection)
+            .await
+            .context("Failed to read canvas table handles")?;
+        models
+            .into_iter()
+            .map(|model| {
+                Ok(CanvasTableHandle {
+                    handle: Address::from_str(&model.handle)
+                        .context("Failed to parse table handle")?,
+                    canvas_address: Address::from_str(&model.canvas_address)
+                        .context("Failed to parse canvas address")?,
+                    table_name: model.table_name.parse()?,
+                    created_version: model.created_version as u64,
+                    bootstrapped: model.bootstrapped,
+                    created_with_canvas: model.created_with_canvas,
+                })
+            })
+            .collect()
+    }
+
+    async fn create_dead_letter_transaction(
+        &self,
+        intent: CreateDeadLetterTransactionIntent,
+    ) -> Result<()> {
+        let new_dead_letter_transaction = dead_letter_transaction::ActiveModel {
+            processor_name: sea_orm::Set(intent.processor_name),
+            transaction_version: sea_orm::Set(intent.transaction_version as i64),
+            canvas_address: sea_orm::Set(
+                intent
+                    .canvas_address
+                    .map(|canvas_address| canvas_address.to_string()),
+            ),
+            error_kind: sea_orm::Set(intent.error_kind),
+            error_message: sea_orm::Set(intent.error_message),
+            action: sea_orm::Set(intent.action.as_str().to_string()),
+        };
+
+        // If we reprocess a txn we skipped before, keep the latest reason.
+        let query = dead_letter_transaction::Entity::insert(new_dead_letter_transaction)
+            .on_conflict(
+                OnConflict::columns([
+                    dead_letter_transaction::Column::ProcessorName,
+                    dead_letter_transaction::Column::TransactionVersion,
+                ])
+                .update_columns([
+                    dead_letter_transaction::Column::CanvasAddress,
+                    dead_letter_transaction::Column::ErrorKind,
+                    dead_letter_transaction::Column::ErrorMessage,
+                    dead_letter_transaction::Column::Action,
+                ])
+                .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to create dead letter transaction")?;
+
+        Ok(())
+    }
+
+    async fn read_quarantined_canvases(&self, processor_name: &str) -> Result<Vec<Address>> {
+        let models = dead_letter_transaction::Entity::find()
+            .filter(dead_letter_transaction::Column::ProcessorName.eq(processor_name))
+            .filter(
+                dead_letter_transaction::Column::Action.eq(DeadLetterAction::Quarantined.as_str()),
+            )
+            .all(&self.connection)
+            .await
+            .context("Failed to read quarantined canvases")?;
+        models
+            .into_iter()
+            .filter_map(|model| model.canvas_address)
+            .map(|canvas_address| {
+                Address::from_str(&canvas_address).context("Failed to parse canvas address")
+            })
+            .collect()
+    }
+
+    async fn update_contract_layout(&self, intent: UpdateContractLayoutIntent) -> Result<()> {
+        let new_contract_layout = contract_layout::ActiveModel {
+            contract_address: sea_orm::Set(intent.contract_address.to_string()),
+            layout_version: sea_orm::Set(intent.layout_version as i32),
+            last_updated_version: sea_orm::Set(intent.last_updated_version as i64),
+        };
+
+        let query = contract_layout::Entity::insert(new_contract_layout)
+            .on_conflict(
+                OnConflict::column(contract_layout::Column::ContractAddress)
+                    .update_columns([
+                        contract_layout::Column::LayoutVersion,
+                        contract_layout::Column::LastUpdatedVersion,
+                    ])
+                    .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to update contract layout")?;
+
+        Ok(())
+    }
+
+    async fn read_contract_layout(&self, contract_address: &Address) -> Result<Option<u16>> {
+        Ok(
+            contract_layout::Entity::find_by_id(contract_address.to_string())
+                .one(&self.connection)
+                .await
+                .context("Failed to read contract layout")?
+                .map(|model| model.layout_version as u16),
+        )
+    }
+
+    async fn create_canvas_snapshot(&self, intent: CreateCanvasSnapshotIntent) -> Result<()> {
+        let new_canvas_snapshot = canvas_snapshot::ActiveModel {
+            canvas_address: sea_orm::Set(intent.canvas_address.to_string()),
+            taken_at_secs: sea_orm::Set(intent.taken_at_secs as i64),
+            version: sea_orm::Set(intent.version.map(|version| version as i64)),
+            num_pixels: sea_orm::Set(intent.num_pixels as i64),
+            path: sea_orm::Set(intent.path),
+        };
+
+        // If we retry after failing partway through, the later attempt wins.
+        let query = canvas_snapshot::Entity::insert(new_canvas_snapshot)
+            .on_conflict(
+                OnConflict::columns([
+                    canvas_snapshot::Column::CanvasAddress,
+                    canvas_snapshot::Column::TakenAtSecs,
+                ])
+                .update_columns([
+                    canvas_snapshot::Column::Version,
+                    canvas_snapshot::Column::NumPixels,
+                    canvas_snapshot::Column::Path,
+                ])
+                .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+
+        self.connection
+            .execute(query)
+            .await
+            .context("Failed to create canvas snapshot")?;
+
+        Ok(())
+    }
+
+    async fn delete_canvas_metadata(&self, canvas_address: &Address) -> Result<()> {
+        let result = pixel_attribution::Entity::delete_many()
+            .filter(pixel_attribution::Column::CanvasAddress.eq(canvas_address.to_string()))
+            .exec(&self.connection)
+            .await
+            .context("Failed to delete attribution")?;
+        info!(
+            "Deleted {} attributions for canvas {}",
+            result.rows_affected, canvas_address
+        );
+
+        let result = canvas_account::Entity::delete_many()
+            .filter(canvas_account::Column::CanvasAddress.eq(canvas_address.to_string()))
+            .exec(&self.connection)
+            .await
+            .context("Failed to delete canvas accounts")?;
+        info!(
+            "Deleted {} canvas accounts for canvas {}",
+            result.rows_affected, canvas_address
+        );
+
+        Ok(())
+    }
+
+    async fn reset(&self) -> Result<()> {
+        // Do it all in one DB txn so we don't end up with half the data deleted.
+        let txn = self
+            .connection
+            .begin()
+            .await
+            .context("Failed to start DB txn")?;
+        pixel_attribution::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete attribution")?;
+        canvas_account::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete canvas accounts")?;
+        canvas_table_handle::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete canvas table handles")?;
+        canvas::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete canvases")?;
+        collection::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete collections")?;
+        contract_layout::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete contract layouts")?;
+        canvas_snapshot::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete canvas snapshots")?;
+        dead_letter_transaction::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete dead letter txns")?;
+        paint_activity::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete PAINT activity")?;
+        paint_balance::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete PAINT balances")?;
+        last_processed_version::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete last processed versions")?;
+        chain_id::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .context("Failed to delete chain ID")?;
+        txn.commit().await.context("Failed to commit DB txn")?;
+
+        info!("Deleted all processed data from the DB");
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
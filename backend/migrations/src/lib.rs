@@ -1,12 +1,34 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20261018_000001_create_canvas_table;
+mod m20261018_000002_create_collection_table;
+mod m20261018_000003_create_paint_tables;
+mod m20261018_000004_create_canvas_account_tables;
+mod m20261018_000005_create_dead_letter_transaction_table;
+mod m20261018_000006_add_contract_address_columns;
+mod m20261018_000007_create_contract_layout_table;
+mod m20261018_000008_create_canvas_snapshot_table;
+mod m20261019_000009_add_bootstrapped_column;
+mod m20261019_000010_add_created_with_canvas_column;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20261018_000001_create_canvas_table::Migration),
+            Box::new(m20261018_000002_create_collection_table::Migration),
+            Box::new(m20261018_000003_create_paint_tables::Migration),
+            Box::new(m20261018_000004_create_canvas_account_tables::Migration),
+            Box::new(m20261018_000005_create_dead_letter_transaction_table::Migration),
+            Box::new(m20261018_000006_add_contract_address_columns::Migration),
+            Box::new(m20261018_000007_create_contract_layout_table::Migration),
+            Box::new(m20261018_000008_create_canvas_snapshot_table::Migration),
+            Box::new(m20261019_000009_add_bootstrapped_column::Migration),
+            Box::new(m20261019_000010_add_created_with_canvas_column::Migration),
+        ]
     }
 }
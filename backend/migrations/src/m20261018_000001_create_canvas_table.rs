@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the canvas table. This mirrors the Canvas resource (and the
+        // CanvasConfig within it) from the canvas_token module.
+        manager
+            .create_table(
+                Table::create()
+                    .table(Canvas::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Canvas::CanvasAddress)
+                            .string()
+                            .primary_key()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Canvas::Width).integer().not_null())
+                    .col(ColumnDef::new(Canvas::Height).integer().not_null())
+                    .col(
+                        ColumnDef::new(Canvas::PerAccountTimeoutS)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Canvas::DefaultColor)
+                            .small_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Canvas::MaxNumberOfPixelsPerDraw)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Canvas::DrawEnabledForNonAdmin)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Canvas::CreatedAtS).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Canvas::LastUpdatedVersion)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Canvas::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Canvas {
+    Table,
+    CanvasAddress,
+    Width,
+    Height,
+    PerAccountTimeoutS,
+    DefaultColor,
+    MaxNumberOfPixelsPerDraw,
+    DrawEnabledForNonAdmin,
+    CreatedAtS,
+    LastUpdatedVersion,
+}
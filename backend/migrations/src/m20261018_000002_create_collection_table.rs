@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the collection table. This combines the CollectionConfig resource
+        // from the canvas_collection module with the Collection and ObjectCore
+        // resources from the framework, which all live at the collection address.
+        // Everything other than the address is nullable since each txn only writes
+        // some of these resources.
+        manager
+            .create_table(
+                Table::create()
+                    .table(Collection::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Collection::CollectionAddress)
+                            .string()
+                            .primary_key()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Collection::Name).string())
+                    .col(ColumnDef::new(Collection::Description).string())
+                    .col(ColumnDef::new(Collection::Uri).string())
+                    .col(ColumnDef::new(Collection::CreatorAddress).string())
+                    .col(ColumnDef::new(Collection::OwnerAddress).string())
+                    .col(ColumnDef::new(Collection::MaxWidth).integer())
+                    .col(ColumnDef::new(Collection::MaxHeight).integer())
+                    .col(
+                        ColumnDef::new(Collection::LastUpdatedVersion)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Collection::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Collection {
+    Table,
+    CollectionAddress,
+    Name,
+    Description,
+    Uri,
+    CreatorAddress,
+    OwnerAddress,
+    MaxWidth,
+    MaxHeight,
+    LastUpdatedVersion,
+}
@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the paint balance table. There is one row per account, representing
+        // the balance of their primary PAINT store.
+        manager
+            .create_table(
+                Table::create()
+                    .table(PaintBalance::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PaintBalance::AccountAddress)
+                            .string()
+                            .primary_key()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PaintBalance::StoreAddress)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaintBalance::Balance)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaintBalance::LastUpdatedVersion)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create the paint activity table. There is one row per deposit / withdraw
+        // event on a PAINT store.
+        manager
+            .create_table(
+                Table::create()
+                    .table(PaintActivity::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PaintActivity::TransactionVersion)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaintActivity::EventIndex)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaintActivity::AccountAddress)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaintActivity::StoreAddress)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaintActivity::ActivityType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PaintActivity::Amount)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        &mut IndexCreateStatement::new()
+                            .col(PaintActivity::TransactionVersion)
+                            .col(PaintActivity::EventIndex)
+                            .to_owned(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PaintBalance::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(PaintActivity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PaintBalance {
+    Table,
+    AccountAddress,
+    StoreAddress,
+    Balance,
+    LastUpdatedVersion,
+}
+
+#[derive(DeriveIden)]
+enum PaintActivity {
+    Table,
+    TransactionVersion,
+    EventIndex,
+    AccountAddress,
+    StoreAddress,
+    ActivityType,
+    Amount,
+}
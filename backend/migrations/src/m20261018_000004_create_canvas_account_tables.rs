@@ -0,0 +1,141 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The owner of the canvas object is the super admin of the canvas.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Canvas::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Canvas::OwnerAddress).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create the canvas account table. This tracks the per account state from the
+        // tables in the Canvas resource, which is what the contract uses to decide if
+        // an account can draw.
+        manager
+            .create_table(
+                Table::create()
+                    .table(CanvasAccount::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CanvasAccount::CanvasAddress)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CanvasAccount::AccountAddress)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CanvasAccount::LastContributionS).big_integer())
+                    .col(
+                        ColumnDef::new(CanvasAccount::IsAdmin)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(CanvasAccount::IsUnlimitedArtist)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(CanvasAccount::LastUpdatedVersion)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        &mut IndexCreateStatement::new()
+                            .col(CanvasAccount::CanvasAddress)
+                            .col(CanvasAccount::AccountAddress)
+                            .to_owned(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create the canvas table handle table. The processor uses this to figure out
+        // which canvas (and which table in it) a table item belongs to.
+        manager
+            .create_table(
+                Table::create()
+                    .table(CanvasTableHandle::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CanvasTableHandle::Handle)
+                            .string()
+                            .primary_key()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CanvasTableHandle::CanvasAddress)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CanvasTableHandle::TableName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CanvasTableHandle::CreatedVersion)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CanvasTableHandle::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(CanvasAccount::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Canvas::Table)
+                    .drop_column(Canvas::OwnerAddress)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Canvas {
+    Table,
+    OwnerAddress,
+}
+
+#[derive(DeriveIden)]
+enum CanvasAccount {
+    Table,
+    CanvasAddress,
+    AccountAddress,
+    LastContributionS,
+    IsAdmin,
+    IsUnlimitedArtist,
+    LastUpdatedVersion,
+}
+
+#[derive(DeriveIden)]
+enum CanvasTableHandle {
+    Table,
+    Handle,
+    CanvasAddress,
+    TableName,
+    CreatedVersion,
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the dead letter table. There is one row per txn a processor skipped
+        // because of an error, see ErrorPolicy in the processor.
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeadLetterTransaction::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DeadLetterTransaction::ProcessorName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetterTransaction::TransactionVersion)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeadLetterTransaction::CanvasAddress).string())
+                    .col(
+                        ColumnDef::new(DeadLetterTransaction::ErrorKind)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetterTransaction::ErrorMessage)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetterTransaction::Action)
+                            .string()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        &mut IndexCreateStatement::new()
+                            .col(DeadLetterTransaction::ProcessorName)
+                            .col(DeadLetterTransaction::TransactionVersion)
+                            .to_owned(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeadLetterTransaction::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum DeadLetterTransaction {
+    Table,
+    ProcessorName,
+    TransactionVersion,
+    CanvasAddress,
+    ErrorKind,
+    ErrorMessage,
+    Action,
+}
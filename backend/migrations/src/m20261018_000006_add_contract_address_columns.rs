@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One processor can index multiple deployments of the contract, so we record
+        // which one each canvas and attribution came from. This is null for rows
+        // written before we started doing this.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Canvas::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Canvas::ContractAddress).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PixelAttribution::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(PixelAttribution::ContractAddress).string(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PixelAttribution::Table)
+                    .drop_column(PixelAttribution::ContractAddress)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Canvas::Table)
+                    .drop_column(Canvas::ContractAddress)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Canvas {
+    Table,
+    ContractAddress,
+}
+
+#[derive(DeriveIden)]
+enum PixelAttribution {
+    Table,
+    ContractAddress,
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the contract layout table. This tracks which layout of the structs in
+        // the canvas_token module is active for each contract deployment, so the
+        // processor knows how to decode them.
+        manager
+            .create_table(
+                Table::create()
+                    .table(ContractLayout::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ContractLayout::ContractAddress)
+                            .string()
+                            .primary_key()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ContractLayout::LayoutVersion)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ContractLayout::LastUpdatedVersion)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ContractLayout::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ContractLayout {
+    Table,
+    ContractAddress,
+    LayoutVersion,
+    LastUpdatedVersion,
+}
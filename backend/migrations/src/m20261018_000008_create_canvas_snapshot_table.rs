@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the canvas snapshot table. There is one row per image of a canvas the
+        // snapshot scheduler archived, see SnapshotScheduler in the flusher.
+        manager
+            .create_table(
+                Table::create()
+                    .table(CanvasSnapshot::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CanvasSnapshot::CanvasAddress)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CanvasSnapshot::TakenAtSecs)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CanvasSnapshot::Version).big_integer())
+                    .col(
+                        ColumnDef::new(CanvasSnapshot::NumPixels)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CanvasSnapshot::Path).string().not_null())
+                    .primary_key(
+                        &mut IndexCreateStatement::new()
+                            .col(CanvasSnapshot::CanvasAddress)
+                            .col(CanvasSnapshot::TakenAtSecs)
+                            .to_owned(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CanvasSnapshot::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CanvasSnapshot {
+    Table,
+    CanvasAddress,
+    TakenAtSecs,
+    Version,
+    NumPixels,
+    Path,
+}
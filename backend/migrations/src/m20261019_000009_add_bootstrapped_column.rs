@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Canvases bootstrapped from a state snapshot can't be rebuilt by reprocessing
+        // txns, since we never saw the txn that created them, so we record which
+        // table handles came from a snapshot.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CanvasTableHandle::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(CanvasTableHandle::Bootstrapped)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CanvasTableHandle::Table)
+                    .drop_column(CanvasTableHandle::Bootstrapped)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CanvasTableHandle {
+    Table,
+    Bootstrapped,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // We learn about table handles from any txn that writes the Canvas resource,
+        // so the first handle of a canvas isn't necessarily from the txn that created
+        // it. We record which handles are. This is null for rows written before we
+        // started doing this, since we can't tell.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CanvasTableHandle::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(CanvasTableHandle::CreatedWithCanvas).boolean(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CanvasTableHandle::Table)
+                    .drop_column(CanvasTableHandle::CreatedWithCanvas)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CanvasTableHandle {
+    Table,
+    CreatedWithCanvas,
+}
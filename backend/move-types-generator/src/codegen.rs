@@ -0,0 +1,99 @@
+use crate::schema::{FieldType, Schema};
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt::Write,
+};
+
+const HEADER: &str = "// DO NOT EDIT THIS FILE
+// This file was generated by move-types-generator, see the README for how to
+// regenerate it.
+use aptos_move_graphql_scalars::*;
+use serde::{Deserialize, Serialize};
+";
+
+/// Rust keywords that can't be used as raw identifiers, so we rename the field.
+const UNRAWABLE_KEYWORDS: &[&str] = &["self", "Self", "super", "crate"];
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Find the given types and every type they depend on.
+pub fn resolve_types(schema: &Schema, root_types: &[&str]) -> Result<BTreeSet<String>> {
+    let mut resolved = BTreeSet::new();
+    let mut queue: VecDeque<String> = root_types.iter().map(|name| name.to_string()).collect();
+    while let Some(name) = queue.pop_front() {
+        if resolved.contains(&name) {
+            continue;
+        }
+        let fields = schema
+            .types
+            .get(&name)
+            .with_context(|| format!("Type {} is not in the schema", name))?;
+        for field in fields {
+            let base_name = field.field_type.base_name();
+            if schema.types.contains_key(base_name) {
+                queue.push_back(base_name.to_string());
+            }
+        }
+        resolved.insert(name);
+    }
+    Ok(resolved)
+}
+
+/// Generate the contents of objects.rs for the given types. The output is already
+/// formatted the way rustfmt would format it, so check mode can compare it as is.
+pub fn generate(schema: &Schema, types: &BTreeSet<String>) -> Result<String> {
+    let mut out = HEADER.to_string();
+    for name in types {
+        let fields = schema
+            .types
+            .get(name)
+            .with_context(|| format!("Type {} is not in the schema", name))?;
+        writeln!(out, "#[derive(Serialize, Deserialize, Debug, Clone)]")?;
+        writeln!(out, "pub struct {} {{", name)?;
+        for field in fields {
+            let rust_type = rust_type(schema, &field.field_type)
+                .with_context(|| format!("Failed to generate field {}.{}", name, field.name))?;
+            if UNRAWABLE_KEYWORDS.contains(&field.name.as_str()) {
+                writeln!(out, "    #[serde(rename = \"{}\")]", field.name)?;
+                writeln!(out, "    pub {}_: {},", field.name, rust_type)?;
+            } else if KEYWORDS.contains(&field.name.as_str()) {
+                writeln!(out, "    pub r#{}: {},", field.name, rust_type)?;
+            } else {
+                writeln!(out, "    pub {}: {},", field.name, rust_type)?;
+            }
+        }
+        writeln!(out, "}}")?;
+    }
+    Ok(out)
+}
+
+fn rust_type(schema: &Schema, field_type: &FieldType) -> Result<String> {
+    let (rust_type, nullable) = match field_type {
+        FieldType::Named { name, nullable } => {
+            let rust_type = match name.as_str() {
+                "Boolean" => "bool".to_string(),
+                "String" => "String".to_string(),
+                name if schema.scalars.iter().any(|scalar| scalar == name) => name.to_string(),
+                name if schema.types.contains_key(name) => name.to_string(),
+                name => bail!("Unknown type {}", name),
+            };
+            (rust_type, *nullable)
+        },
+        FieldType::List { inner, nullable } => {
+            (format!("Vec<{}>", rust_type(schema, inner)?), *nullable)
+        },
+    };
+    // Move has no null, so we never expect this, but the Move Option type is called
+    // Option, so we must use the full path if we do.
+    if nullable {
+        return Ok(format!("std::option::Option<{}>", rust_type));
+    }
+    Ok(rust_type)
+}
@@ -0,0 +1,101 @@
+// This generates move-types/src/objects.rs from the GraphQL schema representation of
+// the package ABI. We only generate the types the backend actually decodes (and the
+// types they depend on), rather than every struct in the framework.
+
+mod codegen;
+mod move_source;
+mod schema;
+
+use crate::{
+    codegen::{generate, resolve_types},
+    move_source::{find_drift, read_move_structs},
+    schema::Schema,
+};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::{fs, path::PathBuf};
+
+/// The types we generate. Anything these depend on is generated too. If you use a
+/// new type from move-types in the processor, add it here.
+const ROOT_TYPES: &[&str] = &[
+    // canvas_token
+    "Canvas",
+    // canvas_collection
+    "CollectionConfig",
+    // Framework
+    "Collection",
+    "Entry",
+    "FungibleStore",
+    "Object",
+    "ObjectCore",
+    "_0x1__fungible_asset__DepositEvent",
+    "_0x1__fungible_asset__WithdrawEvent",
+];
+
+/// The default paths assume you run this from the root of the backend workspace.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// The GraphQL schema representation of the package ABI, generated with
+    /// `aptos move generate schema`.
+    #[clap(long, default_value = "../move/schema.graphql")]
+    pub schema_path: PathBuf,
+
+    /// The Move sources of the package.
+    #[clap(long, default_value = "../move/sources")]
+    pub move_sources_path: PathBuf,
+
+    /// Where to write the generated types.
+    #[clap(long, default_value = "move-types/src/objects.rs")]
+    pub output_path: PathBuf,
+
+    /// Rather than writing the generated types, fail if they differ from what is in
+    /// `output_path` or if the schema has drifted from the Move sources.
+    #[clap(long)]
+    pub check: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let schema = fs::read_to_string(&args.schema_path)
+        .with_context(|| format!("Failed to read {}", args.schema_path.display()))?;
+    let schema = Schema::parse(&schema)?;
+    let types = resolve_types(&schema, ROOT_TYPES)?;
+
+    // The schema is generated from the Move sources separately, so make sure it
+    // hasn't fallen behind them before we generate anything from it.
+    let move_structs = read_move_structs(&args.move_sources_path)?;
+    let drift = find_drift(&schema, &types, &move_structs);
+    if !drift.is_empty() {
+        bail!(
+            "The schema at {} has drifted from the Move sources at {}, regenerate it:\n{}",
+            args.schema_path.display(),
+            args.move_sources_path.display(),
+            drift.join("\n")
+        );
+    }
+
+    let generated = generate(&schema, &types)?;
+
+    if args.check {
+        let existing = fs::read_to_string(&args.output_path)
+            .with_context(|| format!("Failed to read {}", args.output_path.display()))?;
+        if existing != generated {
+            bail!(
+                "{} is out of date, run move-types-generator without --check to regenerate it",
+                args.output_path.display()
+            );
+        }
+        println!("{} is up to date", args.output_path.display());
+        return Ok(());
+    }
+
+    fs::write(&args.output_path, generated)
+        .with_context(|| format!("Failed to write {}", args.output_path.display()))?;
+    println!(
+        "Wrote {} types to {}",
+        types.len(),
+        args.output_path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use crate::schema::{unqualified_name, FieldType, Schema};
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::{collections::BTreeSet, fs, path::Path};
+
+/// A struct as declared in the Move source.
+#[derive(Debug)]
+pub struct MoveStruct {
+    pub module: String,
+    pub name: String,
+    /// The names of the generic type parameters, if any.
+    pub type_params: Vec<String>,
+    /// Field names and their types, as written in the source.
+    pub fields: Vec<(String, String)>,
+}
+
+/// Read every struct declared in the .move files under the given directory.
+pub fn read_move_structs(sources_path: &Path) -> Result<Vec<MoveStruct>> {
+    let mut structs = vec![];
+    for path in find_move_files(sources_path)? {
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        structs.extend(
+            parse_move_structs(&source)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+        );
+    }
+    Ok(structs)
+}
+
+fn find_move_files(path: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let mut files = vec![];
+    for entry in
+        fs::read_dir(path).with_context(|| format!("Failed to read dir {}", path.display()))?
+    {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(find_move_files(&path)?);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "move")
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn parse_move_structs(source: &str) -> Result<Vec<MoveStruct>> {
+    // Strip comments first, doc comments on fields would otherwise get in the way.
+    let block_comment = Regex::new(r"(?s)/\*.*?\*/")?;
+    let line_comment = Regex::new(r"//[^\n]*")?;
+    let source = block_comment.replace_all(source, "");
+    let source = line_comment.replace_all(&source, "");
+
+    let module_regex = Regex::new(r"\bmodule\s+\w+::(\w+)")?;
+    let struct_regex =
+        Regex::new(r"\bstruct\s+(\w+)\s*(<[^{]*?>)?\s*(?:has\s+[\w\s,]+)?\{([^}]*)\}")?;
+
+    let modules: Vec<(usize, String)> = module_regex
+        .captures_iter(&source)
+        .map(|captures| (captures.get(0).unwrap().start(), captures[1].to_string()))
+        .collect();
+
+    let mut structs = vec![];
+    for captures in struct_regex.captures_iter(&source) {
+        let start = captures.get(0).unwrap().start();
+        let module = modules
+            .iter()
+            .rev()
+            .find(|(module_start, _)| *module_start < start)
+            .map(|(_, module)| module.clone())
+            .context("Struct declared outside of a module")?;
+        let type_params = captures
+            .get(2)
+            .map(|type_params| {
+                let type_params = type_params.as_str();
+                split_top_level(&type_params[1..type_params.len() - 1])
+                    .into_iter()
+                    .filter_map(|param| {
+                        let param = param.trim().trim_start_matches("phantom ").trim();
+                        param.split(':').next().map(|name| name.trim().to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut fields = vec![];
+        for field in split_top_level(&captures[3]) {
+            let field = field.trim();
+            if field.is_empty() {
+                continue;
+            }
+            let (name, move_type) = field
+                .split_once(':')
+                .with_context(|| format!("Failed to parse field {:?}", field))?;
+            fields.push((name.trim().to_string(), move_type.trim().to_string()));
+        }
+        structs.push(MoveStruct {
+            module,
+            name: captures[1].to_string(),
+            type_params,
+            fields,
+        });
+    }
+    Ok(structs)
+}
+
+/// Split on commas that aren't inside angle brackets.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Compare the structs in the Move source with the generated types they correspond
+/// to, returning a description of every difference. Structs we don't generate a type
+/// for are ignored, since drift there doesn't affect us.
+pub fn find_drift(
+    schema: &Schema,
+    types: &BTreeSet<String>,
+    move_structs: &[MoveStruct],
+) -> Vec<String> {
+    let mut drift = vec![];
+    for move_struct in move_structs {
+        let qualified_suffix = format!("__{}__{}", move_struct.module, move_struct.name);
+        let type_name = match types
+            .iter()
+            .find(|name| **name == move_struct.name || name.ends_with(&qualified_suffix))
+        {
+            Some(type_name) => type_name,
+            None => continue,
+        };
+        let fields = &schema.types[type_name];
+        let context = format!(
+            "{}::{} (type {})",
+            move_struct.module, move_struct.name, type_name
+        );
+
+        let schema_names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+        let move_names: Vec<&str> = move_struct
+            .fields
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if schema_names != move_names {
+            drift.push(format!(
+                "{}: schema has fields {:?} but the source has {:?}",
+                context, schema_names, move_names
+            ));
+            continue;
+        }
+
+        for (field, (name, move_type)) in fields.iter().zip(&move_struct.fields) {
+            let expected = move_type_to_field_type(move_type, &move_struct.type_params);
+            let actual = field.field_type.unqualified();
+            if expected != actual {
+                drift.push(format!(
+                    "{}: field {} is {} in the schema but {} ({}) in the source",
+                    context, name, actual, expected, move_type
+                ));
+            }
+        }
+    }
+    drift
+}
+
+/// Work out what type the schema should have for a field, given its Move type.
+fn move_type_to_field_type(move_type: &str, type_params: &[String]) -> FieldType {
+    let move_type = move_type.trim();
+    let named = |name: &str| FieldType::Named {
+        name: name.to_string(),
+        nullable: false,
+    };
+    if let Some(inner) = move_type
+        .strip_prefix("vector<")
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        return FieldType::List {
+            inner: Box::new(move_type_to_field_type(inner, type_params)),
+            nullable: false,
+        };
+    }
+    match move_type {
+        "u8" => named("U8"),
+        "u16" => named("U16"),
+        "u32" => named("U32"),
+        "u64" => named("U64"),
+        "u128" => named("U128"),
+        "u256" => named("U256"),
+        "bool" => named("Boolean"),
+        "address" => named("Address"),
+        move_type if type_params.iter().any(|param| param == move_type) => named("Any"),
+        move_type => {
+            // Drop any generics and module path, e.g. token::MutatorRef.
+            let name = move_type.split('<').next().unwrap_or(move_type).trim();
+            let name = name.rsplit("::").next().unwrap_or(name);
+            named(unqualified_name(name))
+        },
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{bail, Context, Result};
+use std::{collections::BTreeMap, fmt};
+
+/// The parts of the GraphQL schema representation of a package ABI that we care
+/// about. The schema only contains object types and scalars, one object type per
+/// Move struct.
+#[derive(Debug, Default)]
+pub struct Schema {
+    pub types: BTreeMap<String, Vec<Field>>,
+    pub scalars: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Field {
+    pub name: String,
+    pub field_type: FieldType,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FieldType {
+    Named {
+        name: String,
+        nullable: bool,
+    },
+    List {
+        inner: Box<FieldType>,
+        nullable: bool,
+    },
+}
+
+impl Schema {
+    pub fn parse(schema: &str) -> Result<Self> {
+        let mut parsed = Schema::default();
+        let mut current: Option<(String, Vec<Field>)> = None;
+        for (i, line) in schema.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_context = || format!("Failed to parse line {} of schema: {}", i + 1, line);
+            match current.as_mut() {
+                None => {
+                    if let Some(name) = line.strip_prefix("scalar ") {
+                        parsed.scalars.push(name.trim().to_string());
+                    } else if let Some(rest) = line.strip_prefix("type ") {
+                        let name = rest
+                            .strip_suffix('{')
+                            .with_context(line_context)?
+                            .trim()
+                            .to_string();
+                        current = Some((name, vec![]));
+                    } else {
+                        bail!("{}", line_context());
+                    }
+                },
+                Some((_, fields)) => {
+                    if line == "}" {
+                        let (name, fields) = current.take().unwrap();
+                        parsed.types.insert(name, fields);
+                        continue;
+                    }
+                    let (name, field_type) = line.split_once(':').with_context(line_context)?;
+                    fields.push(Field {
+                        name: name.trim().to_string(),
+                        field_type: FieldType::parse(field_type.trim())
+                            .with_context(line_context)?,
+                    });
+                },
+            }
+        }
+        if let Some((name, _)) = current {
+            bail!("Type {} in schema is never closed", name);
+        }
+        Ok(parsed)
+    }
+}
+
+impl FieldType {
+    fn parse(field_type: &str) -> Result<Self> {
+        let (field_type, nullable) = match field_type.strip_suffix('!') {
+            Some(field_type) => (field_type, false),
+            None => (field_type, true),
+        };
+        if let Some(inner) = field_type.strip_prefix('[') {
+            let inner = inner.strip_suffix(']').context("Unclosed list type")?;
+            return Ok(FieldType::List {
+                inner: Box::new(FieldType::parse(inner)?),
+                nullable,
+            });
+        }
+        if field_type.is_empty() || !field_type.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            bail!("Invalid type name {:?}", field_type);
+        }
+        Ok(FieldType::Named {
+            name: field_type.to_string(),
+            nullable,
+        })
+    }
+
+    /// The name of the type at the bottom of any lists.
+    pub fn base_name(&self) -> &str {
+        match self {
+            FieldType::Named { name, .. } => name,
+            FieldType::List { inner, .. } => inner.base_name(),
+        }
+    }
+
+    /// Types that clash with another type of the same name are given the fully
+    /// qualified name, e.g. `_0x4__token__MutatorRef`. This strips that back to just
+    /// the struct name, which is what the Move source uses.
+    pub fn unqualified(&self) -> FieldType {
+        match self {
+            FieldType::Named { name, nullable } => FieldType::Named {
+                name: unqualified_name(name).to_string(),
+                nullable: *nullable,
+            },
+            FieldType::List { inner, nullable } => FieldType::List {
+                inner: Box::new(inner.unqualified()),
+                nullable: *nullable,
+            },
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nullable = match self {
+            FieldType::Named { name, nullable } => {
+                write!(f, "{}", name)?;
+                nullable
+            },
+            FieldType::List { inner, nullable } => {
+                write!(f, "[{}]", inner)?;
+                nullable
+            },
+        };
+        if !nullable {
+            write!(f, "!")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn unqualified_name(name: &str) -> &str {
+    match name.rsplit_once("__") {
+        Some((_, name)) if name.starts_with(|c: char| c.is_ascii_uppercase()) => name,
+        _ => name,
+    }
+}
@@ -5,12 +5,7 @@
     clippy::clone_on_copy,
     clippy::too_many_arguments
 )]
-// DO NOT EDIT THIS FILE
-// This file was generated by https://github.com/tacogips/async-graphql-reverse
+// DO NOT EDIT objects.rs
+// It is generated by move-types-generator, see the README for how to regenerate it.
 mod objects;
 pub use objects::*;
-// Skip building schema_builder() due to no query defined.
-// // example schema_builder()
-// pub fn schema_builder() -> SchemaBuilder<YourQueryType, EmptyMutation, EmptySubscription> {
-//     Schema::build(YourQueryType, EmptyMutation, EmptySubscription)
-// }
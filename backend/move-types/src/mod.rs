@@ -1,16 +0,0 @@
-#[allow(
-    dead_code,
-    non_camel_case_types,
-    clippy::upper_case_acronyms,
-    clippy::clone_on_copy,
-    clippy::too_many_arguments
-)]
-// DO NOT EDIT THIS FILE
-// This file was generated by https://github.com/tacogips/async-graphql-reverse
-mod objects;
-pub use objects::*;
-// Skip building schema_builder() due to no query defined.
-// // example schema_builder()
-// pub fn schema_builder() -> SchemaBuilder<YourQueryType, EmptyMutation, EmptySubscription> {
-//     Schema::build(YourQueryType, EmptyMutation, EmptySubscription)
-// }
@@ -0,0 +1,121 @@
+use super::{CreateCanvasIntent, HardcodedColor, PixelStorageTrait, WritePixelIntent};
+use anyhow::{Context, Result};
+use aptos_move_graphql_scalars::Address;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// A change made to the pixel storage. The MmapPixelStorage publishes these as it
+/// is written to, so replicas can follow along and keep their own copy of the
+/// canvases. Applying every delta, in order, to a copy of the canvases taken after
+/// subscribing leaves it the same as the original.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PixelDelta {
+    CanvasCreated {
+        canvas_address: String,
+        width: u16,
+        height: u16,
+        default_color: u8,
+        version: u64,
+    },
+    PixelsWritten {
+        canvas_address: String,
+        /// The index and color of each pixel.
+        pixels: Vec<(u32, u8)>,
+        version: u64,
+    },
+    CanvasDeleted {
+        canvas_address: String,
+    },
+    Reset,
+}
+
+impl PixelDelta {
+    pub fn canvas_created(intent: &CreateCanvasIntent) -> Self {
+        PixelDelta::CanvasCreated {
+            canvas_address: intent.canvas_address.to_string(),
+            width: intent.width,
+            height: intent.height,
+            default_color: intent.default_color.clone() as u8,
+            version: intent.version,
+        }
+    }
+
+    /// Build a delta from intents that all write to the given canvas.
+    pub fn pixels_written(canvas_address: &Address, intents: &[WritePixelIntent]) -> Self {
+        PixelDelta::PixelsWritten {
+            canvas_address: canvas_address.to_string(),
+            pixels: intents
+                .iter()
+                .map(|intent| (intent.index, intent.color.clone() as u8))
+                .collect(),
+            version: intents
+                .iter()
+                .map(|intent| intent.version)
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The version of the txn that made the change, if it came from one.
+    pub fn version(&self) -> Option<u64> {
+        match self {
+            PixelDelta::CanvasCreated { version, .. } => Some(*version),
+            PixelDelta::PixelsWritten { version, .. } => Some(*version),
+            PixelDelta::CanvasDeleted { .. } | PixelDelta::Reset => None,
+        }
+    }
+
+    /// Make the same change to the given storage.
+    pub async fn apply(&self, pixel_storage: &dyn PixelStorageTrait) -> Result<()> {
+        match self {
+            PixelDelta::CanvasCreated {
+                canvas_address,
+                width,
+                height,
+                default_color,
+                version,
+            } => {
+                pixel_storage
+                    .create_canvas(CreateCanvasIntent {
+                        canvas_address: parse_address(canvas_address)?,
+                        width: *width,
+                        height: *height,
+                        default_color: HardcodedColor::try_from(*default_color)?,
+                        version: *version,
+                    })
+                    .await
+            },
+            PixelDelta::PixelsWritten {
+                canvas_address,
+                pixels,
+                version,
+            } => {
+                let canvas_address = parse_address(canvas_address)?;
+                let intents = pixels
+                    .iter()
+                    .map(|(index, color)| {
+                        Ok(WritePixelIntent {
+                            canvas_address,
+                            index: *index,
+                            color: HardcodedColor::try_from(*color)?,
+                            version: *version,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                pixel_storage.write_pixels(intents).await
+            },
+            PixelDelta::CanvasDeleted { canvas_address } => {
+                pixel_storage
+                    .delete_canvas(&parse_address(canvas_address)?)
+                    .await
+            },
+            PixelDelta::Reset => pixel_storage.reset().await,
+        }
+    }
+}
+
+fn parse_address(canvas_address: &str) -> Result<Address> {
+    Address::from_str(canvas_address)
+        .with_context(|| format!("Invalid canvas address {}", canvas_address))
+}
@@ -0,0 +1,42 @@
+use aptos_move_graphql_scalars::Address;
+use std::{fmt, path::PathBuf};
+
+/// Errors from the pixel storage that callers might want to handle specifically.
+#[derive(Debug)]
+pub enum PixelStorageError {
+    /// A write referred to a pixel outside of the canvas. None of the pixels in the
+    /// write were written.
+    IndexOutOfBounds {
+        canvas_address: Address,
+        index: u32,
+        num_pixels: u64,
+    },
+    /// The canvas file doesn't match the format we expect.
+    CorruptCanvasFile { path: PathBuf, reason: String },
+    /// There is no canvas with this address.
+    CanvasNotFound { canvas_address: Address },
+}
+
+impl fmt::Display for PixelStorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PixelStorageError::IndexOutOfBounds {
+                canvas_address,
+                index,
+                num_pixels,
+            } => write!(
+                f,
+                "Index {} is out of bounds for canvas {} with {} pixels",
+                index, canvas_address, num_pixels
+            ),
+            PixelStorageError::CorruptCanvasFile { path, reason } => {
+                write!(f, "Canvas file {} is corrupt: {}", path.display(), reason)
+            },
+            PixelStorageError::CanvasNotFound { canvas_address } => {
+                write!(f, "Failed to find canvas {}", canvas_address)
+            },
+        }
+    }
+}
+
+impl std::error::Error for PixelStorageError {}
@@ -1,8 +1,15 @@
+mod delta;
+mod error;
+mod memory;
+mod metrics;
 mod mmap;
 mod utils;
 
 use anyhow::Result;
 use aptos_move_graphql_scalars::Address;
+pub use delta::PixelDelta;
+pub use error::PixelStorageError;
+pub use memory::MemoryPixelStorage;
 pub use mmap::{MmapPixelStorage, MmapPixelStorageConfig};
 use std::{collections::HashMap, fmt::Debug};
 
@@ -11,8 +18,35 @@ use std::{collections::HashMap, fmt::Debug};
 pub trait PixelStorageTrait: Debug + Send + Sync + 'static {
     async fn create_canvas(&self, intent: CreateCanvasIntent) -> Result<()>;
     async fn write_pixels(&self, intent: Vec<WritePixelIntent>) -> Result<()>;
+    async fn canvas_exists(&self, canvas_address: &Address) -> Result<bool>;
+    /// Delete a canvas, e.g. so it can be rebuilt. This is a no-op if the canvas
+    /// doesn't exist.
+    async fn delete_canvas(&self, canvas_address: &Address) -> Result<()>;
     async fn get_canvas_as_png(&self, canvas_address: &Address) -> Result<Vec<u8>>;
+    /// Like `get_canvas_as_png`, but the image is read along with its version while
+    /// holding the lock on the canvas. Fails with `PixelStorageError::CanvasNotFound`
+    /// if the canvas doesn't exist.
+    async fn get_canvas_as_png_with_version(&self, canvas_address: &Address) -> Result<CanvasPng>;
     async fn get_canvases_as_pngs(&self) -> Result<HashMap<Address, Vec<u8>>>;
+    /// Like `get_canvases_as_pngs`, but each image is read along with its version
+    /// while holding the lock on the canvas, so the version is exactly that of the
+    /// last txn reflected in the image.
+    async fn get_canvases_as_pngs_with_versions(&self) -> Result<HashMap<Address, CanvasPng>>;
+    /// The version of the last txn written to each canvas. This is only tracked
+    /// since startup, canvases that haven't been written to since then aren't
+    /// included.
+    async fn get_canvas_versions(&self) -> Result<HashMap<Address, u64>>;
+    /// The chain the canvases were built from. We check this on startup so we never
+    /// apply txns from one chain to canvases built from another.
+    async fn read_chain_id(&self) -> Result<Option<u8>>;
+    async fn write_chain_id(&self, chain_id: u8) -> Result<()>;
+    /// Check the storage is usable, e.g. for health checks.
+    async fn check_health(&self) -> Result<()>;
+    /// Make sure everything written so far is on disk, e.g. before shutting down.
+    async fn flush_to_disk(&self) -> Result<()>;
+    /// Delete every canvas and the chain ID, e.g. so the processor can start again
+    /// against a different chain.
+    async fn reset(&self) -> Result<()>;
 }
 
 // In the contract we just use different int values to represent different colors,
@@ -29,18 +63,20 @@ pub enum HardcodedColor {
     Violet = 7,
 }
 
-impl From<u8> for HardcodedColor {
-    fn from(color: u8) -> Self {
+impl TryFrom<u8> for HardcodedColor {
+    type Error = anyhow::Error;
+
+    fn try_from(color: u8) -> Result<Self> {
         match color {
-            0 => HardcodedColor::Black,
-            1 => HardcodedColor::White,
-            2 => HardcodedColor::Blue,
-            3 => HardcodedColor::Green,
-            4 => HardcodedColor::Yellow,
-            5 => HardcodedColor::Orange,
-            6 => HardcodedColor::Red,
-            7 => HardcodedColor::Violet,
-            _ => panic!("Invalid color: {}", color),
+            0 => Ok(HardcodedColor::Black),
+            1 => Ok(HardcodedColor::White),
+            2 => Ok(HardcodedColor::Blue),
+            3 => Ok(HardcodedColor::Green),
+            4 => Ok(HardcodedColor::Yellow),
+            5 => Ok(HardcodedColor::Orange),
+            6 => Ok(HardcodedColor::Red),
+            7 => Ok(HardcodedColor::Violet),
+            _ => anyhow::bail!("Invalid color: {}", color),
         }
     }
 }
@@ -91,6 +127,15 @@ impl From<&HardcodedColor> for RgbColor {
     }
 }
 
+/// An image of a canvas.
+#[derive(Clone, Debug)]
+pub struct CanvasPng {
+    pub png_data: Vec<u8>,
+    /// The version of the last txn reflected in the image. This is only known for
+    /// canvases written to since startup.
+    pub version: Option<u64>,
+}
+
 /// All the information necessary to write a Pixel to storage.
 #[derive(Clone, Debug)]
 pub struct WritePixelIntent {
@@ -98,6 +143,8 @@ pub struct WritePixelIntent {
     pub canvas_address: Address,
     pub index: u32,
     pub color: HardcodedColor,
+    /// The version of the txn that wrote the pixel.
+    pub version: u64,
 }
 
 /// All the information necessary to create a Canvas in storage.
@@ -108,4 +155,6 @@ pub struct CreateCanvasIntent {
     pub width: u16,
     pub height: u16,
     pub default_color: HardcodedColor,
+    /// The version of the txn that created the canvas.
+    pub version: u64,
 }
@@ -0,0 +1,252 @@
+use super::{
+    utils::get_image, CanvasPng, CreateCanvasIntent, PixelStorageError, PixelStorageTrait,
+    WritePixelIntent,
+};
+use crate::{metrics::PNG_ENCODE_LATENCY, RgbColor};
+use anyhow::{Context, Result};
+use aptos_move_graphql_scalars::Address;
+use image::ImageFormat;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// A canvas held in memory, stored as 3 bytes (r, g, b) per pixel like the canvas
+/// files of the MmapPixelStorage, minus the width and height at the end.
+#[derive(Clone, Debug)]
+struct MemoryCanvas {
+    width: u64,
+    height: u64,
+    pixels: Vec<u8>,
+}
+
+/// Keeps the canvases in memory only. Read replicas use this to hold their copy of
+/// the canvases of the primary, which they build from its feed of deltas on startup,
+/// so nothing needs to persist across restarts.
+#[derive(Debug, Default)]
+pub struct MemoryPixelStorage {
+    canvases: Mutex<HashMap<Address, MemoryCanvas>>,
+    /// The version of the last txn written to each canvas, where known.
+    versions: Mutex<HashMap<Address, u64>>,
+    chain_id: Mutex<Option<u8>>,
+}
+
+impl MemoryPixelStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a canvas from a PNG of it, e.g. one the primary sent us, replacing it if
+    /// it already exists.
+    pub async fn insert_canvas_from_png(
+        &self,
+        canvas_address: Address,
+        png_data: &[u8],
+        version: Option<u64>,
+    ) -> Result<()> {
+        let image = image::load_from_memory_with_format(png_data, ImageFormat::Png)
+            .with_context(|| format!("Failed to decode image of canvas {}", canvas_address))?
+            .to_rgb8();
+        let canvas = MemoryCanvas {
+            width: image.width() as u64,
+            height: image.height() as u64,
+            pixels: image.into_raw(),
+        };
+        let mut canvases = self.canvases.lock().await;
+        canvases.insert(canvas_address, canvas);
+        let mut versions = self.versions.lock().await;
+        match version {
+            Some(version) => versions.insert(canvas_address, version),
+            None => versions.remove(&canvas_address),
+        };
+        Ok(())
+    }
+
+    /// Replace all our canvases with those of `other` in one go, so readers never
+    /// see a mix of the two.
+    pub async fn replace_with(&self, other: MemoryPixelStorage) {
+        let mut canvases = self.canvases.lock().await;
+        let mut versions = self.versions.lock().await;
+        *canvases = other.canvases.into_inner();
+        *versions = other.versions.into_inner();
+    }
+
+    /// Read a canvas as a PNG along with its version.
+    async fn get_canvas_png(&self, canvas_address: &Address) -> Result<CanvasPng> {
+        let (data, width, height, version) = {
+            let canvases = self.canvases.lock().await;
+            let canvas = canvases
+                .get(canvas_address)
+                .ok_or(PixelStorageError::CanvasNotFound {
+                    canvas_address: *canvas_address,
+                })?;
+            let data = canvas
+                .pixels
+                .chunks_exact(3)
+                .map(|pixel| RgbColor {
+                    r: pixel[0],
+                    g: pixel[1],
+                    b: pixel[2],
+                })
+                .collect::<Vec<_>>();
+            // We hold the lock on the canvases, so this is the version of the last
+            // txn in the data.
+            let version = self.versions.lock().await.get(canvas_address).copied();
+            (data, canvas.width, canvas.height, version)
+        };
+
+        let timer = PNG_ENCODE_LATENCY.start_timer();
+        let png = get_image(data, width as u32, height as u32)
+            .context("Failed to convert data to a png")?;
+        timer.observe_duration();
+
+        Ok(CanvasPng {
+            png_data: png,
+            version,
+        })
+    }
+
+    async fn set_version(&self, canvas_address: &Address, version: u64) {
+        let mut versions = self.versions.lock().await;
+        let entry = versions.entry(*canvas_address).or_insert(version);
+        *entry = (*entry).max(version);
+    }
+}
+
+#[async_trait::async_trait]
+impl PixelStorageTrait for MemoryPixelStorage {
+    /// Creating a canvas that already exists replaces it with a blank one, like the
+    /// MmapPixelStorage does.
+    async fn create_canvas(&self, intent: CreateCanvasIntent) -> Result<()> {
+        let width = intent.width as u64;
+        let height = intent.height as u64;
+        let color = RgbColor::from(&intent.default_color);
+        let pixels = [color.r, color.g, color.b].repeat((width * height) as usize);
+        let mut canvases = self.canvases.lock().await;
+        canvases.insert(intent.canvas_address, MemoryCanvas {
+            width,
+            height,
+            pixels,
+        });
+        self.versions
+            .lock()
+            .await
+            .insert(intent.canvas_address, intent.version);
+        Ok(())
+    }
+
+    async fn write_pixels(&self, intents: Vec<WritePixelIntent>) -> Result<()> {
+        let mut canvas_to_intents = HashMap::new();
+        for intent in intents.into_iter() {
+            canvas_to_intents
+                .entry(intent.canvas_address)
+                .or_insert_with(Vec::new)
+                .push(intent);
+        }
+
+        for (canvas_address, intents) in canvas_to_intents.into_iter() {
+            {
+                let mut canvases = self.canvases.lock().await;
+                let canvas = canvases
+                    .get_mut(&canvas_address)
+                    .with_context(|| format!("Failed to find canvas {}", canvas_address))?;
+
+                // Check all the pixels are in bounds before writing any of them.
+                let num_pixels = canvas.width * canvas.height;
+                if let Some(intent) = intents
+                    .iter()
+                    .find(|intent| intent.index as u64 >= num_pixels)
+                {
+                    return Err(PixelStorageError::IndexOutOfBounds {
+                        canvas_address,
+                        index: intent.index,
+                        num_pixels,
+                    }
+                    .into());
+                }
+
+                for intent in &intents {
+                    let index = intent.index as usize;
+                    let color = RgbColor::from(&intent.color);
+                    canvas.pixels[index * 3] = color.r;
+                    canvas.pixels[index * 3 + 1] = color.g;
+                    canvas.pixels[index * 3 + 2] = color.b;
+                }
+                // Record the version while we still hold the lock, so readers never
+                // see pixels without their version.
+                if let Some(version) = intents.iter().map(|intent| intent.version).max() {
+                    self.set_version(&canvas_address, version).await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn canvas_exists(&self, canvas_address: &Address) -> Result<bool> {
+        Ok(self.canvases.lock().await.contains_key(canvas_address))
+    }
+
+    async fn delete_canvas(&self, canvas_address: &Address) -> Result<()> {
+        self.canvases.lock().await.remove(canvas_address);
+        self.versions.lock().await.remove(canvas_address);
+        Ok(())
+    }
+
+    async fn get_canvas_as_png(&self, canvas_address: &Address) -> Result<Vec<u8>> {
+        Ok(self.get_canvas_png(canvas_address).await?.png_data)
+    }
+
+    async fn get_canvas_as_png_with_version(&self, canvas_address: &Address) -> Result<CanvasPng> {
+        self.get_canvas_png(canvas_address).await
+    }
+
+    async fn get_canvases_as_pngs(&self) -> Result<HashMap<Address, Vec<u8>>> {
+        Ok(self
+            .get_canvases_as_pngs_with_versions()
+            .await?
+            .into_iter()
+            .map(|(address, png)| (address, png.png_data))
+            .collect())
+    }
+
+    async fn get_canvases_as_pngs_with_versions(&self) -> Result<HashMap<Address, CanvasPng>> {
+        let addresses: Vec<Address> = self.canvases.lock().await.keys().copied().collect();
+        let mut pngs = HashMap::new();
+        for address in addresses {
+            let png = self
+                .get_canvas_png(&address)
+                .await
+                .context(format!("Failed to get canvas {} as a png", address))?;
+            pngs.insert(address, png);
+        }
+        Ok(pngs)
+    }
+
+    async fn get_canvas_versions(&self) -> Result<HashMap<Address, u64>> {
+        Ok(self.versions.lock().await.clone())
+    }
+
+    async fn read_chain_id(&self) -> Result<Option<u8>> {
+        Ok(*self.chain_id.lock().await)
+    }
+
+    async fn write_chain_id(&self, chain_id: u8) -> Result<()> {
+        *self.chain_id.lock().await = Some(chain_id);
+        Ok(())
+    }
+
+    async fn check_health(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// There is no disk, so there is nothing to flush.
+    async fn flush_to_disk(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn reset(&self) -> Result<()> {
+        self.canvases.lock().await.clear();
+        self.versions.lock().await.clear();
+        *self.chain_id.lock().await = None;
+        Ok(())
+    }
+}
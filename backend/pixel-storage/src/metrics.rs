@@ -0,0 +1,12 @@
+use once_cell::sync::Lazy;
+use prometheus::{register_histogram, Histogram};
+
+/// Encoding a canvas as a PNG is the most expensive thing we do with the pixel data,
+/// and it happens for every canvas on every flush.
+pub static PNG_ENCODE_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "pixel_storage_png_encode_latency_secs",
+        "Time taken to encode a canvas as a PNG"
+    )
+    .unwrap()
+});
@@ -1,18 +1,23 @@
-use super::{utils::get_image, CreateCanvasIntent, PixelStorageTrait, WritePixelIntent};
-use crate::RgbColor;
+use super::{
+    utils::get_image, CanvasPng, CreateCanvasIntent, PixelDelta, PixelStorageError,
+    PixelStorageTrait, WritePixelIntent,
+};
+use crate::{metrics::PNG_ENCODE_LATENCY, RgbColor};
 use anyhow::{Context, Result};
 use aptos_move_graphql_scalars::Address;
 use memmap2::MmapMut;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap},
     fs::{File, OpenOptions},
     io::Write,
+    os::fd::AsRawFd,
     path::{Path, PathBuf},
+    str::FromStr,
     sync::Arc,
 };
-use tokio::sync::Mutex;
-use tracing::{error, info};
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn};
 
 // There could be an alternate implementation where instead of using the mmap, for
 // every pixel we read the png, update the pixel, and write the png back to disk.
@@ -21,8 +26,11 @@ use tracing::{error, info};
 // Note: The POSIX standard states that when a process dies, no matter how (even if
 // it receives SIGKILL) the kernel will eventually flush all the writes in the mmap
 // to disk, as long as the mmap is created using MAP_SHARED (which it is, because we
-// use `map_mut` when creating the mmap). So there should be no need to manually flush
-// the mmap on shutdown.
+// use `map_mut` when creating the mmap). That only holds if the kernel keeps
+// running though. If the host crashes or loses power, whatever dirty pages haven't
+// been written back yet are lost. So we flush explicitly on shutdown (see
+// `flush_to_disk`), which bounds what we lose if the host goes down right after,
+// e.g. when the node is being drained.
 
 // Note: Everything we do here is synchronous, so we could consider making the trait
 // require non async functions. If that were the case, we could safely use std Mutex
@@ -34,28 +42,184 @@ use tracing::{error, info};
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct MmapPixelStorageConfig {
+    /// Where the canvas files live. Only one process can use this at a time.
     pub storage_directory: PathBuf,
+    /// How many deltas a subscriber to the delta feed, e.g. a read replica, can fall
+    /// behind by before it is cut off and has to start again from a fresh snapshot.
+    #[serde(default = "MmapPixelStorageConfig::default_delta_feed_capacity")]
+    pub delta_feed_capacity: usize,
+}
+
+impl MmapPixelStorageConfig {
+    pub fn default_delta_feed_capacity() -> usize {
+        10_000
+    }
 }
 
 /// Handles creating, updating, and reading canvases.
 #[derive(Debug)]
 pub struct MmapPixelStorage {
     config: MmapPixelStorageConfig,
-    mmaps: Arc<Mutex<HashMap<Address, MmapMut>>>,
+    /// Each mmap has its own lock so different canvases can be written to at the
+    /// same time. The outer lock is only held while looking up or opening an mmap.
+    mmaps: Arc<Mutex<HashMap<Address, Arc<Mutex<MmapMut>>>>>,
+    /// The version of the last txn written to each canvas since startup.
+    versions: Arc<Mutex<HashMap<Address, u64>>>,
+    /// Every change we make is sent here, for anyone following along.
+    deltas: broadcast::Sender<PixelDelta>,
+    /// We hold an exclusive lock on the storage directory for as long as we exist,
+    /// see `lock_storage_directory`.
+    _lock: File,
 }
 
 impl MmapPixelStorage {
-    pub fn new(config: MmapPixelStorageConfig) -> Self {
-        Self {
+    /// This fails if another process is already using the storage directory, e.g.
+    /// if we try to rebuild canvases while the processor is running.
+    pub fn new(config: MmapPixelStorageConfig) -> Result<Self> {
+        let lock = lock_storage_directory(&config.storage_directory)?;
+        let (deltas, _) = broadcast::channel(config.delta_feed_capacity.max(1));
+        Ok(Self {
             config,
             mmaps: Arc::new(Mutex::new(HashMap::new())),
+            versions: Arc::new(Mutex::new(HashMap::new())),
+            deltas,
+            _lock: lock,
+        })
+    }
+
+    /// Get every change made from now on. To build a copy of the canvases, subscribe
+    /// first, then read every canvas, then apply the deltas in order. Some of them
+    /// might already be reflected in what was read but applying them again is fine.
+    pub fn subscribe_to_deltas(&self) -> broadcast::Receiver<PixelDelta> {
+        self.deltas.subscribe()
+    }
+
+    /// Send a delta to the subscribers, if there are any. We only build the delta if
+    /// someone is listening since it copies the pixels.
+    fn publish_delta(&self, build_delta: impl FnOnce() -> PixelDelta) {
+        if self.deltas.receiver_count() > 0 {
+            // This only fails if every subscriber went away in the meantime.
+            let _ = self.deltas.send(build_delta());
         }
     }
 
+    /// The addresses of every canvas on disk, including those we haven't opened
+    /// since startup.
+    pub async fn get_canvas_addresses(&self) -> Result<Vec<Address>> {
+        let directory = &self.config.storage_directory;
+        let entries = std::fs::read_dir(directory)
+            .with_context(|| format!("Failed to read directory {}", directory.display()))?;
+        let mut addresses = vec![];
+        for entry in entries {
+            let path = entry.context("Failed to read directory entry")?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("canvas") {
+                continue;
+            }
+            let stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default();
+            match Address::from_str(stem) {
+                Ok(address) => addresses.push(address),
+                Err(error) => warn!("Ignoring canvas file {}: {:?}", path.display(), error),
+            }
+        }
+        Ok(addresses)
+    }
+
     fn get_filename(&self, canvas_address: &Address) -> PathBuf {
         Path::new(&self.config.storage_directory)
             .join(format!("0x{}.canvas", canvas_address.to_canonical_string()))
     }
+
+    fn get_chain_id_filename(&self) -> PathBuf {
+        Path::new(&self.config.storage_directory).join("chain_id")
+    }
+
+    /// Open the canvas file and mmap it. We check the file is the size we expect
+    /// given the width and height in it, so we don't go on to write pixels into the
+    /// width and height, or past the end of the file.
+    fn open_mmap(&self, canvas_address: &Address) -> Result<MmapMut> {
+        let filename = self.get_filename(canvas_address);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(false)
+            .open(&filename)
+            .with_context(|| format!("Failed to open file {}", filename.display()))?;
+        let mmap = unsafe { MmapMut::map_mut(&file) }
+            .with_context(|| format!("Failed to mmap file {}", filename.display()))?;
+        check_integrity(&mmap).map_err(|reason| PixelStorageError::CorruptCanvasFile {
+            path: filename,
+            reason,
+        })?;
+        Ok(mmap)
+    }
+
+    /// Record that the canvas was written to at the given version, unless we already
+    /// have a later one.
+    async fn set_version(&self, canvas_address: &Address, version: u64) {
+        let mut versions = self.versions.lock().await;
+        let entry = versions.entry(*canvas_address).or_insert(version);
+        *entry = (*entry).max(version);
+    }
+
+    /// Read a canvas as a PNG along with its version.
+    async fn get_canvas_png(&self, canvas_address: &Address) -> Result<CanvasPng> {
+        let (data, width, height, version) = {
+            if !self.canvas_exists(canvas_address).await? {
+                return Err(PixelStorageError::CanvasNotFound {
+                    canvas_address: *canvas_address,
+                }
+                .into());
+            }
+            let mmap = self.get_or_open_mmap(canvas_address).await?;
+            let mmap = mmap.lock().await;
+
+            // Get the width and height from the end of the file.
+            let (width, height) =
+                read_width_and_height(&mmap).context("Failed to read width and height")?;
+
+            // Read the data from the file as a vector of RgbColors.
+            let mut data = Vec::with_capacity((width * height) as usize);
+            for i in 0..width * height {
+                let index = i as usize;
+                data.push(RgbColor {
+                    r: mmap[index * 3],
+                    g: mmap[index * 3 + 1],
+                    b: mmap[index * 3 + 2],
+                });
+            }
+            // We hold the lock on the mmap, so no pixels can be written in the
+            // meantime and this is the version of the last txn in the data.
+            let version = self.versions.lock().await.get(canvas_address).copied();
+            (data, width, height, version)
+        };
+
+        // Convert the data to a png.
+        let timer = PNG_ENCODE_LATENCY.start_timer();
+        let png = get_image(data, width as u32, height as u32)
+            .context("Failed to convert data to a png")?;
+        timer.observe_duration();
+
+        Ok(CanvasPng {
+            png_data: png,
+            version,
+        })
+    }
+
+    /// Get the mmap for a canvas, opening it if this is the first time we've used it.
+    async fn get_or_open_mmap(&self, canvas_address: &Address) -> Result<Arc<Mutex<MmapMut>>> {
+        let mut mmaps = self.mmaps.lock().await;
+        let mmap = match mmaps.entry(*canvas_address) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Arc::new(Mutex::new(
+                self.open_mmap(canvas_address)
+                    .context(format!("Failed to open canvas {}", canvas_address))?,
+            ))),
+        };
+        Ok(mmap.clone())
+    }
 }
 
 #[async_trait::async_trait]
@@ -66,7 +230,12 @@ impl PixelStorageTrait for MmapPixelStorage {
     async fn create_canvas(&self, intent: CreateCanvasIntent) -> Result<()> {
         let filename = self.get_filename(&intent.canvas_address);
         info!("Creating canvas file: {:?}", filename.display());
-        let mut file = File::create(&filename)?;
+        // The canvas might already exist, e.g. if it was cleared, and someone might be
+        // reading its mmap. So we write a new file and swap it in, rather than
+        // truncating the file under them.
+        let temp_filename = filename.with_extension("canvas.tmp");
+        let mut file = File::create(&temp_filename)
+            .with_context(|| format!("Failed to create file {}", temp_filename.display()))?;
 
         let width = intent.width as u64;
         let height = intent.height as u64;
@@ -92,7 +261,23 @@ impl PixelStorageTrait for MmapPixelStorage {
         // Write the data to the file.
         file.write_all(&data)?;
 
+        // Hold the lock so no one can open the old file while we replace it. Anyone
+        // still using the old mmap keeps reading the old file until they're done.
+        let mut mmaps = self.mmaps.lock().await;
+        std::fs::rename(&temp_filename, &filename).with_context(|| {
+            format!(
+                "Failed to rename {} to {}",
+                temp_filename.display(),
+                filename.display()
+            )
+        })?;
+        mmaps.remove(&intent.canvas_address);
+        drop(mmaps);
+
         info!("Created canvas file: {:?}", filename.display());
+        self.set_version(&intent.canvas_address, intent.version)
+            .await;
+        self.publish_delta(|| PixelDelta::canvas_created(&intent));
 
         Ok(())
     }
@@ -114,30 +299,32 @@ impl PixelStorageTrait for MmapPixelStorage {
                 intents_len, canvas_address,
             );
             // Get an existing mmap for the canvas file or initialize a new one.
-            let mut mmaps = self.mmaps.lock().await;
-            let mmap = mmaps.entry(canvas_address).or_insert_with(|| {
-                let filename = self.get_filename(&canvas_address);
-                let file = match OpenOptions::new()
-                    .read(true)
-                    .write(true)
-                    .create(false)
-                    .open(&filename)
-                {
-                    Ok(file) => file,
-                    Err(e) => {
-                        error!("Failed to open file {}: {}", filename.display(), e);
-                        panic!("Failed to open file {}: {}", filename.display(), e);
-                    },
-                };
-                unsafe { MmapMut::map_mut(&file).expect("Failed to mmap file") }
-            });
+            let mmap = self.get_or_open_mmap(&canvas_address).await?;
+            let mut mmap = mmap.lock().await;
             info!(
                 "Got mmap, will write {} pixels to canvas {}",
                 intents_len, canvas_address,
             );
 
+            // Check all the pixels are in bounds before writing any of them.
+            let (width, height) =
+                read_width_and_height(&mmap).context("Failed to read width and height")?;
+            let num_pixels = width * height;
+            if let Some(intent) = intents
+                .iter()
+                .find(|intent| intent.index as u64 >= num_pixels)
+            {
+                return Err(PixelStorageError::IndexOutOfBounds {
+                    canvas_address,
+                    index: intent.index,
+                    num_pixels,
+                }
+                .into());
+            }
+
             // Write the pixels to the file through the mmap.
-            for intent in intents {
+            let version = intents.iter().map(|intent| intent.version).max();
+            for intent in &intents {
                 // We don't use the hardcoded colors at our level, we convert
                 // them into proper rgb colors.
                 let index = intent.index as usize;
@@ -148,38 +335,47 @@ impl PixelStorageTrait for MmapPixelStorage {
             }
 
             info!("Wrote {} pixels to canvas {}", intents_len, canvas_address,);
+            if let Some(version) = version {
+                self.set_version(&canvas_address, version).await;
+            }
+            // We still hold the lock on the mmap, so anyone reading the canvas
+            // either sees these pixels or reads it before the delta is sent.
+            self.publish_delta(|| PixelDelta::pixels_written(&canvas_address, &intents));
         }
 
         Ok(())
     }
 
-    async fn get_canvas_as_png(&self, canvas_address: &Address) -> Result<Vec<u8>> {
-        let (data, width, height) = {
-            let mmaps = self.mmaps.lock().await;
-            let mmap = mmaps.get(canvas_address).context("Failed to find canvas")?;
-
-            // Get the width and height from the end of the file.
-            let (width, height) =
-                read_width_and_height(mmap).context("Failed to read width and height")?;
+    async fn canvas_exists(&self, canvas_address: &Address) -> Result<bool> {
+        if self.mmaps.lock().await.contains_key(canvas_address) {
+            return Ok(true);
+        }
+        Ok(self.get_filename(canvas_address).exists())
+    }
 
-            // Read the data from the file as a vector of RgbColors.
-            let mut data = Vec::with_capacity((width * height) as usize);
-            for i in 0..width * height {
-                let index = i as usize;
-                data.push(RgbColor {
-                    r: mmap[index * 3],
-                    g: mmap[index * 3 + 1],
-                    b: mmap[index * 3 + 2],
-                });
-            }
-            (data, width, height)
-        };
+    async fn delete_canvas(&self, canvas_address: &Address) -> Result<()> {
+        // Hold the lock so no one can open the mmap again while we delete the file.
+        let mut mmaps = self.mmaps.lock().await;
+        mmaps.remove(canvas_address);
+        self.versions.lock().await.remove(canvas_address);
+        let filename = self.get_filename(canvas_address);
+        if filename.exists() {
+            info!("Deleting canvas file: {:?}", filename.display());
+            std::fs::remove_file(&filename)
+                .with_context(|| format!("Failed to delete file {}", filename.display()))?;
+        }
+        self.publish_delta(|| PixelDelta::CanvasDeleted {
+            canvas_address: canvas_address.to_string(),
+        });
+        Ok(())
+    }
 
-        // Convert the data to a png.
-        let png = get_image(data, width as u32, height as u32)
-            .context("Failed to convert data to a png")?;
+    async fn get_canvas_as_png(&self, canvas_address: &Address) -> Result<Vec<u8>> {
+        Ok(self.get_canvas_png(canvas_address).await?.png_data)
+    }
 
-        Ok(png)
+    async fn get_canvas_as_png_with_version(&self, canvas_address: &Address) -> Result<CanvasPng> {
+        self.get_canvas_png(canvas_address).await
     }
 
     /// This function returns all the canvases in the mmap as pngs. We use this for the
@@ -189,6 +385,15 @@ impl PixelStorageTrait for MmapPixelStorage {
     /// use in the flusher, since if nothing has been written since startup, there is
     /// also nothing to flush.
     async fn get_canvases_as_pngs(&self) -> Result<HashMap<Address, Vec<u8>>> {
+        Ok(self
+            .get_canvases_as_pngs_with_versions()
+            .await?
+            .into_iter()
+            .map(|(address, png)| (address, png.png_data))
+            .collect())
+    }
+
+    async fn get_canvases_as_pngs_with_versions(&self) -> Result<HashMap<Address, CanvasPng>> {
         let mut pngs = HashMap::new();
         let addresses = {
             let mmaps = self.mmaps.lock().await;
@@ -196,13 +401,105 @@ impl PixelStorageTrait for MmapPixelStorage {
         };
         for address in addresses {
             let png = self
-                .get_canvas_as_png(&address)
+                .get_canvas_png(&address)
                 .await
                 .context(format!("Failed to get canvas {} as a png", address))?;
             pngs.insert(address, png);
         }
         Ok(pngs)
     }
+
+    async fn get_canvas_versions(&self) -> Result<HashMap<Address, u64>> {
+        Ok(self.versions.lock().await.clone())
+    }
+
+    /// The chain ID is kept in a file alongside the canvas files, so it is always
+    /// wiped along with them.
+    async fn read_chain_id(&self) -> Result<Option<u8>> {
+        let filename = self.get_chain_id_filename();
+        if !filename.exists() {
+            return Ok(None);
+        }
+        let chain_id = std::fs::read_to_string(&filename)
+            .with_context(|| format!("Failed to read file {}", filename.display()))?;
+        let chain_id = chain_id
+            .trim()
+            .parse()
+            .with_context(|| format!("Failed to parse chain ID in {}", filename.display()))?;
+        Ok(Some(chain_id))
+    }
+
+    async fn write_chain_id(&self, chain_id: u8) -> Result<()> {
+        let filename = self.get_chain_id_filename();
+        std::fs::write(&filename, chain_id.to_string())
+            .with_context(|| format!("Failed to write file {}", filename.display()))
+    }
+
+    /// The directory must still be there and writable, e.g. if the disk it's on
+    /// gets unmounted we can't write any more pixels.
+    async fn check_health(&self) -> Result<()> {
+        let directory = &self.config.storage_directory;
+        let metadata = std::fs::metadata(directory)
+            .with_context(|| format!("Failed to read directory {}", directory.display()))?;
+        if !metadata.is_dir() {
+            anyhow::bail!("{} is not a directory", directory.display());
+        }
+        if metadata.permissions().readonly() {
+            anyhow::bail!("{} is read only", directory.display());
+        }
+        Ok(())
+    }
+
+    /// The OS writes dirty pages of the mmaps back to the files eventually anyway,
+    /// this just forces it to happen now.
+    async fn flush_to_disk(&self) -> Result<()> {
+        let mmaps: Vec<(Address, Arc<Mutex<MmapMut>>)> = {
+            let mmaps = self.mmaps.lock().await;
+            mmaps
+                .iter()
+                .map(|(address, mmap)| (*address, mmap.clone()))
+                .collect()
+        };
+        for (canvas_address, mmap) in &mmaps {
+            mmap.lock()
+                .await
+                .flush()
+                .with_context(|| format!("Failed to flush mmap for canvas {}", canvas_address))?;
+        }
+        info!("Flushed {} mmaps to disk", mmaps.len());
+        Ok(())
+    }
+
+    async fn reset(&self) -> Result<()> {
+        // Hold the lock so no one can open an mmap again while we delete the files.
+        let mut mmaps = self.mmaps.lock().await;
+        mmaps.clear();
+        self.versions.lock().await.clear();
+        let entries = std::fs::read_dir(&self.config.storage_directory).with_context(|| {
+            format!(
+                "Failed to read directory {}",
+                self.config.storage_directory.display()
+            )
+        })?;
+        let mut num_deleted = 0;
+        for entry in entries {
+            let path = entry.context("Failed to read directory entry")?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("canvas") {
+                continue;
+            }
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to delete file {}", path.display()))?;
+            num_deleted += 1;
+        }
+        let filename = self.get_chain_id_filename();
+        if filename.exists() {
+            std::fs::remove_file(&filename)
+                .with_context(|| format!("Failed to delete file {}", filename.display()))?;
+        }
+        info!("Deleted {} canvas files", num_deleted);
+        self.publish_delta(|| PixelDelta::Reset);
+        Ok(())
+    }
 }
 
 /// Read the width and height from the end of the file.
@@ -224,3 +521,55 @@ fn read_width_and_height(mmap: &MmapMut) -> Result<(u64, u64)> {
 
     Ok((width, height))
 }
+
+/// Check the file is made up of exactly width * height pixels followed by the width
+/// and height.
+fn check_integrity(mmap: &MmapMut) -> std::result::Result<(), String> {
+    let (width, height) = read_width_and_height(mmap).map_err(|e| e.to_string())?;
+    if width == 0 || height == 0 {
+        return Err(format!("Invalid dimensions {}x{}", width, height));
+    }
+    let expected_len = width
+        .checked_mul(height)
+        .and_then(|num_pixels| num_pixels.checked_mul(3))
+        .and_then(|len| len.checked_add(16))
+        .ok_or_else(|| format!("Invalid dimensions {}x{}", width, height))?;
+    if mmap.len() as u64 != expected_len {
+        return Err(format!(
+            "Expected {} bytes for a {}x{} canvas but the file is {} bytes",
+            expected_len,
+            width,
+            height,
+            mmap.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Take an exclusive lock on the storage directory, failing straight away if another
+/// process holds it. Two processes writing to the same canvas files would corrupt
+/// them. We use flock rather than just creating a file since the kernel releases it
+/// when the file is closed, including when the process dies, so a crash never
+/// leaves a stale lock behind.
+fn lock_storage_directory(storage_directory: &Path) -> Result<File> {
+    let path = storage_directory.join("lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+    // SAFETY: The fd is valid for as long as `file` is, and flock doesn't touch
+    // any memory of ours.
+    let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if result != 0 {
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() == Some(libc::EWOULDBLOCK) {
+            anyhow::bail!(
+                "{} is in use by another process, e.g. the processor. Stop it first",
+                storage_directory.display()
+            );
+        }
+        return Err(error).with_context(|| format!("Failed to lock {}", path.display()));
+    }
+    Ok(file)
+}
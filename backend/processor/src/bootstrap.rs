@@ -0,0 +1,442 @@
+//! If we've lost both the canvas files and the txn history, we can't rebuild the
+//! canvases by reprocessing. Instead we can bootstrap them from a snapshot of the
+//! on-chain state: the Canvas resources and the items in their pixels tables at some
+//! version. We write fresh canvas files from that and set the checkpoint to the
+//! snapshot version so normal processing picks up from there.
+//!
+//! Attribution and the per account state of canvases can't be recovered this way,
+//! since they're built from the history of draws.
+
+use crate::{
+    layout::CanvasLayoutVersion,
+    smart_table::decode_smart_table_bucket,
+    utils::{get_object_core_struct_tag, struct_tags_match},
+    CanvasProcessor, CanvasProcessorConfig, RunConfig,
+};
+use anyhow::{bail, Context, Result};
+use aptos_move_graphql_scalars::Address;
+use aptos_processor_framework::{
+    indexer_protos::transaction::v1::MoveStructTag, ProcessorTrait, StorageTrait,
+};
+use metadata_storage::{
+    CanvasTableHandle, CanvasTableName, MetadataStorageTrait, PostgresMetadataStorage,
+    UpdateCanvasIntent,
+};
+use move_types::ObjectCore;
+use pixel_storage::{
+    CreateCanvasIntent, HardcodedColor, MmapPixelStorage, PixelStorageTrait, WritePixelIntent,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, path::Path, str::FromStr, sync::Arc};
+use tracing::info;
+
+/// A dump of on-chain state at a particular version, e.g. built from the node API
+/// (`/accounts/{address}/resource/{type}` and `/tables/{handle}/item` with
+/// `ledger_version` set). The data is in the same JSON format the node API uses.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateSnapshot {
+    /// The version the state was read at.
+    pub version: u64,
+    /// The Canvas resources, plus optionally the ObjectCore of each canvas so we
+    /// know who owns it. Other resources are ignored.
+    pub resources: Vec<SnapshotResource>,
+    /// The items in the pixels table of each canvas. Items from other tables are
+    /// ignored.
+    #[serde(default)]
+    pub table_items: Vec<SnapshotTableItem>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotResource {
+    pub address: String,
+    /// The struct tag of the resource, e.g. `0x123::canvas_token::Canvas`.
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub data: Value,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotTableItem {
+    pub handle: String,
+    pub key: Value,
+    pub value: Value,
+}
+
+/// Write fresh canvas files for every canvas of the contract in the snapshot and
+/// set the checkpoint of the processor for that contract to the snapshot version.
+/// Returns how many canvases we bootstrapped.
+pub async fn bootstrap_from_snapshot(
+    config: RunConfig,
+    snapshot_path: &Path,
+    contract_address: Option<Address>,
+    metadata_storage: Arc<PostgresMetadataStorage>,
+    pixels_storage: Arc<MmapPixelStorage>,
+) -> Result<usize> {
+    let snapshot = std::fs::read_to_string(snapshot_path)
+        .context(format!("Failed to read {}", snapshot_path.display()))?;
+    let snapshot: StateSnapshot = serde_json::from_str(&snapshot)
+        .context(format!("Failed to parse {}", snapshot_path.display()))?;
+    bootstrap_canvases(
+        &config.processor_config,
+        &snapshot,
+        contract_address,
+        metadata_storage,
+        pixels_storage,
+    )
+    .await
+}
+
+/// Does the work of `bootstrap_from_snapshot`, for any storage.
+async fn bootstrap_canvases<M, P>(
+    processor_config: &CanvasProcessorConfig,
+    snapshot: &StateSnapshot,
+    contract_address: Option<Address>,
+    metadata_storage: Arc<M>,
+    pixels_storage: Arc<P>,
+) -> Result<usize>
+where
+    M: MetadataStorageTrait + StorageTrait,
+    P: PixelStorageTrait,
+{
+    let contract_addresses = &processor_config.canvas_contract_addresses;
+    let contract_address = match contract_address {
+        Some(contract_address) => {
+            if !contract_addresses.contains(&contract_address) {
+                bail!(
+                    "Contract {} is not in canvas_contract_addresses",
+                    contract_address
+                );
+            }
+            contract_address
+        },
+        None => match contract_addresses.as_slice() {
+            [contract_address] => *contract_address,
+            _ => bail!("There are multiple contracts in the config, choose one"),
+        },
+    };
+    let processor = CanvasProcessor::new(
+        processor_config.clone(),
+        contract_address,
+        pixels_storage.clone(),
+        metadata_storage.clone(),
+    )?;
+
+    let layout = match metadata_storage
+        .read_contract_layout(&contract_address)
+        .await
+        .context("Failed to read contract layout")?
+    {
+        Some(layout_version) => CanvasLayoutVersion::from_u16(layout_version)?,
+        None => CanvasLayoutVersion::LATEST,
+    };
+
+    // Find the canvases and their owners.
+    let canvas_struct_tag = processor.get_canvas_struct_tag();
+    let object_core_struct_tag = get_object_core_struct_tag();
+    let mut canvases = vec![];
+    let mut object_owners = HashMap::new();
+    for resource in &snapshot.resources {
+        // Dumps of an account include resources we don't care about, e.g.
+        // `0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>`, so skip anything we
+        // can't parse rather than failing.
+        let struct_tag = match parse_struct_tag(&resource.resource_type) {
+            Some(struct_tag) => struct_tag,
+            None => continue,
+        };
+        let is_canvas = struct_tags_match(&struct_tag, &canvas_struct_tag);
+        if !is_canvas && !struct_tags_match(&struct_tag, &object_core_struct_tag) {
+            continue;
+        }
+        let address =
+            Address::from_str(&resource.address).context("Failed to parse resource address")?;
+        if is_canvas {
+            let canvas = layout
+                .decode_canvas(&resource.data.to_string())
+                .context(format!("Failed to decode canvas {}", address))?;
+            canvases.push((address, canvas));
+        } else {
+            let object_core: ObjectCore = serde_json::from_value(resource.data.clone())
+                .context("Failed to parse ObjectCore")?;
+            object_owners.insert(address, object_core.owner);
+        }
+    }
+    if canvases.is_empty() {
+        bail!(
+            "There are no Canvas resources for contract {} in the snapshot",
+            contract_address
+        );
+    }
+
+    // Group the pixels table items by handle.
+    let mut table_items: HashMap<Address, Vec<&SnapshotTableItem>> = HashMap::new();
+    for item in &snapshot.table_items {
+        let handle = Address::from_str(&item.handle).context("Failed to parse table handle")?;
+        table_items.entry(handle).or_default().push(item);
+    }
+
+    for (canvas_address, canvas) in &canvases {
+        let canvas_address = *canvas_address;
+        info!(
+            "Bootstrapping canvas {} from snapshot at version {}",
+            canvas_address, snapshot.version
+        );
+        let default_color =
+            HardcodedColor::try_from(canvas.config.default_color).context(format!(
+                "Unknown default color {} for canvas {}",
+                canvas.config.default_color, canvas_address
+            ))?;
+
+        let pixels_handle = canvas.pixels.buckets.inner.handle;
+        let mut write_pixel_intents = vec![];
+        for item in table_items.get(&pixels_handle).into_iter().flatten() {
+            let entries = decode_smart_table_bucket::<u32, u8>(&item.value.to_string()).context(
+                format!("Failed to parse pixels bucket of {}", canvas_address),
+            )?;
+            for entry in entries {
+                let color = HardcodedColor::try_from(entry.value).context(format!(
+                    "Unknown color {} for canvas {}",
+                    entry.value, canvas_address
+                ))?;
+                write_pixel_intents.push(WritePixelIntent {
+                    canvas_address,
+                    index: entry.key,
+                    color,
+                    version: snapshot.version,
+                });
+            }
+        }
+
+        if !processor_config.disable_pixel_processing {
+            // Start from a fresh file, even if there is one for this canvas already.
+            pixels_storage
+                .delete_canvas(&canvas_address)
+                .await
+                .context(format!("Failed to delete canvas {}", canvas_address))?;
+            pixels_storage
+                .create_canvas(CreateCanvasIntent {
+                    canvas_address,
+                    width: canvas.config.width,
+                    height: canvas.config.height,
+                    default_color,
+                    version: snapshot.version,
+                })
+                .await
+                .context(format!("Failed to create canvas {}", canvas_address))?;
+            info!(
+                "Writing {} pixels to canvas {}",
+                write_pixel_intents.len(),
+                canvas_address
+            );
+            pixels_storage
+                .write_pixels(write_pixel_intents)
+                .await
+                .context(format!(
+                    "Failed to write pixels to canvas {}",
+                    canvas_address
+                ))?;
+        }
+
+        // The processor needs to know the tables of the canvas to process draws.
+        for (table_name, handle) in [
+            (
+                CanvasTableName::LastContributionS,
+                canvas.last_contribution_s.handle,
+            ),
+            (
+                CanvasTableName::UnlimitedArtists,
+                canvas.unlimited_artists.handle,
+            ),
+            (CanvasTableName::Admins, canvas.admins.handle),
+            (CanvasTableName::Pixels, pixels_handle),
+        ] {
+            metadata_storage
+                .write_canvas_table_handle(CanvasTableHandle {
+                    handle,
+                    canvas_address,
+                    table_name,
+                    created_version: snapshot.version,
+                    bootstrapped: true,
+                    created_with_canvas: Some(false),
+                })
+                .await
+                .context("Failed to write canvas table handle to storage")?;
+        }
+
+        if !processor_config.disable_metadata_processing {
+            metadata_storage
+                .update_canvas(UpdateCanvasIntent {
+                    canvas_address,
+                    width: canvas.config.width,
+                    height: canvas.config.height,
+                    per_account_timeout_s: canvas.config.per_account_timeout_s.0,
+                    default_color: canvas.config.default_color,
+                    max_number_of_pixels_per_draw: canvas.config.max_number_of_pixels_per_draw.0,
+                    draw_enabled_for_non_admin: canvas.config.draw_enabled_for_non_admin,
+                    created_at_s: canvas.created_at_s.0,
+                    last_updated_version: snapshot.version,
+                    owner_address: object_owners.get(&canvas_address).copied(),
+                    contract_address,
+                })
+                .await
+                .context("Failed to update canvas in storage")?;
+        }
+    }
+
+    // Normal processing resumes from the txn after the snapshot.
+    let previous_version = metadata_storage
+        .read_last_processed_version(processor.name())
+        .await
+        .context("Failed to read last processed version")?;
+    info!(
+        "Setting last processed version of {} to {} (was {:?})",
+        processor.name(),
+        snapshot.version,
+        previous_version
+    );
+    metadata_storage
+        .write_last_processed_version(processor.name(), snapshot.version)
+        .await
+        .context("Failed to write last processed version")?;
+
+    Ok(canvases.len())
+}
+
+/// Parse a struct tag like `0x1::object::ObjectCore`. We don't support generic
+/// type params since none of the resources we care about have them, so this
+/// returns None for those, as well as for anything else that isn't a struct tag.
+fn parse_struct_tag(struct_tag: &str) -> Option<MoveStructTag> {
+    if struct_tag.contains('<') {
+        return None;
+    }
+    let parts: Vec<&str> = struct_tag.split("::").collect();
+    match parts.as_slice() {
+        [address, module, name] => Some(MoveStructTag {
+            address: address.to_string(),
+            module: module.to_string(),
+            name: name.to_string(),
+            generic_type_params: vec![],
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rebuild::check_canvases_can_be_rebuilt;
+    use metadata_storage::MemoryMetadataStorage;
+    use pixel_storage::MemoryPixelStorage;
+    use serde_json::json;
+
+    const CONTRACT: &str = "0x6b8169be66d9579ba9ad1192708edcf52de713d3513a431df6cb045f425d3d9";
+    const CANVAS: &str = "0xca";
+    const PIXELS_HANDLE: &str = "0x11";
+    const SNAPSHOT_VERSION: u64 = 1000;
+
+    fn processor_config() -> CanvasProcessorConfig {
+        serde_json::from_value(json!({ "canvas_contract_addresses": [CONTRACT] })).unwrap()
+    }
+
+    fn snapshot() -> StateSnapshot {
+        serde_json::from_value(json!({
+            "version": SNAPSHOT_VERSION,
+            "resources": [
+                {
+                    "address": CANVAS,
+                    "type": format!("{}::canvas_token::Canvas", CONTRACT),
+                    "data": {
+                        "config": {
+                            "width": 2,
+                            "height": 2,
+                            "per_account_timeout_s": "0",
+                            "default_color": 1,
+                            "max_number_of_pixels_per_draw": "10",
+                            "draw_enabled_for_non_admin": true,
+                        },
+                        "pixels": {
+                            "buckets": { "inner": { "handle": PIXELS_HANDLE }, "length": "1" },
+                            "num_buckets": "1",
+                            "level": 0,
+                            "size": "1",
+                            "split_load_threshold": 75,
+                            "target_bucket_size": "1",
+                        },
+                        "last_contribution_s": { "handle": "0x12" },
+                        "unlimited_artists": { "handle": "0x13" },
+                        "admins": { "handle": "0x14" },
+                        "created_at_s": "1",
+                        "extend_ref": { "self": CANVAS },
+                        "mutator_ref": { "self": CANVAS },
+                    },
+                },
+                // Generic resources are skipped rather than failing the bootstrap.
+                {
+                    "address": CANVAS,
+                    "type": "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                    "data": {},
+                },
+            ],
+            "table_items": [
+                {
+                    "handle": PIXELS_HANDLE,
+                    "key": "0",
+                    "value": [{ "hash": "0", "key": 3, "value": 6 }],
+                },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bootstrapped_canvas_cannot_be_rebuilt() {
+        let metadata_storage = Arc::new(MemoryMetadataStorage::new());
+        let pixels_storage = Arc::new(MemoryPixelStorage::new());
+        let num_canvases = bootstrap_canvases(
+            &processor_config(),
+            &snapshot(),
+            None,
+            metadata_storage.clone(),
+            pixels_storage.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(num_canvases, 1);
+
+        let canvas_address = Address::from_str(CANVAS).unwrap();
+        assert!(pixels_storage.canvas_exists(&canvas_address).await.unwrap());
+        let processor = CanvasProcessor::new(
+            processor_config(),
+            Address::from_str(CONTRACT).unwrap(),
+            pixels_storage.clone(),
+            metadata_storage.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            metadata_storage
+                .read_last_processed_version(processor.name())
+                .await
+                .unwrap(),
+            Some(SNAPSHOT_VERSION)
+        );
+
+        let table_handles = metadata_storage.read_canvas_table_handles().await.unwrap();
+        assert_eq!(table_handles.len(), 4);
+        assert!(table_handles
+            .iter()
+            .all(|table_handle| table_handle.bootstrapped
+                && table_handle.created_version == SNAPSHOT_VERSION));
+
+        // Rebuilding from the snapshot version would delete the canvas without ever
+        // recreating it, since the txn that created it is long before that.
+        for from_version in [0, SNAPSHOT_VERSION] {
+            let error =
+                check_canvases_can_be_rebuilt(&table_handles, &[canvas_address], from_version)
+                    .unwrap_err();
+            assert!(error.to_string().contains("bootstrapped"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use aptos_move_graphql_scalars::Address;
+use metadata_storage::{CanvasTableHandle, CanvasTableName};
+use std::collections::{HashMap, HashSet};
+
+/// Table items only tell us the handle of the table they're in, so we use this to
+/// figure out which canvas, and which table within that canvas, they belong to. We
+/// learn about handles when the Canvas resource is written.
+#[derive(Debug, Default)]
+pub struct CanvasTables {
+    /// Every handle we have seen, including those of tables that have since been
+    /// replaced, e.g. by `clear_contribution_timeouts`.
+    by_handle: HashMap<Address, (Address, CanvasTableName)>,
+    /// The handle currently in use for each table of each canvas.
+    current: HashMap<(Address, CanvasTableName), Address>,
+}
+
+/// What happened when we told `CanvasTables` about a handle from a Canvas resource.
+#[derive(Debug, Eq, PartialEq)]
+pub enum TableHandleUpdate {
+    /// We already knew this was the current handle for the table.
+    Unchanged,
+    /// This is the first handle we've seen for the table.
+    New,
+    /// The canvas had a different handle for the table before, i.e. the table was
+    /// replaced with a new one.
+    Replaced,
+}
+
+impl CanvasTables {
+    /// Build from the handles in the metadata storage. If there are multiple handles
+    /// for the same table, the most recent one is the current one.
+    pub fn new(mut table_handles: Vec<CanvasTableHandle>) -> Self {
+        table_handles.sort_by_key(|table_handle| table_handle.created_version);
+        let mut canvas_tables = Self::default();
+        for table_handle in table_handles {
+            canvas_tables.update(
+                table_handle.canvas_address,
+                table_handle.table_name,
+                table_handle.handle,
+            );
+        }
+        canvas_tables
+    }
+
+    pub fn get(&self, handle: &Address) -> Option<&(Address, CanvasTableName)> {
+        self.by_handle.get(handle)
+    }
+
+    /// How many canvases we know the tables of.
+    pub fn num_canvases(&self) -> usize {
+        self.current
+            .keys()
+            .map(|(canvas_address, _)| canvas_address)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    pub fn has_table(&self, canvas_address: &Address, table_name: CanvasTableName) -> bool {
+        self.current.contains_key(&(*canvas_address, table_name))
+    }
+
+    pub fn update(
+        &mut self,
+        canvas_address: Address,
+        table_name: CanvasTableName,
+        handle: Address,
+    ) -> TableHandleUpdate {
+        self.by_handle.insert(handle, (canvas_address, table_name));
+        match self.current.insert((canvas_address, table_name), handle) {
+            None => TableHandleUpdate::New,
+            Some(previous) if previous == handle => TableHandleUpdate::Unchanged,
+            Some(_) => TableHandleUpdate::Replaced,
+        }
+    }
+}
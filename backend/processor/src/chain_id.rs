@@ -0,0 +1,103 @@
+//! Everything the processor stores is derived from the txns of a single chain. If we
+//! pointed a processor with existing mainnet data at a testnet stream it would apply
+//! testnet txns on top of it, so before processing anything we check the chain the
+//! stream is for matches the chain the data in storage came from. To switch chains
+//! the data must be wiped explicitly with `reset_storage`.
+
+use anyhow::{bail, Context, Result};
+use aptos_processor_framework::{indexer_protos::indexer::v1::TransactionsResponse, StorageTrait};
+use metadata_storage::MetadataStorageTrait;
+use pixel_storage::PixelStorageTrait;
+use tokio::sync::mpsc::Receiver;
+use tracing::info;
+
+/// Wait for the first response from the stream and check its chain ID against the
+/// chain ID in storage, recording it if this is the first time we've seen one.
+/// This returns the first response, which the caller must process before any
+/// others from the receiver.
+pub async fn check_stream_chain_id<S: StorageTrait>(
+    receiver: &mut Receiver<TransactionsResponse>,
+    metadata_storage: &S,
+    pixels_storage: &dyn PixelStorageTrait,
+) -> Result<TransactionsResponse> {
+    let first_response = receiver
+        .recv()
+        .await
+        .context("Txn stream ended before sending any txns")?;
+    let chain_id = first_response
+        .chain_id
+        .context("Txn stream didn't send a chain ID")?;
+    check_chain_id(chain_id, metadata_storage, pixels_storage).await?;
+    Ok(first_response)
+}
+
+/// Check the chain ID of the stream against the chain IDs recorded in the metadata
+/// and pixel storage. If either doesn't have one yet we record it.
+pub async fn check_chain_id<S: StorageTrait>(
+    chain_id: u64,
+    metadata_storage: &S,
+    pixels_storage: &dyn PixelStorageTrait,
+) -> Result<()> {
+    let chain_id = u8::try_from(chain_id).context(format!("Invalid chain ID {}", chain_id))?;
+
+    let metadata_chain_id = metadata_storage
+        .read_chain_id()
+        .await
+        .context("Failed to read chain ID from metadata storage")?;
+    let pixels_chain_id = pixels_storage
+        .read_chain_id()
+        .await
+        .context("Failed to read chain ID from pixel storage")?;
+    for (storage_name, stored_chain_id) in [
+        ("metadata storage", metadata_chain_id),
+        ("pixel storage", pixels_chain_id),
+    ] {
+        if let Some(stored_chain_id) = stored_chain_id {
+            if stored_chain_id != chain_id {
+                bail!(
+                    "The data in the {} is from chain {} but the txn stream is for chain {}. \
+                    If you meant to switch chains, run with --reset to delete the existing data",
+                    storage_name,
+                    stored_chain_id,
+                    chain_id
+                );
+            }
+        }
+    }
+
+    if metadata_chain_id.is_none() {
+        info!("Recording chain ID {} in metadata storage", chain_id);
+        metadata_storage
+            .write_chain_id(chain_id)
+            .await
+            .context("Failed to write chain ID to metadata storage")?;
+    }
+    if pixels_chain_id.is_none() {
+        info!("Recording chain ID {} in pixel storage", chain_id);
+        pixels_storage
+            .write_chain_id(chain_id)
+            .await
+            .context("Failed to write chain ID to pixel storage")?;
+    }
+
+    Ok(())
+}
+
+/// Delete everything derived from txns, from both the metadata and pixel storage,
+/// including the chain ID and the checkpoints. The processors start from scratch
+/// afterwards.
+pub async fn reset_storage(
+    metadata_storage: &dyn MetadataStorageTrait,
+    pixels_storage: &dyn PixelStorageTrait,
+) -> Result<()> {
+    info!("Resetting metadata and pixel storage");
+    metadata_storage
+        .reset()
+        .await
+        .context("Failed to reset metadata storage")?;
+    pixels_storage
+        .reset()
+        .await
+        .context("Failed to reset pixel storage")?;
+    Ok(())
+}
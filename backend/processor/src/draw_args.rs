@@ -0,0 +1,98 @@
+use anyhow::{bail, Context, Result};
+use aptos_move_graphql_scalars::U64;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A pixel the sender asked to draw, decoded from the entry function arguments.
+#[derive(Clone, Debug)]
+pub struct RequestedPixel {
+    pub x: u32,
+    pub y: u32,
+    /// None if the entry function doesn't take a hardcoded color.
+    pub color: Option<u8>,
+}
+
+/// Decode the arguments of `draw(canvas, xs, ys, colors)`. The signer is not part
+/// of the arguments, so the canvas is the first argument.
+pub fn decode_draw_args(arguments: &[Value]) -> Result<Vec<RequestedPixel>> {
+    if arguments.len() != 4 {
+        bail!("Expected 4 arguments for draw, got {}", arguments.len());
+    }
+    let xs: Vec<u16> =
+        serde_json::from_value(arguments[1].clone()).context("Failed to parse xs")?;
+    let ys: Vec<u16> =
+        serde_json::from_value(arguments[2].clone()).context("Failed to parse ys")?;
+    let colors = decode_u8_vector(&arguments[3]).context("Failed to parse colors")?;
+    // The contract aborts if these differ, so if they do we're decoding it wrong.
+    if xs.len() != ys.len() || xs.len() != colors.len() {
+        bail!(
+            "Mismatched draw argument lengths: {} xs, {} ys, {} colors",
+            xs.len(),
+            ys.len(),
+            colors.len()
+        );
+    }
+    Ok(xs
+        .into_iter()
+        .zip(ys)
+        .zip(colors)
+        .map(|((x, y), color)| RequestedPixel {
+            x: x as u32,
+            y: y as u32,
+            color: Some(color),
+        })
+        .collect())
+}
+
+/// Decode the arguments of `draw_one(canvas, x, y, r, g, b)`. This predates the
+/// hardcoded colors, so we only decode the position.
+pub fn decode_draw_one_args(arguments: &[Value]) -> Result<Vec<RequestedPixel>> {
+    if arguments.len() < 3 {
+        bail!(
+            "Expected at least 3 arguments for draw_one, got {}",
+            arguments.len()
+        );
+    }
+    let x: U64 = serde_json::from_value(arguments[1].clone()).context("Failed to parse x")?;
+    let y: U64 = serde_json::from_value(arguments[2].clone()).context("Failed to parse y")?;
+    Ok(vec![RequestedPixel {
+        x: u32::try_from(x.0).context("x is out of range")?,
+        y: u32::try_from(y.0).context("y is out of range")?,
+        color: None,
+    }])
+}
+
+/// Work out the index of each requested pixel and the color it ends up with. The
+/// contract pops pixels off the back of the vectors, so if the same pixel appears
+/// more than once, the first occurrence is the one that sticks.
+pub fn get_requested_indices(
+    pixels: &[RequestedPixel],
+    canvas_width: u32,
+) -> HashMap<u32, Option<u8>> {
+    pixels
+        .iter()
+        .rev()
+        .map(|pixel| (pixel.y * canvas_width + pixel.x, pixel.color))
+        .collect()
+}
+
+/// Entry function arguments of type `vector<u8>` are given to us as a hex string,
+/// but we accept a JSON array too for good measure.
+fn decode_u8_vector(value: &Value) -> Result<Vec<u8>> {
+    match value {
+        Value::String(s) => {
+            let s = s.strip_prefix("0x").unwrap_or(s);
+            if !s.is_ascii() || s.len() % 2 != 0 {
+                bail!("Invalid hex string: {}", s);
+            }
+            (0..s.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&s[i..i + 2], 16).context("Invalid hex"))
+                .collect()
+        },
+        Value::Array(_) => {
+            serde_json::from_value(value.clone()).context("Failed to parse array of u8")
+        },
+        _ => bail!("Expected a hex string or an array for vector<u8>"),
+    }
+}
@@ -0,0 +1,153 @@
+use aptos_move_graphql_scalars::Address;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The errors we expect to see while processing a single txn. Anything else, e.g.
+/// failing to talk to storage, is not specific to a txn and always fails processing.
+#[derive(Debug)]
+pub enum CanvasProcessorError {
+    /// A pixel or the canvas default color isn't one of the hardcoded colors.
+    UnknownColor { canvas_address: Address, color: u8 },
+    /// The txn refers to a canvas we have no record of, e.g. because we started
+    /// processing after it was created.
+    MissingCanvas { canvas_address: Address },
+    /// We couldn't decode something in the txn, e.g. a resource or an argument.
+    Decode {
+        canvas_address: Option<Address>,
+        message: String,
+    },
+    /// The txn wrote a pixel outside of the canvas.
+    OutOfBoundsIndex {
+        canvas_address: Address,
+        index: u32,
+        num_pixels: u32,
+    },
+}
+
+impl CanvasProcessorError {
+    pub fn kind(&self) -> CanvasProcessorErrorKind {
+        match self {
+            CanvasProcessorError::UnknownColor { .. } => CanvasProcessorErrorKind::UnknownColor,
+            CanvasProcessorError::MissingCanvas { .. } => CanvasProcessorErrorKind::MissingCanvas,
+            CanvasProcessorError::Decode { .. } => CanvasProcessorErrorKind::Decode,
+            CanvasProcessorError::OutOfBoundsIndex { .. } => {
+                CanvasProcessorErrorKind::OutOfBoundsIndex
+            },
+        }
+    }
+
+    pub fn canvas_address(&self) -> Option<Address> {
+        match self {
+            CanvasProcessorError::UnknownColor { canvas_address, .. } => Some(*canvas_address),
+            CanvasProcessorError::MissingCanvas { canvas_address } => Some(*canvas_address),
+            CanvasProcessorError::Decode { canvas_address, .. } => *canvas_address,
+            CanvasProcessorError::OutOfBoundsIndex { canvas_address, .. } => Some(*canvas_address),
+        }
+    }
+}
+
+impl fmt::Display for CanvasProcessorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanvasProcessorError::UnknownColor {
+                canvas_address,
+                color,
+            } => write!(f, "Unknown color {} for canvas {}", color, canvas_address),
+            CanvasProcessorError::MissingCanvas { canvas_address } => {
+                write!(f, "Canvas {} is missing", canvas_address)
+            },
+            CanvasProcessorError::Decode {
+                canvas_address: Some(canvas_address),
+                message,
+            } => write!(
+                f,
+                "Failed to decode txn for canvas {}: {}",
+                canvas_address, message
+            ),
+            CanvasProcessorError::Decode {
+                canvas_address: None,
+                message,
+            } => write!(f, "Failed to decode txn: {}", message),
+            CanvasProcessorError::OutOfBoundsIndex {
+                canvas_address,
+                index,
+                num_pixels,
+            } => write!(
+                f,
+                "Index {} is out of bounds for canvas {} with {} pixels",
+                index, canvas_address, num_pixels
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CanvasProcessorError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CanvasProcessorErrorKind {
+    UnknownColor,
+    MissingCanvas,
+    Decode,
+    OutOfBoundsIndex,
+}
+
+impl CanvasProcessorErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CanvasProcessorErrorKind::UnknownColor => "unknown_color",
+            CanvasProcessorErrorKind::MissingCanvas => "missing_canvas",
+            CanvasProcessorErrorKind::Decode => "decode",
+            CanvasProcessorErrorKind::OutOfBoundsIndex => "out_of_bounds_index",
+        }
+    }
+}
+
+/// What to do when processing a txn fails with a `CanvasProcessorError`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPolicy {
+    /// Fail processing. The processor won't make progress until the txn can be
+    /// processed, e.g. after a fix is deployed.
+    Fail,
+    /// Skip the txn and record it in the dead letter table.
+    SkipAndRecord,
+    /// Like `SkipAndRecord`, but we also skip every later txn that touches the same
+    /// canvas, since its state can no longer be trusted. If we don't know which
+    /// canvas the txn was for, this is the same as `SkipAndRecord`. To lift the
+    /// quarantine, delete the canvas' rows from the dead letter table and reprocess
+    /// the canvas from its creation.
+    Quarantine,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ErrorPolicyConfig {
+    pub unknown_color: ErrorPolicy,
+    pub missing_canvas: ErrorPolicy,
+    pub decode: ErrorPolicy,
+    pub out_of_bounds_index: ErrorPolicy,
+}
+
+impl ErrorPolicyConfig {
+    pub fn get(&self, kind: CanvasProcessorErrorKind) -> ErrorPolicy {
+        match kind {
+            CanvasProcessorErrorKind::UnknownColor => self.unknown_color,
+            CanvasProcessorErrorKind::MissingCanvas => self.missing_canvas,
+            CanvasProcessorErrorKind::Decode => self.decode,
+            CanvasProcessorErrorKind::OutOfBoundsIndex => self.out_of_bounds_index,
+        }
+    }
+}
+
+impl Default for ErrorPolicyConfig {
+    fn default() -> Self {
+        Self {
+            unknown_color: ErrorPolicy::Fail,
+            // We can't do anything with draws for a canvas we missed the creation
+            // of, so by default we just note them and move on.
+            missing_canvas: ErrorPolicy::SkipAndRecord,
+            decode: ErrorPolicy::Fail,
+            out_of_bounds_index: ErrorPolicy::Fail,
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! The contract can be upgraded, and if the upgrade changes the layout of the
+//! Canvas resource, decoding it with the types in move-types (a snapshot of the
+//! contract at one point in time) could silently give us the wrong data. Instead,
+//! we keep track of which layout is active by looking at the ABI whenever the
+//! canvas_token module is published, and decode with the matching decoder. If we
+//! see a layout we don't know, we fail rather than guess.
+
+use anyhow::{bail, Context, Result};
+use aptos_processor_framework::indexer_protos::transaction::v1::MoveModule;
+use move_types::Canvas;
+use serde_json::Value;
+
+/// A layout of the structs in the canvas_token module that we know how to decode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CanvasLayoutVersion {
+    V1,
+}
+
+impl CanvasLayoutVersion {
+    const ALL: &'static [Self] = &[CanvasLayoutVersion::V1];
+    /// We assume this layout if we've never seen the module published, e.g. because
+    /// we started processing after the contract was deployed.
+    pub const LATEST: Self = CanvasLayoutVersion::V1;
+
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            CanvasLayoutVersion::V1 => 1,
+        }
+    }
+
+    pub fn from_u16(version: u16) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .find(|layout| layout.as_u16() == version)
+            .copied()
+            .with_context(|| format!("Unknown canvas layout version {}", version))
+    }
+
+    fn canvas_fields(&self) -> &'static [&'static str] {
+        match self {
+            CanvasLayoutVersion::V1 => &[
+                "config",
+                "pixels",
+                "last_contribution_s",
+                "unlimited_artists",
+                "admins",
+                "created_at_s",
+                "extend_ref",
+                "mutator_ref",
+            ],
+        }
+    }
+
+    fn canvas_config_fields(&self) -> &'static [&'static str] {
+        match self {
+            CanvasLayoutVersion::V1 => &[
+                "width",
+                "height",
+                "per_account_timeout_s",
+                "default_color",
+                "max_number_of_pixels_per_draw",
+                "draw_enabled_for_non_admin",
+            ],
+        }
+    }
+
+    /// Work out the layout from the ABI of a newly published canvas_token module. We
+    /// compare the field names of Canvas and CanvasConfig in order, since that's
+    /// what determines how the resource is laid out.
+    pub fn from_abi(abi: &MoveModule) -> Result<Self> {
+        let field_names = |struct_name: &str| -> Result<Vec<&str>> {
+            let move_struct = abi
+                .structs
+                .iter()
+                .find(|move_struct| move_struct.name == struct_name)
+                .with_context(|| format!("No {} struct in canvas_token ABI", struct_name))?;
+            Ok(move_struct
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect())
+        };
+        let canvas_fields = field_names("Canvas")?;
+        let canvas_config_fields = field_names("CanvasConfig")?;
+        match Self::ALL.iter().find(|layout| {
+            canvas_fields == layout.canvas_fields()
+                && canvas_config_fields == layout.canvas_config_fields()
+        }) {
+            Some(layout) => Ok(*layout),
+            None => bail!(
+                "Unknown canvas layout, the processor must be updated to support it. Canvas fields: {:?}, CanvasConfig fields: {:?}",
+                canvas_fields,
+                canvas_config_fields
+            ),
+        }
+    }
+
+    /// Decode a Canvas resource. We check it has exactly the fields we expect, since
+    /// serde would otherwise ignore any fields added by an upgrade we missed.
+    pub fn decode_canvas(&self, data: &str) -> Result<Canvas> {
+        let value: Value = serde_json::from_str(data).context("Failed to parse Canvas")?;
+        check_fields(&value, self.canvas_fields()).context("Unexpected Canvas layout")?;
+        check_fields(&value["config"], self.canvas_config_fields())
+            .context("Unexpected CanvasConfig layout")?;
+        match self {
+            CanvasLayoutVersion::V1 => {
+                serde_json::from_value(value).context("Failed to parse Canvas")
+            },
+        }
+    }
+}
+
+fn check_fields(value: &Value, expected: &[&str]) -> Result<()> {
+    let object = value.as_object().context("Expected an object")?;
+    let mut actual: Vec<&str> = object.keys().map(|key| key.as_str()).collect();
+    let mut expected = expected.to_vec();
+    actual.sort_unstable();
+    expected.sort_unstable();
+    if actual != expected {
+        bail!("Expected fields {:?} but got {:?}", expected, actual);
+    }
+    Ok(())
+}
@@ -1,5 +1,28 @@
+mod bootstrap;
+mod canvas_tables;
+mod chain_id;
+mod draw_args;
+mod error;
+mod layout;
+mod metrics;
+mod paint_processor;
+mod partition;
 mod processor;
+mod rebuild;
 mod run;
+mod smart_table;
+mod transaction_file;
+mod utils;
 
-pub use processor::{CanvasProcessor, CanvasProcessorConfig};
+pub use bootstrap::{bootstrap_from_snapshot, SnapshotResource, SnapshotTableItem, StateSnapshot};
+pub use chain_id::{check_chain_id, check_stream_chain_id, reset_storage};
+pub use error::{CanvasProcessorError, CanvasProcessorErrorKind, ErrorPolicy, ErrorPolicyConfig};
+pub use metrics::get_last_transaction_timestamps;
+pub use paint_processor::{PaintProcessor, PaintProcessorConfig};
+pub use processor::{migrate_legacy_checkpoint, CanvasProcessor, CanvasProcessorConfig};
+pub use rebuild::rebuild_canvases;
 pub use run::{run, RunConfig};
+pub use transaction_file::{
+    read_transactions, record_transactions, FileStreamSubscriber, FileStreamSubscriberConfig,
+    TransactionFileFormat, TransactionFileWriter,
+};
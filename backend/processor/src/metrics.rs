@@ -0,0 +1,113 @@
+use once_cell::sync::Lazy;
+use prometheus::{
+    core::Collector, register_gauge_vec, register_histogram, register_int_counter,
+    register_int_counter_vec, register_int_gauge_vec, GaugeVec, Histogram, IntCounter,
+    IntCounterVec, IntGaugeVec,
+};
+use std::collections::HashMap;
+
+/// Pixels where the arguments of a draw txn and its write set disagree.
+pub static DRAW_MISMATCH_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "canvas_processor_draw_mismatch_count",
+        "Number of pixels where the draw arguments and the write set disagree",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+/// The last version each processor processed.
+pub static LAST_PROCESSED_VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "canvas_processor_last_processed_version",
+        "Last version the processor processed",
+        &["processor_name"]
+    )
+    .unwrap()
+});
+
+/// How far behind the chain each processor is, based on the timestamp of the last
+/// txn it processed. If this keeps growing the processor has stalled.
+pub static PROCESSOR_LAG_SECS: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        "canvas_processor_lag_secs",
+        "Seconds between now and the timestamp of the last txn the processor processed",
+        &["processor_name"]
+    )
+    .unwrap()
+});
+
+/// The timestamp of the last txn each processor processed. We use this rather than
+/// the lag for health checks since the lag is only updated when a batch is processed,
+/// so it doesn't grow if the processor stalls.
+pub static LAST_TRANSACTION_TIMESTAMP_SECS: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        "canvas_processor_last_transaction_timestamp_secs",
+        "Timestamp of the last txn the processor processed",
+        &["processor_name"]
+    )
+    .unwrap()
+});
+
+/// Txns each processor has processed, including ones it skipped.
+pub static PROCESSED_TRANSACTION_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "canvas_processor_processed_transaction_count",
+        "Number of txns the processor has processed",
+        &["processor_name"]
+    )
+    .unwrap()
+});
+
+/// How many canvases each processor knows about.
+pub static CANVAS_COUNT: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "canvas_processor_canvas_count",
+        "Number of canvases the processor knows about",
+        &["processor_name"]
+    )
+    .unwrap()
+});
+
+pub static WRITTEN_PIXEL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "canvas_processor_written_pixel_count",
+        "Number of pixels written to pixel storage"
+    )
+    .unwrap()
+});
+
+pub static WRITE_PIXELS_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "canvas_processor_write_pixels_latency_secs",
+        "Time taken to write the pixels for a canvas from a batch to pixel storage"
+    )
+    .unwrap()
+});
+
+pub static UPDATE_ATTRIBUTION_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "canvas_processor_update_attribution_latency_secs",
+        "Time taken to write a single attribution to metadata storage"
+    )
+    .unwrap()
+});
+
+/// The timestamp of the last txn each processor has processed since startup, keyed
+/// by processor name.
+pub fn get_last_transaction_timestamps() -> HashMap<String, f64> {
+    let mut timestamps = HashMap::new();
+    for metric_family in LAST_TRANSACTION_TIMESTAMP_SECS.collect() {
+        for metric in metric_family.get_metric() {
+            let processor_name = metric
+                .get_label()
+                .iter()
+                .find(|label| label.get_name() == "processor_name")
+                .map(|label| label.get_value().to_string());
+            if let Some(processor_name) = processor_name {
+                timestamps.insert(processor_name, metric.get_gauge().get_value());
+            }
+        }
+    }
+    timestamps
+}
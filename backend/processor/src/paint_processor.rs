@@ -0,0 +1,314 @@
+use crate::utils::{entry_function_id_matches, get_object_core_struct_tag, struct_tags_match};
+use anyhow::{Context as AnyhowContext, Result};
+use aptos_move_graphql_scalars::{Address, U64};
+use aptos_processor_framework::{
+    indexer_protos::transaction::v1::{
+        transaction::TxnData, write_set_change::Change, EntryFunctionId, Event, MoveModuleId,
+        MoveStructTag, Transaction,
+    },
+    ProcessingResult, ProcessorTrait,
+};
+use metadata_storage::{
+    CreatePaintActivityIntent, MetadataStorageTrait, PaintActivityType, UpdatePaintBalanceIntent,
+};
+use move_types::{
+    _0x1__fungible_asset__DepositEvent as DepositEvent,
+    _0x1__fungible_asset__WithdrawEvent as WithdrawEvent, FungibleStore, ObjectCore,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tracing::{info, warn};
+
+const PAINT_FUNGIBLE_ASSET_MODULE_NAME: &str = "paint_fungible_asset";
+
+/// Older versions of the framework emit these from event handles in the store.
+const DEPOSIT_EVENT_TYPE: &str = "0x1::fungible_asset::DepositEvent";
+const WITHDRAW_EVENT_TYPE: &str = "0x1::fungible_asset::WithdrawEvent";
+
+/// Newer versions of the framework emit these module events instead, which say which
+/// store they're for in the data rather than the event key.
+const DEPOSIT_MODULE_EVENT_TYPE: &str = "0x1::fungible_asset::Deposit";
+const WITHDRAW_MODULE_EVENT_TYPE: &str = "0x1::fungible_asset::Withdraw";
+
+/// The data of a Deposit or Withdraw module event. move-types doesn't include these
+/// since they're newer than the framework it was generated from.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct FungibleStoreModuleEvent {
+    store: Address,
+    amount: U64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaintProcessorConfig {
+    /// The address the package containing the paint modules is published at.
+    pub paint_contract_address: Address,
+
+    /// The address of the PAINT fungible asset Metadata object. You can get this from
+    /// the `paint_fungible_asset::get_metadata` view function.
+    pub paint_metadata_address: Address,
+}
+
+/// This processor tracks PAINT mints, transfers and balances. It doesn't touch the
+/// pixel storage, only the metadata storage.
+#[derive(Debug)]
+pub struct PaintProcessor {
+    config: PaintProcessorConfig,
+    metadata_storage: Arc<dyn MetadataStorageTrait>,
+}
+
+impl PaintProcessor {
+    pub fn new(
+        config: PaintProcessorConfig,
+        metadata_storage: Arc<dyn MetadataStorageTrait>,
+    ) -> Self {
+        Self {
+            config,
+            metadata_storage,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProcessorTrait for PaintProcessor {
+    fn name(&self) -> &'static str {
+        "PaintProcessor"
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+    ) -> Result<ProcessingResult> {
+        let mut all_update_paint_balance_intents = Vec::new();
+        let mut all_create_paint_activity_intents = Vec::new();
+        // The owners of the stores we've seen in this batch, since we only write the
+        // balances to storage at the end of it.
+        let mut store_owners = HashMap::new();
+        for transaction in transactions {
+            // Skip failed transactions.
+            if let Some(info) = &transaction.info {
+                if !info.success {
+                    continue;
+                }
+            }
+
+            let (update_paint_balance_intents, create_paint_activity_intents) = self
+                .process_paint(&transaction, &mut store_owners)
+                .await
+                .context(format!(
+                    "Failed at process_paint for txn version {}",
+                    transaction.version
+                ))?;
+            all_update_paint_balance_intents.extend(update_paint_balance_intents);
+            all_create_paint_activity_intents.extend(create_paint_activity_intents);
+        }
+        info!(
+            start_version = start_version,
+            end_version = end_version,
+            processor_name = self.name(),
+            num_balances_to_update = all_update_paint_balance_intents.len(),
+            num_activities_to_create = all_create_paint_activity_intents.len()
+        );
+
+        for update_paint_balance_intent in all_update_paint_balance_intents {
+            self.metadata_storage
+                .update_paint_balance(update_paint_balance_intent)
+                .await
+                .context("Failed to update paint balance in storage")?;
+        }
+
+        for create_paint_activity_intent in all_create_paint_activity_intents {
+            self.metadata_storage
+                .create_paint_activity(create_paint_activity_intent)
+                .await
+                .context("Failed to create paint activity in storage")?;
+        }
+
+        Ok((start_version, end_version))
+    }
+}
+
+impl PaintProcessor {
+    /// Any txn that changes the balance of a PAINT store writes the FungibleStore
+    /// resource, which we use for the balance. If the txn creates or transfers the
+    /// store its ObjectCore gets written too, which tells us who owns it. Otherwise
+    /// the owner is whoever owned it when we last saw it. The deposit and withdraw
+    /// events on those stores tell us what happened.
+    async fn process_paint(
+        &self,
+        transaction: &Transaction,
+        store_owners: &mut HashMap<Address, Address>,
+    ) -> Result<(
+        Vec<UpdatePaintBalanceIntent>,
+        Vec<CreatePaintActivityIntent>,
+    )> {
+        let info = transaction.info.as_ref().context("No info")?;
+
+        let fungible_store_struct_tag = get_fungible_store_struct_tag();
+        let object_core_struct_tag = get_object_core_struct_tag();
+
+        // Find all the PAINT stores written by this txn, plus the owners of any
+        // objects whose ObjectCore it wrote.
+        let mut paint_store_balances = HashMap::new();
+        let mut object_owners = HashMap::new();
+        for change in &info.changes {
+            let resource = match change.change.as_ref().context("No change")? {
+                Change::WriteResource(resource) => resource,
+                _ => continue,
+            };
+            let struct_tag = resource.r#type.as_ref().context("No type")?;
+            if struct_tags_match(struct_tag, &fungible_store_struct_tag) {
+                let store: FungibleStore = serde_json::from_str(&resource.data)
+                    .context("Failed to parse FungibleStore")?;
+                if store.metadata.inner != self.config.paint_metadata_address {
+                    continue;
+                }
+                let store_address = Address::from_str(&resource.address)
+                    .context("Failed to parse store address")?;
+                paint_store_balances.insert(store_address, store.balance.0);
+            } else if struct_tags_match(struct_tag, &object_core_struct_tag) {
+                let object_core: ObjectCore =
+                    serde_json::from_str(&resource.data).context("Failed to parse ObjectCore")?;
+                let object_address = Address::from_str(&resource.address)
+                    .context("Failed to parse object address")?;
+                object_owners.insert(object_address, object_core.owner);
+            }
+        }
+
+        if paint_store_balances.is_empty() {
+            return Ok((vec![], vec![]));
+        }
+
+        // Work out who owns each store, preferring what the txn wrote.
+        for store_address in paint_store_balances.keys() {
+            if let Some(owner) = object_owners.get(store_address) {
+                store_owners.insert(*store_address, *owner);
+            } else if !store_owners.contains_key(store_address) {
+                let owner = self
+                    .metadata_storage
+                    .read_paint_store_owner(store_address)
+                    .await
+                    .context("Failed to read paint store owner from storage")?;
+                if let Some(owner) = owner {
+                    store_owners.insert(*store_address, owner);
+                }
+            }
+        }
+
+        let mut update_paint_balance_intents = vec![];
+        for (store_address, balance) in &paint_store_balances {
+            // This only happens if we started processing after the store was created.
+            let account_address = match store_owners.get(store_address) {
+                Some(account_address) => *account_address,
+                None => {
+                    warn!(
+                        "Skipping PAINT store {} in txn {}, we don't know who owns it",
+                        store_address, transaction.version
+                    );
+                    continue;
+                },
+            };
+            update_paint_balance_intents.push(UpdatePaintBalanceIntent {
+                account_address,
+                store_address: *store_address,
+                balance: *balance,
+                last_updated_version: transaction.version,
+            });
+        }
+
+        // Only user txns emit events we care about.
+        let events = match transaction.txn_data.as_ref().context("No txn_data")? {
+            TxnData::User(user_transaction) => &user_transaction.events,
+            _ => return Ok((update_paint_balance_intents, vec![])),
+        };
+
+        // Deposits that come from a call to mint are recorded as mints.
+        let mint_function_id = EntryFunctionId {
+            module: Some(MoveModuleId {
+                address: self.config.paint_contract_address.to_string(),
+                name: PAINT_FUNGIBLE_ASSET_MODULE_NAME.to_string(),
+            }),
+            name: "mint".to_string(),
+        };
+        let is_mint = entry_function_id_matches(transaction, &mint_function_id);
+        let deposit_activity_type = if is_mint {
+            PaintActivityType::Mint
+        } else {
+            PaintActivityType::Deposit
+        };
+
+        let mut create_paint_activity_intents = vec![];
+        for (event_index, event) in events.iter().enumerate() {
+            let (store_address, activity_type, amount) = match event.type_str.as_str() {
+                DEPOSIT_EVENT_TYPE => {
+                    let deposit: DepositEvent = serde_json::from_str(&event.data)
+                        .context("Failed to parse DepositEvent")?;
+                    (
+                        get_event_handle_store_address(event)?,
+                        deposit_activity_type,
+                        deposit.amount.0,
+                    )
+                },
+                WITHDRAW_EVENT_TYPE => {
+                    let withdraw: WithdrawEvent = serde_json::from_str(&event.data)
+                        .context("Failed to parse WithdrawEvent")?;
+                    (
+                        get_event_handle_store_address(event)?,
+                        PaintActivityType::Withdraw,
+                        withdraw.amount.0,
+                    )
+                },
+                DEPOSIT_MODULE_EVENT_TYPE => {
+                    let deposit: FungibleStoreModuleEvent =
+                        serde_json::from_str(&event.data).context("Failed to parse Deposit")?;
+                    (deposit.store, deposit_activity_type, deposit.amount.0)
+                },
+                WITHDRAW_MODULE_EVENT_TYPE => {
+                    let withdraw: FungibleStoreModuleEvent =
+                        serde_json::from_str(&event.data).context("Failed to parse Withdraw")?;
+                    (
+                        withdraw.store,
+                        PaintActivityType::Withdraw,
+                        withdraw.amount.0,
+                    )
+                },
+                _ => continue,
+            };
+            if !paint_store_balances.contains_key(&store_address) {
+                continue;
+            }
+            let account_address = match store_owners.get(&store_address) {
+                Some(account_address) => *account_address,
+                None => continue,
+            };
+            create_paint_activity_intents.push(CreatePaintActivityIntent {
+                transaction_version: transaction.version,
+                event_index: event_index as u64,
+                account_address,
+                store_address,
+                activity_type,
+                amount,
+            });
+        }
+
+        Ok((update_paint_balance_intents, create_paint_activity_intents))
+    }
+}
+
+/// The legacy events are emitted from the event handles in the store, so the account
+/// address of the event key is the store address.
+fn get_event_handle_store_address(event: &Event) -> Result<Address> {
+    let key = event.key.as_ref().context("No event key")?;
+    Address::from_str(&key.account_address).context("Failed to parse event key address")
+}
+
+fn get_fungible_store_struct_tag() -> MoveStructTag {
+    MoveStructTag {
+        address: "0x1".to_string(),
+        module: "fungible_asset".to_string(),
+        name: "FungibleStore".to_string(),
+        generic_type_params: vec![],
+    }
+}
@@ -0,0 +1,221 @@
+//! The writes for different canvases don't depend on each other, so once we've
+//! decoded a batch of txns (which happens sequentially) we split the writes up by
+//! canvas and apply each canvas' writes in a separate task. Within a canvas the
+//! writes are applied in the same order as before, so creating a canvas still
+//! happens before drawing on it, but it doesn't hold up writes to any other canvas.
+
+use crate::metrics::{UPDATE_ATTRIBUTION_LATENCY, WRITE_PIXELS_LATENCY, WRITTEN_PIXEL_COUNT};
+use anyhow::{Context, Result};
+use aptos_move_graphql_scalars::Address;
+use metadata_storage::{
+    MetadataStorageTrait, UpdateAttributionIntent, UpdateCanvasAccountIntent, UpdateCanvasIntent,
+};
+use pixel_storage::{CreateCanvasIntent, PixelStorageTrait, WritePixelIntent};
+use std::{collections::HashMap, sync::Arc};
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::info;
+
+/// Everything to write for a single canvas from a batch of txns, in txn order.
+#[derive(Debug)]
+pub struct CanvasPartition {
+    pub canvas_address: Address,
+    pub create_canvas_intents: Vec<CreateCanvasIntent>,
+    pub write_pixel_intents: Vec<WritePixelIntent>,
+    pub update_canvas_intents: Vec<UpdateCanvasIntent>,
+    pub update_canvas_account_intents: Vec<UpdateCanvasAccountIntent>,
+    pub update_attribution_intents: Vec<UpdateAttributionIntent>,
+}
+
+impl CanvasPartition {
+    fn new(canvas_address: Address) -> Self {
+        Self {
+            canvas_address,
+            create_canvas_intents: vec![],
+            write_pixel_intents: vec![],
+            update_canvas_intents: vec![],
+            update_canvas_account_intents: vec![],
+            update_attribution_intents: vec![],
+        }
+    }
+}
+
+/// Split the writes from a batch up by canvas, keeping the order of the writes within
+/// each canvas. The partitions are in the order we first saw each canvas.
+pub fn partition_by_canvas(
+    create_canvas_intents: Vec<CreateCanvasIntent>,
+    write_pixel_intents: Vec<WritePixelIntent>,
+    update_canvas_intents: Vec<UpdateCanvasIntent>,
+    update_canvas_account_intents: Vec<UpdateCanvasAccountIntent>,
+    update_attribution_intents: Vec<UpdateAttributionIntent>,
+) -> Vec<CanvasPartition> {
+    let mut partitions = vec![];
+    let mut indices = HashMap::new();
+    for intent in create_canvas_intents {
+        get_partition(&mut partitions, &mut indices, intent.canvas_address)
+            .create_canvas_intents
+            .push(intent);
+    }
+    for intent in write_pixel_intents {
+        get_partition(&mut partitions, &mut indices, intent.canvas_address)
+            .write_pixel_intents
+            .push(intent);
+    }
+    for intent in update_canvas_intents {
+        get_partition(&mut partitions, &mut indices, intent.canvas_address)
+            .update_canvas_intents
+            .push(intent);
+    }
+    for intent in update_canvas_account_intents {
+        get_partition(&mut partitions, &mut indices, intent.canvas_address)
+            .update_canvas_account_intents
+            .push(intent);
+    }
+    for intent in update_attribution_intents {
+        get_partition(&mut partitions, &mut indices, intent.canvas_address)
+            .update_attribution_intents
+            .push(intent);
+    }
+    partitions
+}
+
+fn get_partition<'a>(
+    partitions: &'a mut Vec<CanvasPartition>,
+    indices: &mut HashMap<Address, usize>,
+    canvas_address: Address,
+) -> &'a mut CanvasPartition {
+    let index = *indices.entry(canvas_address).or_insert_with(|| {
+        partitions.push(CanvasPartition::new(canvas_address));
+        partitions.len() - 1
+    });
+    &mut partitions[index]
+}
+
+/// Where to write the partitions to, and which of the writes to do.
+#[derive(Clone, Debug)]
+pub struct PartitionWriter {
+    pub pixels_storage: Arc<dyn PixelStorageTrait>,
+    pub metadata_storage: Arc<dyn MetadataStorageTrait>,
+    pub disable_pixel_processing: bool,
+    pub disable_metadata_processing: bool,
+}
+
+impl PartitionWriter {
+    /// Write the partitions, with up to `max_concurrent_canvases` of them being
+    /// written at once. If any of them fail we return an error once they've all
+    /// finished, the rest will have been written. This is fine since the batch will
+    /// be processed again and all the writes are idempotent.
+    pub async fn write_all(
+        &self,
+        partitions: Vec<CanvasPartition>,
+        max_concurrent_canvases: usize,
+    ) -> Result<()> {
+        let semaphore = Arc::new(Semaphore::new(max_concurrent_canvases.max(1)));
+        let mut tasks = JoinSet::new();
+        for partition in partitions {
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .context("Failed to acquire permit to write canvas")?;
+            let writer = self.clone();
+            tasks.spawn(async move {
+                let canvas_address = partition.canvas_address;
+                let result = writer
+                    .write(partition)
+                    .await
+                    .context(format!("Failed to write canvas {}", canvas_address));
+                drop(permit);
+                result
+            });
+        }
+        while let Some(result) = tasks.join_next().await {
+            result.context("Canvas write task panicked")??;
+        }
+        Ok(())
+    }
+
+    async fn write(&self, partition: CanvasPartition) -> Result<()> {
+        let CanvasPartition {
+            canvas_address,
+            create_canvas_intents,
+            write_pixel_intents,
+            update_canvas_intents,
+            update_canvas_account_intents,
+            update_attribution_intents,
+        } = partition;
+
+        if !self.disable_pixel_processing {
+            // Create the canvas before drawing on it.
+            for create_canvas_intent in create_canvas_intents {
+                info!("Creating canvas {}", create_canvas_intent.canvas_address);
+                self.pixels_storage
+                    .create_canvas(create_canvas_intent)
+                    .await
+                    .context("Failed to create canvas in storage")?;
+            }
+
+            // Write pixels.
+            if !write_pixel_intents.is_empty() {
+                info!(
+                    "Writing {} pixels to canvas {}",
+                    write_pixel_intents.len(),
+                    canvas_address
+                );
+                let num_pixels = write_pixel_intents.len();
+                let timer = WRITE_PIXELS_LATENCY.start_timer();
+                self.pixels_storage
+                    .write_pixels(write_pixel_intents)
+                    .await
+                    .context("Failed to write pixel in storage")?;
+                timer.observe_duration();
+                WRITTEN_PIXEL_COUNT.inc_by(num_pixels as u64);
+            }
+        }
+
+        if !self.disable_metadata_processing {
+            // Create / update the canvas. We do this before attribution so the canvas
+            // exists in the DB by the time any attribution for it does.
+            for update_canvas_intent in update_canvas_intents {
+                info!(
+                    "Updating canvas {} (from txn {})",
+                    update_canvas_intent.canvas_address, update_canvas_intent.last_updated_version
+                );
+                self.metadata_storage
+                    .update_canvas(update_canvas_intent)
+                    .await
+                    .context("Failed to update canvas in storage")?;
+            }
+
+            // Update the per account state of the canvas. These must be applied in
+            // order, since e.g. clearing the last contributions affects the ones
+            // written before it.
+            for update_canvas_account_intent in update_canvas_account_intents {
+                self.metadata_storage
+                    .update_canvas_account(update_canvas_account_intent)
+                    .await
+                    .context("Failed to update canvas account in storage")?;
+            }
+
+            // Update attribution.
+            let len = update_attribution_intents.len();
+            for (i, update_attribution_intent) in update_attribution_intents.into_iter().enumerate()
+            {
+                info!(
+                    "Updating attribution for canvas {} index {} (intent {}/{})",
+                    update_attribution_intent.canvas_address,
+                    update_attribution_intent.index,
+                    i + 1,
+                    len,
+                );
+                let timer = UPDATE_ATTRIBUTION_LATENCY.start_timer();
+                self.metadata_storage
+                    .update_attribution(update_attribution_intent)
+                    .await
+                    .context("Failed to update attribution in storage")?;
+                timer.observe_duration();
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,28 +1,74 @@
+use crate::{
+    canvas_tables::{CanvasTables, TableHandleUpdate},
+    draw_args::{decode_draw_args, decode_draw_one_args, get_requested_indices},
+    error::{CanvasProcessorError, CanvasProcessorErrorKind, ErrorPolicy, ErrorPolicyConfig},
+    layout::CanvasLayoutVersion,
+    metrics::{
+        CANVAS_COUNT, DRAW_MISMATCH_COUNT, LAST_PROCESSED_VERSION, LAST_TRANSACTION_TIMESTAMP_SECS,
+        PROCESSED_TRANSACTION_COUNT, PROCESSOR_LAG_SECS,
+    },
+    partition::{partition_by_canvas, PartitionWriter},
+    smart_table::decode_smart_table_bucket,
+    utils::{
+        addresses_match, entry_function_id_matches, get_framework_collection_struct_tag,
+        get_object_core_struct_tag, struct_tags_match,
+    },
+};
 use anyhow::{bail, Context as AnyhowContext, Result};
-use aptos_move_graphql_scalars::Address;
+use aptos_move_graphql_scalars::{Address, U64};
 use aptos_processor_framework::{
     indexer_protos::transaction::v1::{
         transaction::TxnData, transaction_payload::Payload, write_set_change::Change,
         EntryFunctionId, MoveModuleId, MoveStructTag, Transaction,
     },
     txn_parsers::get_clean_entry_function_payload,
-    ProcessingResult, ProcessorTrait,
+    ProcessingResult, ProcessorTrait, StorageTrait,
+};
+use metadata_storage::{
+    CanvasAccountChange, CanvasTableHandle, CanvasTableName, CreateDeadLetterTransactionIntent,
+    DeadLetterAction, MetadataStorageTrait, UpdateAttributionIntent, UpdateCanvasAccountIntent,
+    UpdateCanvasIntent, UpdateCollectionIntent, UpdateContractLayoutIntent,
 };
-use metadata_storage::{MetadataStorageTrait, UpdateAttributionIntent};
-use move_types::{Canvas, Entry, Object};
+use move_types::{Canvas, Collection, CollectionConfig, Object, ObjectCore};
 use pixel_storage::{CreateCanvasIntent, HardcodedColor, PixelStorageTrait, WritePixelIntent};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::{str::FromStr, sync::Arc};
-use tracing::info;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
 
 const CANVAS_TOKEN_MODULE_NAME: &str = "canvas_token";
+const CANVAS_COLLECTION_MODULE_NAME: &str = "canvas_collection";
+
+/// Before we supported indexing multiple deployments, the checkpoint of the canvas
+/// processor was keyed by just this.
+const LEGACY_PROCESSOR_NAME: &str = "CanvasProcessor";
+
+/// The entry functions in the canvas_collection module that change the collection.
+const CANVAS_COLLECTION_ENTRY_FUNCTION_NAMES: &[&str] = &[
+    "set_uri",
+    "set_description",
+    "update_max_canvas_dimension",
+    "transfer",
+];
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct CanvasProcessorConfig {
-    // TODO: This should be an Address instead
-    pub canvas_contract_address: String,
+    /// The addresses of the contract deployments to index. We run a separate
+    /// CanvasProcessor for each, with its own stream and checkpoint, so deployments
+    /// can be added later and backfilled without reprocessing the others. For
+    /// configs from before we supported multiple deployments, this can also be given
+    /// as a single address under `canvas_contract_address`.
+    #[serde(
+        alias = "canvas_contract_address",
+        deserialize_with = "deserialize_contract_addresses"
+    )]
+    pub canvas_contract_addresses: Vec<Address>,
 
     /// If set, disable metadata processing and only process pixel data.
     #[serde(default)]
@@ -31,46 +77,272 @@ pub struct CanvasProcessorConfig {
     /// If set, disable pixel processing and only process metadata.
     #[serde(default)]
     pub disable_pixel_processing: bool,
+
+    /// What to do when we fail to process a txn, per kind of error.
+    #[serde(default)]
+    pub error_policy: ErrorPolicyConfig,
+
+    /// Txns are always decoded one at a time, in order. Once we've done that for a
+    /// batch, the writes to storage for each canvas are applied independently of the
+    /// other canvases. This is how many canvases we write to at once. Defaults to the
+    /// number of CPUs.
+    #[serde(default = "CanvasProcessorConfig::default_max_concurrent_canvases")]
+    pub max_concurrent_canvases: usize,
+}
+
+/// Accept either a list of addresses or, like the old `canvas_contract_address`, a
+/// single one.
+fn deserialize_contract_addresses<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Address>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Address),
+        Many(Vec<Address>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(address) => vec![address],
+        OneOrMany::Many(addresses) => addresses,
+    })
+}
+
+impl CanvasProcessorConfig {
+    fn default_max_concurrent_canvases() -> usize {
+        std::thread::available_parallelism()
+            .map(|parallelism| parallelism.get())
+            .unwrap_or(1)
+    }
 }
 
 #[derive(Debug)]
 pub struct CanvasProcessor {
     config: CanvasProcessorConfig,
+    /// The contract deployment this processor indexes, one of those in the config.
+    contract_address: Address,
+    /// The name is used as the key for the checkpoint, so it includes the contract.
+    name: &'static str,
     pixels_storage: Arc<dyn PixelStorageTrait>,
     metadata_storage: Arc<dyn MetadataStorageTrait>,
+    /// This is loaded from the metadata storage the first time we process txns.
+    state: Mutex<Option<CanvasProcessorState>>,
+    /// If set, we only write changes to these canvases. See `for_rebuild`.
+    canvas_filter: Option<HashSet<Address>>,
+}
+
+/// What the processor knows about the canvases beyond what is in the txns it is
+/// currently processing.
+#[derive(Debug)]
+struct CanvasProcessorState {
+    canvas_tables: CanvasTables,
+    /// We need these to turn the (x, y) of a draw into a pixel index and to check
+    /// that indices are in bounds.
+    canvas_dimensions: HashMap<Address, (u16, u16)>,
+    /// See `ErrorPolicy::Quarantine`.
+    quarantined_canvases: HashSet<Address>,
+    /// The layout of the canvas_token structs as of the txn we're processing.
+    layout: CanvasLayoutVersion,
+}
+
+/// Everything we want to write to storage as a result of processing a txn.
+#[derive(Debug, Default)]
+struct TransactionIntents {
+    create_canvas_intents: Vec<CreateCanvasIntent>,
+    write_pixel_intents: Vec<WritePixelIntent>,
+    update_attribution_intents: Vec<UpdateAttributionIntent>,
+    update_canvas_intents: Vec<UpdateCanvasIntent>,
+    update_collection_intents: Vec<UpdateCollectionIntent>,
+    canvas_table_handles: Vec<CanvasTableHandle>,
+    update_canvas_account_intents: Vec<UpdateCanvasAccountIntent>,
+    update_contract_layout_intents: Vec<UpdateContractLayoutIntent>,
+}
+
+impl TransactionIntents {
+    fn extend(&mut self, other: TransactionIntents) {
+        self.create_canvas_intents
+            .extend(other.create_canvas_intents);
+        self.write_pixel_intents.extend(other.write_pixel_intents);
+        self.update_attribution_intents
+            .extend(other.update_attribution_intents);
+        self.update_canvas_intents
+            .extend(other.update_canvas_intents);
+        self.update_collection_intents
+            .extend(other.update_collection_intents);
+        self.canvas_table_handles.extend(other.canvas_table_handles);
+        self.update_canvas_account_intents
+            .extend(other.update_canvas_account_intents);
+        self.update_contract_layout_intents
+            .extend(other.update_contract_layout_intents);
+    }
+
+    /// Drop the changes to any canvases other than these, along with anything that
+    /// isn't tied to a canvas.
+    fn retain_canvases(&mut self, canvas_addresses: &HashSet<Address>) {
+        self.create_canvas_intents
+            .retain(|i| canvas_addresses.contains(&i.canvas_address));
+        self.write_pixel_intents
+            .retain(|i| canvas_addresses.contains(&i.canvas_address));
+        self.update_attribution_intents
+            .retain(|i| canvas_addresses.contains(&i.canvas_address));
+        self.update_canvas_intents
+            .retain(|i| canvas_addresses.contains(&i.canvas_address));
+        self.update_canvas_account_intents
+            .retain(|i| canvas_addresses.contains(&i.canvas_address));
+        self.update_collection_intents.clear();
+    }
+
+    /// Drop the pixel writes and attribution for these canvases.
+    fn drop_draws(&mut self, canvas_addresses: &HashSet<Address>) {
+        self.write_pixel_intents
+            .retain(|i| !canvas_addresses.contains(&i.canvas_address));
+        self.update_attribution_intents
+            .retain(|i| !canvas_addresses.contains(&i.canvas_address));
+    }
+
+    /// The canvases this txn changes.
+    fn canvas_addresses(&self) -> HashSet<Address> {
+        let mut canvas_addresses = HashSet::new();
+        canvas_addresses.extend(self.create_canvas_intents.iter().map(|i| i.canvas_address));
+        canvas_addresses.extend(self.write_pixel_intents.iter().map(|i| i.canvas_address));
+        canvas_addresses.extend(
+            self.update_attribution_intents
+                .iter()
+                .map(|i| i.canvas_address),
+        );
+        canvas_addresses.extend(self.update_canvas_intents.iter().map(|i| i.canvas_address));
+        canvas_addresses.extend(
+            self.update_canvas_account_intents
+                .iter()
+                .map(|i| i.canvas_address),
+        );
+        canvas_addresses
+    }
 }
 
 impl CanvasProcessor {
     pub fn new(
         config: CanvasProcessorConfig,
+        contract_address: Address,
         pixels_storage: Arc<dyn PixelStorageTrait>,
         metadata_storage: Arc<dyn MetadataStorageTrait>,
     ) -> Result<Self> {
         if config.disable_metadata_processing && config.disable_pixel_processing {
             bail!("disable_metadata_processing and disable_pixel_processing are both set to true, this is invalid");
         }
+        // ProcessorTrait::name requires a static str. We only build one processor per
+        // contract, so leaking the name is fine.
+        let name = Box::leak(Self::get_name(&contract_address).into_boxed_str());
         Ok(Self {
             config,
+            contract_address,
+            name,
             pixels_storage,
             metadata_storage,
+            state: Mutex::new(None),
+            canvas_filter: None,
         })
     }
 
+    /// The name of the processor for the given deployment, which is also the key of
+    /// its checkpoint.
+    fn get_name(contract_address: &Address) -> String {
+        format!(
+            "CanvasProcessor:0x{}",
+            contract_address.to_canonical_string()
+        )
+    }
+
+    /// Turn this into a processor for rebuilding the given canvases. It only writes
+    /// changes to those canvases, plus what the processor itself depends on (e.g. the
+    /// table handles), and has a different name so it doesn't share the checkpoint or
+    /// dead letters of the main processor. Errors for those canvases always fail
+    /// processing, regardless of `error_policy`.
+    pub fn for_rebuild(mut self, canvas_addresses: HashSet<Address>) -> Self {
+        self.name = Box::leak(
+            format!(
+                "CanvasRebuild:0x{}",
+                self.contract_address.to_canonical_string()
+            )
+            .into_boxed_str(),
+        );
+        self.canvas_filter = Some(canvas_addresses);
+        self
+    }
+
     pub fn get_canvas_struct_tag(&self) -> MoveStructTag {
         MoveStructTag {
-            address: self.config.canvas_contract_address.clone(),
+            address: self.contract_address.to_string(),
             module: CANVAS_TOKEN_MODULE_NAME.to_string(),
             name: "Canvas".to_string(),
             generic_type_params: vec![],
         }
     }
+
+    pub fn get_collection_config_struct_tag(&self) -> MoveStructTag {
+        MoveStructTag {
+            address: self.contract_address.to_string(),
+            module: CANVAS_COLLECTION_MODULE_NAME.to_string(),
+            name: "CollectionConfig".to_string(),
+            generic_type_params: vec![],
+        }
+    }
+
+    pub fn get_collection_refs_struct_tag(&self) -> MoveStructTag {
+        MoveStructTag {
+            address: self.contract_address.to_string(),
+            module: CANVAS_COLLECTION_MODULE_NAME.to_string(),
+            name: "CollectionRefs".to_string(),
+            generic_type_params: vec![],
+        }
+    }
 }
 
-/// A processor that just prints the txn version.
+/// Before we supported indexing multiple deployments, the checkpoint was keyed by
+/// `LEGACY_PROCESSOR_NAME`. If only one deployment is configured and it has no
+/// checkpoint of its own yet, we copy that one over, so upgrading doesn't reprocess
+/// everything from `initial_starting_version`.
+pub async fn migrate_legacy_checkpoint<S: StorageTrait>(
+    config: &CanvasProcessorConfig,
+    storage: &S,
+) -> Result<()> {
+    let contract_address = match config.canvas_contract_addresses.as_slice() {
+        [contract_address] => contract_address,
+        _ => return Ok(()),
+    };
+    let processor_name = CanvasProcessor::get_name(contract_address);
+    if storage
+        .read_last_processed_version(&processor_name)
+        .await
+        .context("Failed to read last processed version")?
+        .is_some()
+    {
+        return Ok(());
+    }
+    let legacy_version = match storage
+        .read_last_processed_version(LEGACY_PROCESSOR_NAME)
+        .await
+        .context("Failed to read legacy last processed version")?
+    {
+        Some(legacy_version) => legacy_version,
+        None => return Ok(()),
+    };
+    info!(
+        "Copying last processed version {} from {} to {}",
+        legacy_version, LEGACY_PROCESSOR_NAME, processor_name
+    );
+    storage
+        .write_last_processed_version(&processor_name, legacy_version)
+        .await
+        .context("Failed to write last processed version")
+}
+
+/// Processes the txns of one deployment of the contract, writing the canvases to the
+/// pixel storage and everything else about them (attribution, table handles, etc.)
+/// to the metadata storage.
 #[async_trait::async_trait]
 impl ProcessorTrait for CanvasProcessor {
     fn name(&self) -> &'static str {
-        "CanvasProcessor"
+        self.name
     }
 
     async fn process_transactions(
@@ -79,9 +351,51 @@ impl ProcessorTrait for CanvasProcessor {
         start_version: u64,
         end_version: u64,
     ) -> Result<ProcessingResult> {
-        let mut all_create_canvas_intents = Vec::new();
-        let mut all_write_pixel_intents = Vec::new();
-        let mut all_update_attribution_intents = Vec::new();
+        let mut all_intents = TransactionIntents::default();
+        let mut create_dead_letter_transaction_intents = Vec::new();
+
+        // Load what we know about the canvases if we haven't already.
+        let mut state = self.state.lock().await;
+        if state.is_none() {
+            let table_handles = self
+                .metadata_storage
+                .read_canvas_table_handles()
+                .await
+                .context("Failed to read canvas table handles")?;
+            let canvas_dimensions = self
+                .metadata_storage
+                .read_canvas_dimensions()
+                .await
+                .context("Failed to read canvas dimensions")?;
+            let quarantined_canvases = self
+                .metadata_storage
+                .read_quarantined_canvases(self.name())
+                .await
+                .context("Failed to read quarantined canvases")?;
+            let layout = match self
+                .metadata_storage
+                .read_contract_layout(&self.contract_address)
+                .await
+                .context("Failed to read contract layout")?
+            {
+                Some(layout_version) => CanvasLayoutVersion::from_u16(layout_version)?,
+                None => CanvasLayoutVersion::LATEST,
+            };
+            *state = Some(CanvasProcessorState {
+                canvas_tables: CanvasTables::new(table_handles),
+                canvas_dimensions,
+                quarantined_canvases: quarantined_canvases.into_iter().collect(),
+                layout,
+            });
+        }
+        let state = state.as_mut().unwrap();
+
+        let num_transactions = transactions.len();
+        let last_timestamp_secs = transactions
+            .last()
+            .and_then(|transaction| transaction.timestamp.as_ref())
+            .map(|timestamp| timestamp.seconds);
+
         for transaction in transactions {
             // Skip failed transactions.
             if let Some(info) = &transaction.info {
@@ -90,25 +404,105 @@ impl ProcessorTrait for CanvasProcessor {
                 }
             }
 
-            // todo process canvas_token::create and create images for that
-            // todo create a storage interface with like create that takes in a default color
-            // a width and height, then methods for writing pixels to it, and also reading
-            // the full thing. it should handle the read update write process inside it
-            let (write_pixel_intents, update_attribution_intents) =
-                self.process_draw(&transaction).context(format!(
-                    "Failed at process_draw for txn version {}",
-                    transaction.version
-                ))?;
-            all_write_pixel_intents.extend(write_pixel_intents);
-            all_update_attribution_intents.extend(update_attribution_intents);
-            let create_canvas_intent = self.process_create(&transaction).context(format!(
-                "Failed at process_create for txn version {}",
-                transaction.version
-            ))?;
-            if let Some(create_canvas_intent) = create_canvas_intent {
-                all_create_canvas_intents.push(create_canvas_intent);
-            }
+            let mut intents = TransactionIntents::default();
+            let result = self
+                .process_transaction(&transaction, state, &all_intents, &mut intents)
+                .await;
+
+            let (error, canvas_address, kind, action) = match result {
+                Ok(()) => {
+                    if let Some(canvas_filter) = &self.canvas_filter {
+                        intents.retain_canvases(canvas_filter);
+                    }
+                    let quarantined_canvas = intents
+                        .canvas_addresses()
+                        .into_iter()
+                        .find(|canvas_address| state.quarantined_canvases.contains(canvas_address));
+                    match quarantined_canvas {
+                        None => {
+                            // Creating a canvas, including resetting it when it's
+                            // cleared, happens before any drawing in the batch, so
+                            // drop the earlier draws or they'd end up on top.
+                            let created_canvases = intents
+                                .create_canvas_intents
+                                .iter()
+                                .map(|i| i.canvas_address)
+                                .collect::<HashSet<_>>();
+                            all_intents.drop_draws(&created_canvases);
+                            all_intents.extend(intents);
+                            continue;
+                        },
+                        Some(canvas_address) => (
+                            anyhow::anyhow!("Canvas {} is quarantined", canvas_address),
+                            Some(canvas_address),
+                            "quarantined_canvas",
+                            DeadLetterAction::Skipped,
+                        ),
+                    }
+                },
+                Err(error) => {
+                    let (kind, canvas_address) = match classify_error(&error) {
+                        Some(classified) => classified,
+                        None => return Err(error),
+                    };
+                    let policy = match (&self.canvas_filter, canvas_address) {
+                        // When rebuilding, skipping a txn for a canvas we're rebuilding
+                        // would leave it wrong, so we stop instead.
+                        (Some(canvas_filter), Some(canvas_address))
+                            if canvas_filter.contains(&canvas_address) =>
+                        {
+                            ErrorPolicy::Fail
+                        },
+                        _ => self.config.error_policy.get(kind),
+                    };
+                    let action = match (policy, canvas_address) {
+                        (ErrorPolicy::Fail, _) => return Err(error),
+                        (ErrorPolicy::Quarantine, Some(canvas_address)) => {
+                            state.quarantined_canvases.insert(canvas_address);
+                            DeadLetterAction::Quarantined
+                        },
+                        (ErrorPolicy::SkipAndRecord, _) | (ErrorPolicy::Quarantine, None) => {
+                            DeadLetterAction::Skipped
+                        },
+                    };
+                    (error, canvas_address, kind.as_str(), action)
+                },
+            };
+
+            warn!(
+                "Skipping txn {} ({}): {:#}",
+                transaction.version,
+                action.as_str(),
+                error
+            );
+            // The table handles and layout describe what is on chain regardless of
+            // whether we could process the rest of the txn, and they're already in the
+            // state, so we keep them.
+            all_intents
+                .canvas_table_handles
+                .extend(intents.canvas_table_handles);
+            all_intents
+                .update_contract_layout_intents
+                .extend(intents.update_contract_layout_intents);
+            create_dead_letter_transaction_intents.push(CreateDeadLetterTransactionIntent {
+                processor_name: self.name().to_string(),
+                transaction_version: transaction.version,
+                canvas_address,
+                error_kind: kind.to_string(),
+                error_message: format!("{:#}", error),
+                action,
+            });
         }
+        let TransactionIntents {
+            create_canvas_intents: all_create_canvas_intents,
+            write_pixel_intents: all_write_pixel_intents,
+            update_attribution_intents: all_update_attribution_intents,
+            update_canvas_intents: all_update_canvas_intents,
+            update_collection_intents: all_update_collection_intents,
+            canvas_table_handles: all_canvas_table_handles,
+            update_canvas_account_intents: all_update_canvas_account_intents,
+            update_contract_layout_intents: all_update_contract_layout_intents,
+        } = all_intents;
         info!(
             start_version = start_version,
             end_version = end_version,
@@ -117,82 +511,242 @@ impl ProcessorTrait for CanvasProcessor {
             num_pixels_to_write = all_write_pixel_intents.len()
         );
 
-        if !self.config.disable_pixel_processing {
-            // Create canvases.
-            for create_canvas_intent in all_create_canvas_intents {
-                info!("Creating canvas {}", create_canvas_intent.canvas_address);
-                self.pixels_storage
-                    .create_canvas(create_canvas_intent)
-                    .await
-                    .context("Failed to create canvas in storage")?;
-            }
+        // Write the table handles. We always do this, even if metadata processing is
+        // disabled, since the processor itself depends on them.
+        for canvas_table_handle in all_canvas_table_handles {
+            self.metadata_storage
+                .write_canvas_table_handle(canvas_table_handle)
+                .await
+                .context("Failed to write canvas table handle to storage")?;
+        }
+        for update_contract_layout_intent in all_update_contract_layout_intents {
+            self.metadata_storage
+                .update_contract_layout(update_contract_layout_intent)
+                .await
+                .context("Failed to update contract layout in storage")?;
+        }
 
-            // Write pixels.
-            if !all_write_pixel_intents.is_empty() {
-                info!(
-                    "Writing {} pixels (from txns {} to {})",
-                    all_write_pixel_intents.len(),
-                    start_version,
-                    end_version
-                );
-                self.pixels_storage
-                    .write_pixels(all_write_pixel_intents)
-                    .await
-                    .context("Failed to write pixel in storage")?;
-            }
+        // Likewise for the txns we skipped.
+        for create_dead_letter_transaction_intent in create_dead_letter_transaction_intents {
+            self.metadata_storage
+                .create_dead_letter_transaction(create_dead_letter_transaction_intent)
+                .await
+                .context("Failed to create dead letter transaction in storage")?;
         }
 
+        // The collection isn't tied to any one canvas, so we write it first.
         if !self.config.disable_metadata_processing {
-            // Update attribution.
-            let len = all_update_attribution_intents.len();
-            for (i, update_attribution_intent) in
-                all_update_attribution_intents.into_iter().enumerate()
-            {
+            for update_collection_intent in all_update_collection_intents {
                 info!(
-                    "Updating attribution for canvas {} index {} (intent {}/{} from txns {} to {})",
-                    update_attribution_intent.canvas_address,
-                    update_attribution_intent.index,
-                    i + 1,
-                    len,
-                    start_version,
-                    end_version
+                    "Updating collection {} (from txn {})",
+                    update_collection_intent.collection_address,
+                    update_collection_intent.last_updated_version
                 );
                 self.metadata_storage
-                    .update_attribution(update_attribution_intent)
+                    .update_collection(update_collection_intent)
                     .await
-                    .context("Failed to update attribution in storage")?;
+                    .context("Failed to update collection in storage")?;
             }
         }
 
+        // Everything else is written per canvas, with canvases written concurrently.
+        let partitions = partition_by_canvas(
+            all_create_canvas_intents,
+            all_write_pixel_intents,
+            all_update_canvas_intents,
+            all_update_canvas_account_intents,
+            all_update_attribution_intents,
+        );
+        let writer = PartitionWriter {
+            pixels_storage: self.pixels_storage.clone(),
+            metadata_storage: self.metadata_storage.clone(),
+            disable_pixel_processing: self.config.disable_pixel_processing,
+            disable_metadata_processing: self.config.disable_metadata_processing,
+        };
+        writer
+            .write_all(partitions, self.config.max_concurrent_canvases)
+            .await
+            .context(format!(
+                "Failed to write canvases for txns {} to {}",
+                start_version, end_version
+            ))?;
+
+        PROCESSED_TRANSACTION_COUNT
+            .with_label_values(&[self.name()])
+            .inc_by(num_transactions as u64);
+        LAST_PROCESSED_VERSION
+            .with_label_values(&[self.name()])
+            .set(end_version as i64);
+        CANVAS_COUNT
+            .with_label_values(&[self.name()])
+            .set(state.canvas_tables.num_canvases() as i64);
+        if let Some(last_timestamp_secs) = last_timestamp_secs {
+            let now_secs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .context("Time went backwards")?
+                .as_secs_f64();
+            LAST_TRANSACTION_TIMESTAMP_SECS
+                .with_label_values(&[self.name()])
+                .set(last_timestamp_secs as f64);
+            PROCESSOR_LAG_SECS
+                .with_label_values(&[self.name()])
+                .set(now_secs - last_timestamp_secs as f64);
+        }
+
         Ok((start_version, end_version))
     }
 }
 
 impl CanvasProcessor {
+    /// Process a single txn, adding what should be written to storage to `intents`.
+    /// `batch_intents` is everything from the earlier txns in the batch.
+    async fn process_transaction(
+        &self,
+        transaction: &Transaction,
+        state: &mut CanvasProcessorState,
+        batch_intents: &TransactionIntents,
+        intents: &mut TransactionIntents,
+    ) -> Result<()> {
+        // This must happen first so we decode everything else in the txn with the
+        // layout the contract has after it.
+        let update_contract_layout_intent = self
+            .process_module_publish(transaction, &mut state.layout)
+            .context(format!(
+                "Failed at process_module_publish for txn version {}",
+                transaction.version
+            ))?;
+        intents
+            .update_contract_layout_intents
+            .extend(update_contract_layout_intent);
+        // This must happen before process_draw and process_canvas_accounts so we
+        // know about any tables created or replaced in this txn.
+        let (
+            update_canvas_intents,
+            canvas_table_handles,
+            update_canvas_account_intents,
+            reset_canvas_intents,
+        ) = self
+            .process_canvas_writes(transaction, state.layout, &mut state.canvas_tables)
+            .context(format!(
+                "Failed at process_canvas_writes for txn version {}",
+                transaction.version
+            ))?;
+        for update_canvas_intent in &update_canvas_intents {
+            state.canvas_dimensions.insert(
+                update_canvas_intent.canvas_address,
+                (update_canvas_intent.width, update_canvas_intent.height),
+            );
+        }
+        intents.update_canvas_intents.extend(update_canvas_intents);
+        intents.canvas_table_handles.extend(canvas_table_handles);
+        intents
+            .update_canvas_account_intents
+            .extend(update_canvas_account_intents);
+        intents.create_canvas_intents.extend(reset_canvas_intents);
+        // todo process canvas_token::create and create images for that
+        // todo create a storage interface with like create that takes in a default color
+        // a width and height, then methods for writing pixels to it, and also reading
+        // the full thing. it should handle the read update write process inside it
+        let (write_pixel_intents, update_attribution_intents) = self
+            .process_draw(transaction, &state.canvas_tables, &state.canvas_dimensions)
+            .context(format!(
+                "Failed at process_draw for txn version {}",
+                transaction.version
+            ))?;
+        intents.write_pixel_intents.extend(write_pixel_intents);
+        intents
+            .update_attribution_intents
+            .extend(update_attribution_intents);
+        let create_canvas_intent =
+            self.process_create(transaction, state.layout)
+                .context(format!(
+                    "Failed at process_create for txn version {}",
+                    transaction.version
+                ))?;
+        if let Some(create_canvas_intent) = create_canvas_intent {
+            // This is how we know when the canvas was created, e.g. for rebuilding it.
+            for canvas_table_handle in &mut intents.canvas_table_handles {
+                if canvas_table_handle.canvas_address == create_canvas_intent.canvas_address {
+                    canvas_table_handle.created_with_canvas = Some(true);
+                }
+            }
+            intents.create_canvas_intents.push(create_canvas_intent);
+        }
+        let update_canvas_account_intents = self
+            .process_canvas_accounts(transaction, &state.canvas_tables)
+            .context(format!(
+                "Failed at process_canvas_accounts for txn version {}",
+                transaction.version
+            ))?;
+        intents
+            .update_canvas_account_intents
+            .extend(update_canvas_account_intents);
+        let update_collection_intents = self.process_collection(transaction).context(format!(
+            "Failed at process_collection for txn version {}",
+            transaction.version
+        ))?;
+        intents
+            .update_collection_intents
+            .extend(update_collection_intents);
+
+        // Make sure the pixel storage has every canvas we're about to draw on, either
+        // already or because it's created earlier in the batch.
+        if !self.config.disable_pixel_processing {
+            let created_canvases: HashSet<Address> = batch_intents
+                .create_canvas_intents
+                .iter()
+                .chain(intents.create_canvas_intents.iter())
+                .map(|intent| intent.canvas_address)
+                .collect();
+            let drawn_canvases: HashSet<Address> = intents
+                .write_pixel_intents
+                .iter()
+                .map(|intent| intent.canvas_address)
+                .collect();
+            for canvas_address in drawn_canvases {
+                if created_canvases.contains(&canvas_address) {
+                    continue;
+                }
+                if !self
+                    .pixels_storage
+                    .canvas_exists(&canvas_address)
+                    .await
+                    .context("Failed to check if canvas exists in storage")?
+                {
+                    return Err(CanvasProcessorError::MissingCanvas { canvas_address }.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn process_draw(
         &self,
         transaction: &Transaction,
+        canvas_tables: &CanvasTables,
+        canvas_dimensions: &HashMap<Address, (u16, u16)>,
     ) -> Result<(Vec<WritePixelIntent>, Vec<UpdateAttributionIntent>)> {
         let nothing = Ok((vec![], vec![]));
 
         // Skip this transaction if this wasn't a draw transaction.
         let draw_function_id = EntryFunctionId {
             module: Some(MoveModuleId {
-                address: self.config.canvas_contract_address.clone(),
+                address: self.contract_address.to_string(),
                 name: CANVAS_TOKEN_MODULE_NAME.to_string(),
             }),
             name: "draw".to_string(),
         };
         let draw_one_function_id = EntryFunctionId {
             module: Some(MoveModuleId {
-                address: self.config.canvas_contract_address.clone(),
+                address: self.contract_address.to_string(),
                 name: CANVAS_TOKEN_MODULE_NAME.to_string(),
             }),
             name: "draw_one".to_string(),
         };
-        if !(entry_function_id_matches(transaction, &draw_function_id)
-            || entry_function_id_matches(transaction, &draw_one_function_id))
-        {
+        let is_draw = entry_function_id_matches(transaction, &draw_function_id);
+        let is_draw_one = entry_function_id_matches(transaction, &draw_one_function_id);
+        if !(is_draw || is_draw_one) {
             return nothing;
         }
 
@@ -204,7 +758,7 @@ impl CanvasProcessor {
         };
 
         let request = user_transaction.request.as_ref().context("No request")?;
-        let payload = request.payload.as_ref().unwrap();
+        let payload = request.payload.as_ref().context("No payload")?;
         let entry_function_payload = match payload.payload.as_ref().context("No payload")? {
             Payload::EntryFunctionPayload(payload) => payload,
             _ => return nothing,
@@ -212,69 +766,159 @@ impl CanvasProcessor {
 
         let clean_entry_function_payload =
             get_clean_entry_function_payload(entry_function_payload, 0);
+        let arguments = &clean_entry_function_payload.arguments;
 
-        let first_arg = clean_entry_function_payload.arguments[0].clone();
+        let first_arg = arguments.first().context("No arguments")?.clone();
 
         let obj: Object = serde_json::from_value(first_arg).context("Failed to parse as Object")?;
         let canvas_address = obj.inner;
 
-        // TODO: This is a bit flaky.
-        let draw_value_type = "vector<0x1::smart_table::Entry<u32, u8>>".to_string();
+        // The write set tells us the full contents of every bucket the draw touched,
+        // which includes pixels that didn't change, so we use the arguments to know
+        // which pixels the sender actually drew.
+        let requested_pixels = if is_draw {
+            decode_draw_args(arguments)
+        } else {
+            decode_draw_one_args(arguments)
+        }
+        .map_err(|error| CanvasProcessorError::Decode {
+            canvas_address: Some(canvas_address),
+            message: format!("Failed to decode draw arguments: {:#}", error),
+        })?;
 
         let info = transaction.info.as_ref().context("No info")?;
 
         let sender =
             Address::from_str(&request.sender).context("Failed to parse sender address")?;
 
+        // If we don't know the handle of the pixels table we must have missed the
+        // creation of the canvas, in which case it must be reprocessed from there.
+        if !canvas_tables.has_table(&canvas_address, CanvasTableName::Pixels) {
+            return Err(CanvasProcessorError::MissingCanvas { canvas_address }.into());
+        }
+        let dimensions = canvas_dimensions.get(&canvas_address);
+
         let mut write_pixel_intents = vec![];
-        let mut update_attribution_intents = vec![];
+        // The final color of every pixel in the buckets written by this txn.
+        let mut written_pixels = HashMap::new();
 
         for change in &info.changes {
             match change.change.as_ref().context("No change")? {
                 // There could be multiple WriteTableItems since draw supports drawing
                 // multiple pixels at once.
-                Change::WriteTableItem(resource) => {
-                    let data = resource.data.as_ref().context("No WriteTableItem data")?;
-                    if data.value_type != draw_value_type {
+                Change::WriteTableItem(item) => {
+                    // Only look at items in the pixels table of the canvas being drawn
+                    // on. Other SmartTables of the same type, whether they're from
+                    // other canvases or other contracts entirely, are ignored.
+                    let handle =
+                        Address::from_str(&item.handle).context("Failed to parse table handle")?;
+                    if canvas_tables.get(&handle)
+                        != Some(&(canvas_address, CanvasTableName::Pixels))
+                    {
                         continue;
                     }
-                    let values: Vec<Value> = serde_json::from_str(&data.value).unwrap();
-                    // There could be many values because a SmartTable internally is a
+                    let data = item.data.as_ref().context("No WriteTableItem data")?;
+                    // There could be many entries because a SmartTable internally is a
                     // Table where the values are vectors. This means each WriteTableItem
                     // will have the full new vector being written.
-                    for value in values {
-                        let value: Entry =
-                            serde_json::from_value(value).context("Failed to parse as Entry")?;
-                        let index = value.key.as_u64().unwrap() as u32;
-                        let hardcoded_color_raw: u8 = serde_json::from_value(value.value).unwrap();
+                    let entries = decode_smart_table_bucket::<u32, u8>(&data.value)
+                        .context("Failed to parse pixels bucket")?;
+                    for entry in entries {
+                        if let Some((width, height)) = dimensions {
+                            let num_pixels = *width as u32 * *height as u32;
+                            if entry.key >= num_pixels {
+                                return Err(CanvasProcessorError::OutOfBoundsIndex {
+                                    canvas_address,
+                                    index: entry.key,
+                                    num_pixels,
+                                }
+                                .into());
+                            }
+                        }
+                        let color = HardcodedColor::try_from(entry.value).map_err(|_| {
+                            CanvasProcessorError::UnknownColor {
+                                canvas_address,
+                                color: entry.value,
+                            }
+                        })?;
+                        // Writing the unchanged pixels again is harmless and keeps the
+                        // image in line with what is on chain.
                         write_pixel_intents.push(WritePixelIntent {
                             canvas_address,
-                            index,
-                            color: HardcodedColor::from(hardcoded_color_raw),
-                        });
-                        update_attribution_intents.push(UpdateAttributionIntent {
-                            canvas_address,
-                            artist_address: sender,
-                            index,
-                            // This information is gone from the contract so we just
-                            // use a hardcoded value for now.
-                            drawn_at_secs: 42,
+                            index: entry.key,
+                            color,
+                            version: transaction.version,
                         });
+                        written_pixels.insert(entry.key, entry.value);
                     }
                 },
                 _ => continue,
             }
         }
 
+        if self.config.disable_metadata_processing {
+            return Ok((write_pixel_intents, vec![]));
+        }
+
+        // Only attribute the pixels the sender drew, after checking that they made it
+        // into the write set with the color they asked for.
+        let canvas_width = match dimensions {
+            Some((width, _)) => *width as u32,
+            None => {
+                warn!(
+                    "Not attributing draw in txn {} since we don't know the width of canvas {}",
+                    transaction.version, canvas_address
+                );
+                DRAW_MISMATCH_COUNT
+                    .with_label_values(&["unknown_canvas_width"])
+                    .inc_by(requested_pixels.len() as u64);
+                return Ok((write_pixel_intents, vec![]));
+            },
+        };
+        let mut update_attribution_intents = vec![];
+        for (index, requested_color) in get_requested_indices(&requested_pixels, canvas_width) {
+            let mismatch_reason = match (written_pixels.get(&index), requested_color) {
+                (None, _) => Some("missing_from_write_set"),
+                (Some(written_color), Some(requested_color))
+                    if *written_color != requested_color =>
+                {
+                    Some("color_mismatch")
+                },
+                _ => None,
+            };
+            if let Some(mismatch_reason) = mismatch_reason {
+                warn!(
+                    "Draw in txn {} for canvas {} index {} doesn't match the write set: {}",
+                    transaction.version, canvas_address, index, mismatch_reason
+                );
+                DRAW_MISMATCH_COUNT
+                    .with_label_values(&[mismatch_reason])
+                    .inc();
+                continue;
+            }
+            update_attribution_intents.push(UpdateAttributionIntent {
+                canvas_address,
+                artist_address: sender,
+                index,
+                contract_address: self.contract_address,
+                // This information is gone from the contract so we just
+                // use a hardcoded value for now.
+                drawn_at_secs: 42,
+            });
+        }
+
         Ok((write_pixel_intents, update_attribution_intents))
     }
 
-    fn process_create(&self, transaction: &Transaction) -> Result<Option<CreateCanvasIntent>> {
-        // TODO: This check doesn't handle account addresses with leading zeroes.
+    fn process_create(
+        &self,
+        transaction: &Transaction,
+        layout: CanvasLayoutVersion,
+    ) -> Result<Option<CreateCanvasIntent>> {
         // Skip this transaction if this wasn't a create transaction.
         let create_function_id = EntryFunctionId {
             module: Some(MoveModuleId {
-                address: self.config.canvas_contract_address.clone(),
+                address: self.contract_address.to_string(),
                 name: CANVAS_TOKEN_MODULE_NAME.to_string(),
             }),
             name: "create".to_string(),
@@ -283,64 +927,443 @@ impl CanvasProcessor {
             return Ok(None);
         }
 
+        let canvases = self.get_canvas_resources(transaction, layout)?;
+        let (canvas_address, canvas) = match canvases.into_iter().next() {
+            Some(canvas) => canvas,
+            None => return Ok(None),
+        };
+        let default_color =
+            HardcodedColor::try_from(canvas.config.default_color).map_err(|_| {
+                CanvasProcessorError::UnknownColor {
+                    canvas_address,
+                    color: canvas.config.default_color,
+                }
+            })?;
+        Ok(Some(CreateCanvasIntent {
+            canvas_address,
+            width: canvas.config.width,
+            height: canvas.config.height,
+            default_color,
+            version: transaction.version,
+        }))
+    }
+
+    /// Any txn that writes the Canvas resource (create, but also the admin functions
+    /// that change the config, e.g. update_per_account_timeout) results in us
+    /// updating the canvas in the metadata storage with the full contents of the
+    /// Canvas resource. We also learn about the tables in the canvas this way, and
+    /// if the pixels table was replaced (see `clear`) we reset the canvas.
+    #[allow(clippy::type_complexity)]
+    fn process_canvas_writes(
+        &self,
+        transaction: &Transaction,
+        layout: CanvasLayoutVersion,
+        canvas_tables: &mut CanvasTables,
+    ) -> Result<(
+        Vec<UpdateCanvasIntent>,
+        Vec<CanvasTableHandle>,
+        Vec<UpdateCanvasAccountIntent>,
+        Vec<CreateCanvasIntent>,
+    )> {
+        let canvases = self.get_canvas_resources(transaction, layout)?;
+        if canvases.is_empty() {
+            return Ok((vec![], vec![], vec![], vec![]));
+        }
+
+        // Canvas is in the ObjectGroup, so if the owner of the canvas changed the
+        // ObjectCore will be written alongside it.
+        let info = transaction.info.as_ref().context("No info")?;
+        let object_core_struct_tag = get_object_core_struct_tag();
+        let mut object_owners = HashMap::new();
+        for change in &info.changes {
+            if let Change::WriteResource(resource) = change.change.as_ref().context("No change")? {
+                if !struct_tags_match(
+                    resource.r#type.as_ref().context("No type")?,
+                    &object_core_struct_tag,
+                ) {
+                    continue;
+                }
+                let object_core: ObjectCore =
+                    serde_json::from_str(&resource.data).context("Failed to parse ObjectCore")?;
+                let address = Address::from_str(&resource.address)
+                    .context("Failed to parse object address")?;
+                object_owners.insert(address, object_core.owner);
+            }
+        }
+
+        let mut update_canvas_intents = vec![];
+        let mut canvas_table_handles = vec![];
+        let mut update_canvas_account_intents = vec![];
+        let mut reset_canvas_intents = vec![];
+        for (canvas_address, canvas) in canvases {
+            for (table_name, handle) in [
+                (
+                    CanvasTableName::LastContributionS,
+                    canvas.last_contribution_s.handle,
+                ),
+                (
+                    CanvasTableName::UnlimitedArtists,
+                    canvas.unlimited_artists.handle,
+                ),
+                (CanvasTableName::Admins, canvas.admins.handle),
+                (CanvasTableName::Pixels, canvas.pixels.buckets.inner.handle),
+            ] {
+                let update = canvas_tables.update(canvas_address, table_name, handle);
+                // clear_contribution_timeouts replaces the table with a new empty one.
+                if update == TableHandleUpdate::Replaced
+                    && table_name == CanvasTableName::LastContributionS
+                {
+                    update_canvas_account_intents.push(UpdateCanvasAccountIntent {
+                        canvas_address,
+                        change: CanvasAccountChange::ClearLastContributions,
+                        last_updated_version: transaction.version,
+                    });
+                }
+                // clear replaces the pixels table with a new empty one, so every pixel
+                // is back to the default color. Creating the canvas again resets it.
+                if update == TableHandleUpdate::Replaced && table_name == CanvasTableName::Pixels {
+                    let default_color = HardcodedColor::try_from(canvas.config.default_color)
+                        .map_err(|_| CanvasProcessorError::UnknownColor {
+                            canvas_address,
+                            color: canvas.config.default_color,
+                        })?;
+                    reset_canvas_intents.push(CreateCanvasIntent {
+                        canvas_address,
+                        width: canvas.config.width,
+                        height: canvas.config.height,
+                        default_color,
+                        version: transaction.version,
+                    });
+                    update_canvas_account_intents.push(UpdateCanvasAccountIntent {
+                        canvas_address,
+                        change: CanvasAccountChange::ClearAttribution,
+                        last_updated_version: transaction.version,
+                    });
+                }
+                // Every draw writes the Canvas resource, so most of the time we
+                // already know the handles and there's nothing to write.
+                if update != TableHandleUpdate::Unchanged {
+                    canvas_table_handles.push(CanvasTableHandle {
+                        handle,
+                        canvas_address,
+                        table_name,
+                        created_version: transaction.version,
+                        bootstrapped: false,
+                        // process_transaction sets this if this is the create txn.
+                        created_with_canvas: Some(false),
+                    });
+                }
+            }
+            update_canvas_intents.push(UpdateCanvasIntent {
+                canvas_address,
+                width: canvas.config.width,
+                height: canvas.config.height,
+                per_account_timeout_s: canvas.config.per_account_timeout_s.0,
+                default_color: canvas.config.default_color,
+                max_number_of_pixels_per_draw: canvas.config.max_number_of_pixels_per_draw.0,
+                draw_enabled_for_non_admin: canvas.config.draw_enabled_for_non_admin,
+                created_at_s: canvas.created_at_s.0,
+                last_updated_version: transaction.version,
+                owner_address: object_owners.get(&canvas_address).copied(),
+                contract_address: self.contract_address,
+            });
+        }
+
+        Ok((
+            update_canvas_intents,
+            canvas_table_handles,
+            update_canvas_account_intents,
+            reset_canvas_intents,
+        ))
+    }
+
+    /// Draws write to the `last_contribution_s` table (if the canvas has a timeout)
+    /// and the admin functions write to the `admins` and `unlimited_artists` tables.
+    /// We use these to keep track of whether an account can draw.
+    fn process_canvas_accounts(
+        &self,
+        transaction: &Transaction,
+        canvas_tables: &CanvasTables,
+    ) -> Result<Vec<UpdateCanvasAccountIntent>> {
+        let info = transaction.info.as_ref().context("No info")?;
+
+        let mut update_canvas_account_intents = vec![];
+        for change in &info.changes {
+            let change = match change.change.as_ref().context("No change")? {
+                Change::WriteTableItem(item) => {
+                    let handle =
+                        Address::from_str(&item.handle).context("Failed to parse table handle")?;
+                    let (canvas_address, table_name) = match canvas_tables.get(&handle) {
+                        // Pixels are handled by process_draw.
+                        Some((_, CanvasTableName::Pixels)) | None => continue,
+                        Some(canvas_table) => *canvas_table,
+                    };
+                    let data = item.data.as_ref().context("No WriteTableItem data")?;
+                    let account_address: Address = serde_json::from_str(&data.key)
+                        .context("Failed to parse table key as address")?;
+                    let change = match table_name {
+                        CanvasTableName::LastContributionS => {
+                            let last_contribution_s: U64 = serde_json::from_str(&data.value)
+                                .context("Failed to parse last contribution time")?;
+                            CanvasAccountChange::SetLastContribution {
+                                account_address,
+                                last_contribution_s: last_contribution_s.0,
+                            }
+                        },
+                        CanvasTableName::Admins => CanvasAccountChange::SetAdmin {
+                            account_address,
+                            is_admin: serde_json::from_str(&data.value)
+                                .context("Failed to parse admin value")?,
+                        },
+                        CanvasTableName::UnlimitedArtists => {
+                            CanvasAccountChange::SetUnlimitedArtist {
+                                account_address,
+                                is_unlimited_artist: serde_json::from_str(&data.value)
+                                    .context("Failed to parse unlimited artist value")?,
+                            }
+                        },
+                        CanvasTableName::Pixels => unreachable!(),
+                    };
+                    (canvas_address, change)
+                },
+                Change::DeleteTableItem(item) => {
+                    let handle =
+                        Address::from_str(&item.handle).context("Failed to parse table handle")?;
+                    let (canvas_address, table_name) = match canvas_tables.get(&handle) {
+                        // Pixels are handled by process_draw.
+                        Some((_, CanvasTableName::Pixels)) | None => continue,
+                        Some(canvas_table) => *canvas_table,
+                    };
+                    let data = item.data.as_ref().context("No DeleteTableItem data")?;
+                    let account_address: Address = serde_json::from_str(&data.key)
+                        .context("Failed to parse table key as address")?;
+                    let change = match table_name {
+                        // The contract never deletes from this table, it replaces it.
+                        CanvasTableName::LastContributionS => continue,
+                        CanvasTableName::Admins => CanvasAccountChange::SetAdmin {
+                            account_address,
+                            is_admin: false,
+                        },
+                        CanvasTableName::UnlimitedArtists => {
+                            CanvasAccountChange::SetUnlimitedArtist {
+                                account_address,
+                                is_unlimited_artist: false,
+                            }
+                        },
+                        CanvasTableName::Pixels => unreachable!(),
+                    };
+                    (canvas_address, change)
+                },
+                _ => continue,
+            };
+            let (canvas_address, change) = change;
+            update_canvas_account_intents.push(UpdateCanvasAccountIntent {
+                canvas_address,
+                change,
+                last_updated_version: transaction.version,
+            });
+        }
+
+        Ok(update_canvas_account_intents)
+    }
+
+    /// When the package is upgraded the canvas_token module is published again, which
+    /// might change the layout of Canvas. We work out the new layout from the ABI and
+    /// fail if it's not one we know how to decode.
+    fn process_module_publish(
+        &self,
+        transaction: &Transaction,
+        layout: &mut CanvasLayoutVersion,
+    ) -> Result<Option<UpdateContractLayoutIntent>> {
         let info = transaction.info.as_ref().context("No info")?;
 
+        let mut update_contract_layout_intent = None;
+        for change in &info.changes {
+            let module = match change.change.as_ref().context("No change")? {
+                Change::WriteModule(module) => module,
+                _ => continue,
+            };
+            if !addresses_match(&module.address, &self.contract_address.to_string()) {
+                continue;
+            }
+            let abi = match module.data.as_ref().and_then(|data| data.abi.as_ref()) {
+                Some(abi) => abi,
+                None => continue,
+            };
+            if abi.name != CANVAS_TOKEN_MODULE_NAME {
+                continue;
+            }
+            let new_layout = CanvasLayoutVersion::from_abi(abi).context(format!(
+                "Failed to determine canvas layout of contract {}",
+                self.contract_address
+            ))?;
+            if new_layout != *layout {
+                info!(
+                    "Canvas layout of contract {} changed from {:?} to {:?} (txn {})",
+                    self.contract_address, layout, new_layout, transaction.version
+                );
+            }
+            *layout = new_layout;
+            // We record the layout on every publish, not just when it changes, so we
+            // know which one to use if we start from a checkpoint later on.
+            update_contract_layout_intent = Some(UpdateContractLayoutIntent {
+                contract_address: self.contract_address,
+                layout_version: new_layout.as_u16(),
+                last_updated_version: transaction.version,
+            });
+        }
+
+        Ok(update_contract_layout_intent)
+    }
+
+    /// Get all the Canvas resources written by this txn, along with the address of
+    /// the object they were written to.
+    fn get_canvas_resources(
+        &self,
+        transaction: &Transaction,
+        layout: CanvasLayoutVersion,
+    ) -> Result<Vec<(Address, Canvas)>> {
+        let info = transaction.info.as_ref().context("No info")?;
+
+        let mut canvases = vec![];
         for change in &info.changes {
             match change.change.as_ref().context("No change")? {
                 Change::WriteResource(resource) => {
-                    if resource.r#type.as_ref().unwrap() != &self.get_canvas_struct_tag() {
+                    let struct_tag = resource.r#type.as_ref().context("No type")?;
+                    if !struct_tags_match(struct_tag, &self.get_canvas_struct_tag()) {
                         continue;
                     }
-                    let canvas: Canvas =
-                        serde_json::from_str(&resource.data).context("Failed to parse Canvas")?;
-                    return Ok(Some(CreateCanvasIntent {
-                        canvas_address: Address::from_str(&resource.address).unwrap(),
-                        width: canvas.config.width,
-                        height: canvas.config.height,
-                        default_color: HardcodedColor::from(canvas.config.default_color),
-                    }));
+                    let canvas = layout.decode_canvas(&resource.data)?;
+                    let canvas_address = Address::from_str(&resource.address)
+                        .context("Failed to parse canvas address")?;
+                    canvases.push((canvas_address, canvas));
                 },
                 _ => continue,
             }
         }
-        Ok(None)
+        Ok(canvases)
     }
-}
 
-fn entry_function_id_matches(
-    transaction: &Transaction,
-    entry_function_id: &EntryFunctionId,
-) -> bool {
-    let txn_data = transaction
-        .txn_data
-        .as_ref()
-        .context("No txn_data")
-        .unwrap();
-    let user_transaction = match txn_data {
-        TxnData::User(user_transaction) => user_transaction,
-        _ => return false,
-    };
-    let request = user_transaction
-        .request
-        .as_ref()
-        .context("No request")
-        .unwrap();
-    let payload = request.payload.as_ref().unwrap();
-    let entry_function_payload = match payload.payload.as_ref().context("No payload").unwrap() {
-        Payload::EntryFunctionPayload(payload) => payload,
-        _ => return false,
-    };
+    /// The collection is created when the package is published (in init_module of
+    /// canvas_collection) and then changed by the entry functions in that module.
+    /// Both of these write resources at the collection address, which we use to
+    /// build the full picture of the collection: CollectionConfig (from our module),
+    /// Collection (from 0x4::collection), and ObjectCore (for the owner).
+    fn process_collection(&self, transaction: &Transaction) -> Result<Vec<UpdateCollectionIntent>> {
+        let called_collection_entry_function =
+            CANVAS_COLLECTION_ENTRY_FUNCTION_NAMES.iter().any(|name| {
+                let function_id = EntryFunctionId {
+                    module: Some(MoveModuleId {
+                        address: self.contract_address.to_string(),
+                        name: CANVAS_COLLECTION_MODULE_NAME.to_string(),
+                    }),
+                    name: name.to_string(),
+                };
+                entry_function_id_matches(transaction, &function_id)
+            });
+
+        let info = transaction.info.as_ref().context("No info")?;
+
+        let collection_config_struct_tag = self.get_collection_config_struct_tag();
+        let collection_refs_struct_tag = self.get_collection_refs_struct_tag();
+        let framework_collection_struct_tag = get_framework_collection_struct_tag();
+        let object_core_struct_tag = get_object_core_struct_tag();
+
+        // First figure out where the collection lives. Our resources are only ever
+        // written at the collection address. If the txn called one of the collection
+        // entry functions, any Collection it wrote must also be ours.
+        let mut collection_addresses = HashSet::new();
+        for change in &info.changes {
+            if let Change::WriteResource(resource) = change.change.as_ref().context("No change")? {
+                let struct_tag = resource.r#type.as_ref().context("No type")?;
+                if struct_tags_match(struct_tag, &collection_config_struct_tag)
+                    || struct_tags_match(struct_tag, &collection_refs_struct_tag)
+                    || (called_collection_entry_function
+                        && struct_tags_match(struct_tag, &framework_collection_struct_tag))
+                {
+                    collection_addresses.insert(resource.address.clone());
+                }
+            }
+        }
+
+        // Now pull out the information from each of the resources at those addresses.
+        let mut intents = HashMap::new();
+        for change in &info.changes {
+            let resource = match change.change.as_ref().context("No change")? {
+                Change::WriteResource(resource) => resource,
+                _ => continue,
+            };
+            if !collection_addresses.contains(&resource.address) {
+                continue;
+            }
+            let collection_address = Address::from_str(&resource.address)
+                .context("Failed to parse collection address")?;
+            let intent =
+                intents
+                    .entry(collection_address)
+                    .or_insert_with(|| UpdateCollectionIntent {
+                        collection_address,
+                        name: None,
+                        description: None,
+                        uri: None,
+                        creator_address: None,
+                        owner_address: None,
+                        max_width: None,
+                        max_height: None,
+                        last_updated_version: transaction.version,
+                    });
+            let struct_tag = resource.r#type.as_ref().context("No type")?;
+            if struct_tags_match(struct_tag, &collection_config_struct_tag) {
+                let config: CollectionConfig = serde_json::from_str(&resource.data)
+                    .context("Failed to parse CollectionConfig")?;
+                intent.max_width = Some(config.max_width);
+                intent.max_height = Some(config.max_height);
+            } else if struct_tags_match(struct_tag, &framework_collection_struct_tag) {
+                let collection: Collection =
+                    serde_json::from_str(&resource.data).context("Failed to parse Collection")?;
+                intent.name = Some(collection.name);
+                intent.description = Some(collection.description);
+                intent.uri = Some(collection.uri);
+                intent.creator_address = Some(collection.creator);
+            } else if struct_tags_match(struct_tag, &object_core_struct_tag) {
+                let object_core: ObjectCore =
+                    serde_json::from_str(&resource.data).context("Failed to parse ObjectCore")?;
+                intent.owner_address = Some(object_core.owner);
+            }
+        }
 
-    let function_id = entry_function_payload
-        .function
-        .as_ref()
-        .context("No function")
-        .unwrap();
+        Ok(intents.into_values().collect())
+    }
+}
 
-    function_id == entry_function_id
+/// Work out whether an error from processing a txn is one of the kinds we have a
+/// policy for, and if so, which canvas it was for. Decode failures we haven't
+/// explicitly turned into a `CanvasProcessorError` are recognized by their source.
+fn classify_error(error: &anyhow::Error) -> Option<(CanvasProcessorErrorKind, Option<Address>)> {
+    if let Some(error) = error.downcast_ref::<CanvasProcessorError>() {
+        return Some((error.kind(), error.canvas_address()));
+    }
+    if error.chain().any(|cause| cause.is::<serde_json::Error>()) {
+        return Some((CanvasProcessorErrorKind::Decode, None));
+    }
+    None
 }
 
-// Functions we need:
-// - Make it easier to pull out the entry function payload, one function.
-// - Something like get_clean_* for each of the Change:: variants, like WriteTableData.
-// - This entry_function_id_matches function above.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONTRACT: &str = "0x5047733bb1360184c72e42fabb906a614cc547d751fc5764d2f74c9fd723035a";
+
+    #[test]
+    fn test_legacy_contract_address_config() {
+        let expected = vec![Address::from_str(CONTRACT).unwrap()];
+        let config: CanvasProcessorConfig =
+            serde_json::from_value(json!({ "canvas_contract_addresses": [CONTRACT] })).unwrap();
+        assert_eq!(config.canvas_contract_addresses, expected);
+        let config: CanvasProcessorConfig =
+            serde_json::from_value(json!({ "canvas_contract_address": CONTRACT })).unwrap();
+        assert_eq!(config.canvas_contract_addresses, expected);
+    }
+}
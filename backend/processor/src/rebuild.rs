@@ -0,0 +1,259 @@
+//! If a canvas ends up in a bad state, e.g. the file gets corrupted, we can rebuild
+//! it by deleting it and reprocessing its txns, without having to reset everything.
+//! Other canvases and the checkpoint of the main processor are left untouched.
+
+use crate::{
+    chain_id::check_stream_chain_id, migrate_legacy_checkpoint, CanvasProcessor,
+    FileStreamSubscriber, RunConfig,
+};
+use anyhow::{bail, Context, Result};
+use aptos_move_graphql_scalars::Address;
+use aptos_processor_framework::{
+    GrpcStreamSubscriber, ProcessorTrait, StorageTrait, StreamSubscriberTrait,
+};
+use metadata_storage::{CanvasTableHandle, MetadataStorageTrait, PostgresMetadataStorage};
+use pixel_storage::{MmapPixelStorage, PixelStorageTrait};
+use std::{collections::HashSet, sync::Arc};
+use tracing::{info, warn};
+
+/// Delete the given canvases and rebuild them by reprocessing txns from
+/// `from_version` up to the last version the main processor processed. The main
+/// processor must be stopped while this runs, otherwise it might write to the
+/// canvases while we're rebuilding them. The MmapPixelStorage locks the storage
+/// directory, so building it fails if the processor is still running.
+pub async fn rebuild_canvases(
+    config: RunConfig,
+    canvas_addresses: Vec<Address>,
+    from_version: u64,
+    contract_address: Option<Address>,
+    metadata_storage: Arc<PostgresMetadataStorage>,
+    pixels_storage: Arc<MmapPixelStorage>,
+) -> Result<()> {
+    if canvas_addresses.is_empty() {
+        bail!("No canvases given to rebuild");
+    }
+
+    // Work out which deployment of the contract the canvases belong to.
+    let contract_addresses = &config.processor_config.canvas_contract_addresses;
+    let contract_address = match contract_address {
+        Some(contract_address) => {
+            if !contract_addresses.contains(&contract_address) {
+                bail!(
+                    "Contract {} is not in canvas_contract_addresses",
+                    contract_address
+                );
+            }
+            contract_address
+        },
+        None => match contract_addresses.as_slice() {
+            [contract_address] => *contract_address,
+            _ => bail!("There are multiple contracts in the config, choose one"),
+        },
+    };
+
+    let processor = CanvasProcessor::new(
+        config.processor_config.clone(),
+        contract_address,
+        pixels_storage.clone(),
+        metadata_storage.clone(),
+    )?;
+
+    // We rebuild up to where the main processor is, it takes it from there.
+    migrate_legacy_checkpoint(&config.processor_config, metadata_storage.as_ref()).await?;
+    let end_version = metadata_storage
+        .read_last_processed_version(processor.name())
+        .await
+        .context("Failed to read last processed version")?
+        .context("The processor hasn't processed anything yet, there is nothing to rebuild")?;
+    if from_version > end_version {
+        bail!(
+            "from_version {} is after the last version the processor processed ({})",
+            from_version,
+            end_version
+        );
+    }
+
+    let table_handles = metadata_storage
+        .read_canvas_table_handles()
+        .await
+        .context("Failed to read canvas table handles")?;
+    check_canvases_can_be_rebuilt(&table_handles, &canvas_addresses, from_version)?;
+
+    // Reprocess the txns, only writing changes to the canvases we're rebuilding. We
+    // call the processor directly rather than through the dispatcher so we don't
+    // write a checkpoint.
+    let processor = processor.for_rebuild(canvas_addresses.iter().copied().collect::<HashSet<_>>());
+    let channel_handle = match &config.transaction_file_config {
+        Some(transaction_file_config) => {
+            FileStreamSubscriber {
+                config: transaction_file_config.clone(),
+                processor_name: processor.name().to_string(),
+                starting_version: from_version,
+                shutdown: None,
+            }
+            .start()
+            .await?
+        },
+        None => {
+            GrpcStreamSubscriber {
+                config: config.stream_subscriber_config.clone(),
+                processor_name: processor.name().to_string(),
+                starting_version: from_version,
+            }
+            .start()
+            .await?
+        },
+    };
+
+    let mut receiver = channel_handle.receiver;
+    let mut first_response = Some(
+        check_stream_chain_id(
+            &mut receiver,
+            metadata_storage.as_ref(),
+            pixels_storage.as_ref(),
+        )
+        .await?,
+    );
+
+    // Delete the canvases, now we know the stream is for the right chain.
+    for canvas_address in &canvas_addresses {
+        info!("Deleting canvas {} to rebuild it", canvas_address);
+        pixels_storage
+            .delete_canvas(canvas_address)
+            .await
+            .context(format!("Failed to delete canvas {}", canvas_address))?;
+        metadata_storage
+            .delete_canvas_metadata(canvas_address)
+            .await
+            .context(format!(
+                "Failed to delete metadata for canvas {}",
+                canvas_address
+            ))?;
+    }
+
+    let mut next_version = from_version;
+    while next_version <= end_version {
+        let response = match first_response.take() {
+            Some(response) => response,
+            None => receiver.recv().await.context(format!(
+                "Txn stream ended at version {} before we reached {}",
+                next_version, end_version
+            ))?,
+        };
+        let transactions: Vec<_> = response
+            .transactions
+            .into_iter()
+            .filter(|transaction| {
+                transaction.version >= next_version && transaction.version <= end_version
+            })
+            .collect();
+        let (first, last) = match (transactions.first(), transactions.last()) {
+            (Some(first), Some(last)) => (first.version, last.version),
+            _ => continue,
+        };
+        processor
+            .process_transactions(transactions, first, last)
+            .await
+            .context(format!("Failed to process txns {} to {}", first, last))?;
+        info!(
+            "Rebuilt canvases up to version {} (of {})",
+            last, end_version
+        );
+        next_version = last + 1;
+    }
+    channel_handle.join_handle.abort();
+
+    // If we never reprocessed the txn that created a canvas, nothing recreated the
+    // file we deleted, so the rebuild didn't work.
+    if !config.processor_config.disable_pixel_processing {
+        for canvas_address in &canvas_addresses {
+            if !pixels_storage
+                .canvas_exists(canvas_address)
+                .await
+                .context(format!("Failed to check canvas {} exists", canvas_address))?
+            {
+                bail!(
+                    "Canvas {} doesn't exist after reprocessing txns {} to {}, from_version must be at or before the txn that created it",
+                    canvas_address,
+                    from_version,
+                    end_version
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// We must reprocess the txn that created each canvas, since that's what creates
+/// the file. We learn about the tables of a canvas from any txn that writes the
+/// Canvas resource, so we only know when it was created if we saw the create txn
+/// itself, in which case its table handles are marked `created_with_canvas`. For
+/// canvases we bootstrapped from a state snapshot, or that were created before we
+/// started processing, we never saw it, so those can't be rebuilt this way.
+pub(crate) fn check_canvases_can_be_rebuilt(
+    table_handles: &[CanvasTableHandle],
+    canvas_addresses: &[Address],
+    from_version: u64,
+) -> Result<()> {
+    for canvas_address in canvas_addresses {
+        let canvas_table_handles: Vec<_> = table_handles
+            .iter()
+            .filter(|table_handle| table_handle.canvas_address == *canvas_address)
+            .collect();
+        if let Some(table_handle) = canvas_table_handles
+            .iter()
+            .find(|table_handle| table_handle.bootstrapped)
+        {
+            bail!(
+                "Canvas {} was bootstrapped from a snapshot at version {}, so we don't have the txn that created it and it can't be rebuilt. Bootstrap it again instead",
+                canvas_address,
+                table_handle.created_version
+            );
+        }
+        if canvas_table_handles.is_empty() {
+            bail!(
+                "Canvas {} was never processed, it can't be rebuilt",
+                canvas_address
+            );
+        }
+        let created_version = canvas_table_handles
+            .iter()
+            .filter(|table_handle| table_handle.created_with_canvas == Some(true))
+            .map(|table_handle| table_handle.created_version)
+            .min();
+        let created_version = match created_version {
+            Some(created_version) => created_version,
+            // The handles were recorded before we kept track of which came from the
+            // create txn, so the best we can do is the first one. If that was a draw
+            // rather than the create txn, we notice once we've reprocessed the txns.
+            None if canvas_table_handles
+                .iter()
+                .any(|table_handle| table_handle.created_with_canvas.is_none()) =>
+            {
+                let created_version = canvas_table_handles
+                    .iter()
+                    .map(|table_handle| table_handle.created_version)
+                    .min()
+                    .unwrap_or_default();
+                warn!(
+                    "Don't know when canvas {} was created, assuming version {}",
+                    canvas_address, created_version
+                );
+                created_version
+            },
+            None => bail!(
+                "We never saw the txn that created canvas {}, e.g. because it was before we started processing, so it can't be rebuilt",
+                canvas_address
+            ),
+        };
+        if from_version > created_version {
+            bail!(
+                "Canvas {} was created at version {}, from_version must be at or before that",
+                canvas_address,
+                created_version
+            );
+        }
+    }
+    Ok(())
+}
@@ -2,17 +2,29 @@
 //! the processor. The dev could glue all these pieces together themselves, this file
 //! doesn't use anything private, so this is all just for dev convenience / dedupe.
 
-use crate::{CanvasProcessor, CanvasProcessorConfig};
-use anyhow::{Context, Result};
+use crate::{
+    chain_id::check_stream_chain_id, migrate_legacy_checkpoint, CanvasProcessor,
+    CanvasProcessorConfig, FileStreamSubscriber, FileStreamSubscriberConfig, PaintProcessor,
+    PaintProcessorConfig,
+};
+use anyhow::{bail, Context, Result};
 use aptos_processor_framework::{
-    CommonStorageConfig, Dispatcher, DispatcherConfig, GrpcStreamSubscriber,
-    GrpcStreamSubscriberConfig, ProcessorTrait, StorageTrait, StreamSubscriberTrait,
+    indexer_protos::indexer::v1::TransactionsResponse, CommonStorageConfig, Dispatcher,
+    DispatcherConfig, GrpcStreamSubscriber, GrpcStreamSubscriberConfig, ProcessorTrait,
+    StorageTrait, StreamSubscriberTrait,
 };
 use metadata_storage::PostgresMetadataStorage;
 use pixel_storage::MmapPixelStorage;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::task::JoinHandle;
+use tokio::{
+    sync::{
+        mpsc::{self, Receiver},
+        watch,
+    },
+    task::JoinHandle,
+};
+use tracing::{error, info};
 
 /// This contains all the configs necessary to build the components required to run the
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -22,26 +34,90 @@ pub struct RunConfig {
     pub dispatcher_config: DispatcherConfig,
     pub common_storage_config: CommonStorageConfig,
     pub processor_config: CanvasProcessorConfig,
+    /// If set, we also run the PaintProcessor alongside the CanvasProcessor.
+    #[serde(default)]
+    pub paint_processor_config: Option<PaintProcessorConfig>,
+    /// If set, we read txns from this file instead of the txn stream service, e.g. to
+    /// replay txns recorded with `service record`. `stream_subscriber_config` is
+    /// ignored in this case.
+    #[serde(default)]
+    pub transaction_file_config: Option<FileStreamSubscriberConfig>,
 }
 
 /// Build all the relevant pieces required to run the processor, and the processor
-/// itself, and spawn tokio tasks for them. This returns handles to those tasks.
+/// itself, and spawn tokio tasks for them. This returns handles to those tasks. Once
+/// `shutdown` is set the processors stop taking txns from the stream, finish the
+/// txns they already have, and the tasks end.
 pub async fn run(
     config: RunConfig,
     metadata_storage: Arc<PostgresMetadataStorage>,
     pixels_storage: Arc<MmapPixelStorage>,
+    shutdown: watch::Receiver<bool>,
 ) -> Result<Vec<JoinHandle<()>>> {
-    // Build the canvas processor, which is what processes transactions and updates the
-    // canvas storage and the DB.
-    let processor = Arc::new(
-        CanvasProcessor::new(
-            config.processor_config.clone(),
-            pixels_storage.clone(),
+    if config.processor_config.canvas_contract_addresses.is_empty() {
+        bail!("canvas_contract_addresses must contain at least one address");
+    }
+    migrate_legacy_checkpoint(&config.processor_config, metadata_storage.as_ref()).await?;
+
+    // Build a canvas processor for each contract deployment, which is what processes
+    // transactions and updates the canvas storage and the DB.
+    let mut task_handles = Vec::new();
+    for contract_address in &config.processor_config.canvas_contract_addresses {
+        let processor = Arc::new(
+            CanvasProcessor::new(
+                config.processor_config.clone(),
+                *contract_address,
+                pixels_storage.clone(),
+                metadata_storage.clone(),
+            )
+            .context(format!(
+                "Failed to build processor for contract {}",
+                contract_address
+            ))?,
+        );
+        task_handles.extend(
+            run_processor(
+                &config,
+                processor,
+                metadata_storage.clone(),
+                pixels_storage.clone(),
+                shutdown.clone(),
+            )
+            .await?,
+        );
+    }
+
+    // Build and run the paint processor if configured. This only writes to the DB.
+    if let Some(paint_processor_config) = &config.paint_processor_config {
+        let paint_processor = Arc::new(PaintProcessor::new(
+            paint_processor_config.clone(),
             metadata_storage.clone(),
-        )
-        .context("Failed to build processor")?,
-    );
+        ));
+        task_handles.extend(
+            run_processor(
+                &config,
+                paint_processor,
+                metadata_storage.clone(),
+                pixels_storage.clone(),
+                shutdown.clone(),
+            )
+            .await?,
+        );
+    }
 
+    Ok(task_handles)
+}
+
+/// Build the stream subscriber and dispatcher for the given processor and spawn tokio
+/// tasks for them. Each processor has its own stream and keeps track of the last
+/// version it processed separately, keyed by its name.
+async fn run_processor<P: ProcessorTrait + Send + Sync + 'static>(
+    config: &RunConfig,
+    processor: Arc<P>,
+    metadata_storage: Arc<PostgresMetadataStorage>,
+    pixels_storage: Arc<MmapPixelStorage>,
+    shutdown: watch::Receiver<bool>,
+) -> Result<Vec<JoinHandle<()>>> {
     // From the DB, read the last version we processed.
     let starting_version_from_db = metadata_storage
         .read_last_processed_version(processor.name())
@@ -53,39 +129,118 @@ pub async fn run(
         .common_storage_config
         .determine_starting_version(starting_version_from_db);
 
-    // Build the stream subscriber, which subscribes to txn stream service and pushes
-    // the txns to an internal channel.
-    let stream_subscriber = GrpcStreamSubscriber {
-        config: config.stream_subscriber_config.clone(),
-        processor_name: processor.name().to_string(),
-        starting_version,
+    // Build the stream subscriber, which subscribes to txn stream service (or reads
+    // from a file) and pushes the txns to an internal channel, and start it.
+    let channel_handle = match &config.transaction_file_config {
+        Some(transaction_file_config) => {
+            FileStreamSubscriber {
+                config: transaction_file_config.clone(),
+                processor_name: processor.name().to_string(),
+                starting_version,
+                shutdown: Some(shutdown.clone()),
+            }
+            .start()
+            .await?
+        },
+        None => {
+            GrpcStreamSubscriber {
+                config: config.stream_subscriber_config.clone(),
+                processor_name: processor.name().to_string(),
+                starting_version,
+            }
+            .start()
+            .await?
+        },
     };
 
-    // Start the stream subscriber.
-    let channel_handle = stream_subscriber.start().await?;
-
-    // Forcibly set the number of concurrent workers to 1. This processor depends on
-    // txns being processed in order because of how we only create files when we
-    // process the create canvas txns.
+    // Forcibly set the number of concurrent workers to 1. The canvas processor
+    // depends on txns being processed in order because of how we only create files
+    // when we process the create canvas txns. The paint processor writes balances
+    // as they are in the txn, so it also needs txns to be processed in order. So
+    // decoding txns and building the intents is sequential, on one task. The only
+    // concurrency is in the canvas processor writing the intents of a batch to
+    // storage, where each canvas is written separately (see
+    // `max_concurrent_canvases`).
     let mut dispatcher_config = config.dispatcher_config.clone();
     dispatcher_config.num_concurrent_processing_tasks = 1;
 
+    // Before processing anything, check the stream is for the same chain as the data
+    // we already have. This waits for the first txns from the stream, which holds up
+    // startup, but it means we refuse to start before the API and flushers are up
+    // rather than serving and flushing for a while and then dying.
+    let processor_name = processor.name();
+    let mut receiver = channel_handle.receiver;
+    let first_response = match check_stream_chain_id(
+        &mut receiver,
+        metadata_storage.as_ref(),
+        pixels_storage.as_ref(),
+    )
+    .await
+    {
+        Ok(first_response) => first_response,
+        Err(err) => {
+            channel_handle.join_handle.abort();
+            return Err(err.context(format!("Refusing to start {}", processor_name)));
+        },
+    };
+
     // Build the dispatcher, which is what reads from the channel and dispatches txns
     // to the processor.
-    let metadata_storage_clone = metadata_storage.clone();
     let dispatcher_task = tokio::spawn(async move {
+        let receiver = forward_until_shutdown(first_response, receiver, shutdown.clone());
+
         let mut dispatcher = Dispatcher {
             config: dispatcher_config,
-            storage: metadata_storage_clone.clone(),
+            storage: metadata_storage.clone(),
             processor: processor.clone(),
-            receiver: channel_handle.receiver,
+            receiver,
             starting_version,
         };
         let result = dispatcher.dispatch().await;
-        eprintln!("Dispatcher finished unexpectedly: {:?}", result);
+        if *shutdown.borrow() {
+            info!("Dispatcher for {} finished for shutdown", processor_name);
+        } else {
+            error!(
+                "Dispatcher for {} finished unexpectedly: {:?}",
+                processor_name, result
+            );
+        }
     });
 
     let task_handles = vec![dispatcher_task, channel_handle.join_handle];
 
     Ok(task_handles)
 }
+
+/// Forward responses from the stream to the dispatcher, starting with `first_response`,
+/// until `shutdown` is set. Then we stop, dropping both ends: the dispatcher finishes
+/// the responses it already has and ends when the channel closes, and the stream
+/// subscriber ends when it next fails to send. The channel only holds one response
+/// so there isn't much left to process when we shut down.
+fn forward_until_shutdown(
+    first_response: TransactionsResponse,
+    mut receiver: Receiver<TransactionsResponse>,
+    mut shutdown: watch::Receiver<bool>,
+) -> Receiver<TransactionsResponse> {
+    let (sender, forwarded_receiver) = mpsc::channel(1);
+    tokio::spawn(async move {
+        if sender.send(first_response).await.is_err() {
+            return;
+        }
+        loop {
+            let response = tokio::select! {
+                response = receiver.recv() => response,
+                _ = shutdown.changed() => None,
+            };
+            match response {
+                Some(response) => {
+                    if sender.send(response).await.is_err() {
+                        return;
+                    }
+                },
+                None => return,
+            }
+        }
+    });
+    forwarded_receiver
+}
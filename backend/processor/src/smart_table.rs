@@ -0,0 +1,22 @@
+use anyhow::{Context, Result};
+use aptos_move_graphql_scalars::U64;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// A typed version of the Entry struct from 0x1::smart_table. The generated
+/// `move_types::Entry` uses `Any` for the key and value since it is generic.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SmartTableEntry<K, V> {
+    pub hash: U64,
+    pub key: K,
+    pub value: V,
+}
+
+/// Internally a SmartTable is a TableWithLength of buckets, where each bucket is a
+/// vector of entries. Each WriteTableItem on the buckets table contains the full new
+/// contents of a bucket, not just the entries that changed. This decodes the value
+/// of one of those WriteTableItems.
+pub fn decode_smart_table_bucket<K: DeserializeOwned, V: DeserializeOwned>(
+    value: &str,
+) -> Result<Vec<SmartTableEntry<K, V>>> {
+    serde_json::from_str(value).context("Failed to parse SmartTable bucket")
+}
@@ -0,0 +1,294 @@
+//! These let us run the processors without the txn stream service. The recorder
+//! pulls a range of txns from the txn stream service and writes them to a file, and
+//! `FileStreamSubscriber` feeds the txns in such a file to the dispatcher as if they
+//! came from the stream. This is helpful for testing, backfilling, and reproducing
+//! bugs from production, since the same file always results in the same txns.
+
+use anyhow::{bail, Context, Result};
+use aptos_processor_framework::{
+    indexer_protos::{indexer::v1::TransactionsResponse, transaction::v1::Transaction},
+    ChannelHandle, GrpcStreamSubscriber, GrpcStreamSubscriberConfig, StreamSubscriberTrait,
+};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+
+/// How the txns are stored in a txn file.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionFileFormat {
+    /// One JSON encoded `Transaction` per line. This is easy to read and edit by
+    /// hand, e.g. to cut a bug down to the txns that trigger it.
+    Json,
+    /// Length delimited protobuf encoded `Transaction`s, one after the other. This is
+    /// much more compact than JSON.
+    Binary,
+}
+
+impl TransactionFileFormat {
+    /// Guess the format from the extension of the file, `.json` / `.jsonl` for JSON
+    /// and anything else for binary.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") | Some("jsonl") => TransactionFileFormat::Json,
+            _ => TransactionFileFormat::Binary,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileStreamSubscriberConfig {
+    /// The file to read txns from.
+    pub path: PathBuf,
+
+    /// If not set, we guess from the extension of `path`.
+    #[serde(default)]
+    pub format: Option<TransactionFileFormat>,
+
+    /// The chain the txns in the file came from. The dispatcher checks this against
+    /// the chain ID in the DB like it would with the txn stream service.
+    pub chain_id: u64,
+
+    /// How many txns to send to the dispatcher at once.
+    #[serde(default = "FileStreamSubscriberConfig::default_batch_size")]
+    pub batch_size: usize,
+}
+
+impl FileStreamSubscriberConfig {
+    fn default_batch_size() -> usize {
+        1000
+    }
+
+    fn format(&self) -> TransactionFileFormat {
+        self.format
+            .unwrap_or_else(|| TransactionFileFormat::from_path(&self.path))
+    }
+}
+
+/// Reads txns from a file and sends them to the dispatcher. Like the txn stream
+/// service, txns before `starting_version` are skipped, so if the processor has
+/// already processed part of the file it'll pick up where it left off.
+#[derive(Debug)]
+pub struct FileStreamSubscriber {
+    pub config: FileStreamSubscriberConfig,
+    pub processor_name: String,
+    pub starting_version: u64,
+    /// The shutdown signal of the service, if we're running as part of it. The
+    /// dispatcher stops receiving txns when we shut down, so we only warn about it
+    /// if this isn't set.
+    pub shutdown: Option<watch::Receiver<bool>>,
+}
+
+#[async_trait::async_trait]
+impl StreamSubscriberTrait for FileStreamSubscriber {
+    async fn start(&self) -> Result<ChannelHandle> {
+        let transactions = read_transactions(&self.config.path, self.config.format()).context(
+            format!("Failed to read txns from {}", self.config.path.display()),
+        )?;
+        let transactions: Vec<Transaction> = transactions
+            .into_iter()
+            .filter(|transaction| transaction.version >= self.starting_version)
+            .collect();
+        info!(
+            processor_name = self.processor_name,
+            starting_version = self.starting_version,
+            num_transactions = transactions.len(),
+            "Replaying txns from {}",
+            self.config.path.display()
+        );
+
+        let (sender, receiver) = mpsc::channel(10);
+        let chain_id = self.config.chain_id;
+        let batch_size = self.config.batch_size.max(1);
+        let processor_name = self.processor_name.clone();
+        let shutdown = self.shutdown.clone();
+        let join_handle = tokio::spawn(async move {
+            let last_version = transactions.last().map(|transaction| transaction.version);
+            let mut transactions = transactions.into_iter().peekable();
+            while transactions.peek().is_some() {
+                let batch: Vec<Transaction> = transactions.by_ref().take(batch_size).collect();
+                let response = TransactionsResponse {
+                    transactions: batch,
+                    chain_id: Some(chain_id),
+                };
+                if sender.send(response).await.is_err() {
+                    if shutdown
+                        .as_ref()
+                        .map_or(false, |shutdown| *shutdown.borrow())
+                    {
+                        info!(
+                            processor_name = processor_name,
+                            "Stopped sending txns for shutdown"
+                        );
+                    } else {
+                        warn!(
+                            processor_name = processor_name,
+                            "Dispatcher stopped receiving txns"
+                        );
+                    }
+                    return;
+                }
+            }
+            info!(
+                processor_name = processor_name,
+                last_version = last_version,
+                "Sent all txns from the file"
+            );
+            // If we returned now the service would treat it as the stream having
+            // stopped unexpectedly, possibly before the dispatcher has processed
+            // everything we sent it, so we wait for the dispatcher instead.
+            sender.closed().await;
+        });
+
+        Ok(ChannelHandle {
+            receiver,
+            join_handle,
+        })
+    }
+}
+
+/// Read every txn in a txn file.
+pub fn read_transactions(path: &Path, format: TransactionFileFormat) -> Result<Vec<Transaction>> {
+    let file = File::open(path).context("Failed to open file")?;
+    let mut reader = BufReader::new(file);
+    let mut transactions = vec![];
+    match format {
+        TransactionFileFormat::Json => {
+            for (i, line) in reader.lines().enumerate() {
+                let line = line.context("Failed to read line")?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                transactions.push(
+                    serde_json::from_str(&line)
+                        .context(format!("Failed to parse txn on line {}", i + 1))?,
+                );
+            }
+        },
+        TransactionFileFormat::Binary => {
+            let mut data = vec![];
+            reader
+                .read_to_end(&mut data)
+                .context("Failed to read file")?;
+            let mut buf = data.as_slice();
+            while !buf.is_empty() {
+                transactions.push(Transaction::decode_length_delimited(&mut buf).context(
+                    format!("Failed to decode txn {} in file", transactions.len()),
+                )?);
+            }
+        },
+    }
+    // The txns must be in order, otherwise the dispatcher will fail on a gap.
+    if let Some(window) = transactions
+        .windows(2)
+        .find(|window| window[1].version <= window[0].version)
+    {
+        bail!(
+            "Txns in file are not in order: {} is followed by {}",
+            window[0].version,
+            window[1].version
+        );
+    }
+    Ok(transactions)
+}
+
+/// Writes txns to a txn file.
+pub struct TransactionFileWriter {
+    writer: BufWriter<File>,
+    format: TransactionFileFormat,
+}
+
+impl TransactionFileWriter {
+    pub fn create(path: &Path, format: TransactionFileFormat) -> Result<Self> {
+        let file = File::create(path).context(format!("Failed to create {}", path.display()))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            format,
+        })
+    }
+
+    pub fn write(&mut self, transaction: &Transaction) -> Result<()> {
+        match self.format {
+            TransactionFileFormat::Json => {
+                serde_json::to_writer(&mut self.writer, transaction)
+                    .context("Failed to write txn as JSON")?;
+                self.writer
+                    .write_all(b"\n")
+                    .context("Failed to write newline")?;
+            },
+            TransactionFileFormat::Binary => {
+                self.writer
+                    .write_all(&transaction.encode_length_delimited_to_vec())
+                    .context("Failed to write txn")?;
+            },
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.writer.flush().context("Failed to flush txn file")
+    }
+}
+
+/// Read the txns from `start_version` to `end_version` (inclusive) from the txn
+/// stream service and write them to a file. Returns how many txns we wrote.
+pub async fn record_transactions(
+    stream_subscriber_config: GrpcStreamSubscriberConfig,
+    start_version: u64,
+    end_version: u64,
+    path: &Path,
+    format: TransactionFileFormat,
+) -> Result<u64> {
+    if end_version < start_version {
+        bail!(
+            "end_version {} is before start_version {}",
+            end_version,
+            start_version
+        );
+    }
+
+    let stream_subscriber = GrpcStreamSubscriber {
+        config: stream_subscriber_config,
+        processor_name: "TransactionRecorder".to_string(),
+        starting_version: start_version,
+    };
+    let mut channel_handle = stream_subscriber.start().await?;
+
+    let mut writer = TransactionFileWriter::create(path, format)?;
+    let mut num_written = 0;
+    let mut done = false;
+    while !done {
+        let response = channel_handle
+            .receiver
+            .recv()
+            .await
+            .context("Txn stream ended before we reached end_version")?;
+        for transaction in response.transactions {
+            if transaction.version < start_version {
+                continue;
+            }
+            if transaction.version > end_version {
+                done = true;
+                break;
+            }
+            writer.write(&transaction)?;
+            num_written += 1;
+            if transaction.version == end_version {
+                done = true;
+                break;
+            }
+        }
+        info!("Recorded {} txns to {} so far", num_written, path.display());
+    }
+    writer.finish()?;
+    channel_handle.join_handle.abort();
+
+    Ok(num_written)
+}
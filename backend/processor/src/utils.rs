@@ -0,0 +1,210 @@
+//! Helpers for pulling information out of txns that are shared between processors.
+
+use aptos_processor_framework::indexer_protos::transaction::v1::{
+    transaction::TxnData, transaction_payload::Payload, EntryFunctionId, MoveStructTag, Transaction,
+};
+
+/// Whether the txn is a user txn calling the given entry function. Anything that
+/// doesn't look like one, including malformed txns, doesn't match.
+pub fn entry_function_id_matches(
+    transaction: &Transaction,
+    entry_function_id: &EntryFunctionId,
+) -> bool {
+    let user_transaction = match transaction.txn_data.as_ref() {
+        Some(TxnData::User(user_transaction)) => user_transaction,
+        _ => return false,
+    };
+    let payload = match user_transaction
+        .request
+        .as_ref()
+        .and_then(|request| request.payload.as_ref())
+    {
+        Some(payload) => payload,
+        None => return false,
+    };
+    let entry_function_payload = match payload.payload.as_ref() {
+        Some(Payload::EntryFunctionPayload(payload)) => payload,
+        _ => return false,
+    };
+
+    match entry_function_payload.function.as_ref() {
+        Some(function_id) => entry_function_ids_match(function_id, entry_function_id),
+        None => false,
+    }
+}
+
+/// Addresses can be written in short form (0x1), long form (0x000..001) or anything
+/// in between, with or without the 0x prefix. This returns the long form without the
+/// prefix, so we can compare addresses no matter how they were written. Returns None
+/// if this isn't an address at all.
+pub fn normalize_address(address: &str) -> Option<String> {
+    let hex = address.strip_prefix("0x").unwrap_or(address);
+    if hex.is_empty() || hex.len() > 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(format!("{:0>64}", hex.to_ascii_lowercase()))
+}
+
+pub fn addresses_match(a: &str, b: &str) -> bool {
+    match (normalize_address(a), normalize_address(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Compare struct tags, treating different forms of the same address as equal.
+pub fn struct_tags_match(a: &MoveStructTag, b: &MoveStructTag) -> bool {
+    addresses_match(&a.address, &b.address)
+        && a.module == b.module
+        && a.name == b.name
+        && a.generic_type_params == b.generic_type_params
+}
+
+/// Compare entry function IDs, treating different forms of the same address as equal.
+pub fn entry_function_ids_match(a: &EntryFunctionId, b: &EntryFunctionId) -> bool {
+    let modules_match = match (&a.module, &b.module) {
+        (Some(a), Some(b)) => addresses_match(&a.address, &b.address) && a.name == b.name,
+        (None, None) => true,
+        _ => false,
+    };
+    modules_match && a.name == b.name
+}
+
+pub fn get_framework_collection_struct_tag() -> MoveStructTag {
+    MoveStructTag {
+        address: "0x4".to_string(),
+        module: "collection".to_string(),
+        name: "Collection".to_string(),
+        generic_type_params: vec![],
+    }
+}
+
+pub fn get_object_core_struct_tag() -> MoveStructTag {
+    MoveStructTag {
+        address: "0x1".to_string(),
+        module: "object".to_string(),
+        name: "ObjectCore".to_string(),
+        generic_type_params: vec![],
+    }
+}
+
+// Functions we need:
+// - Make it easier to pull out the entry function payload, one function.
+// - Something like get_clean_* for each of the Change:: variants, like WriteTableData.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_processor_framework::indexer_protos::transaction::v1::{
+        EntryFunctionPayload, MoveModuleId, TransactionPayload, UserTransaction,
+        UserTransactionRequest,
+    };
+
+    const SHORT: &str = "0x1";
+    const LONG: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+    const ZERO_PADDED: &str = "0x0001";
+
+    const CONTRACT_SHORT: &str =
+        "0x6b8169be66d9579ba9ad1192708edcf52de713d3513a431df6cb045f425d3d9";
+    const CONTRACT_LONG: &str =
+        "0x06b8169be66d9579ba9ad1192708edcf52de713d3513a431df6cb045f425d3d9";
+
+    fn struct_tag(address: &str) -> MoveStructTag {
+        MoveStructTag {
+            address: address.to_string(),
+            module: "object".to_string(),
+            name: "ObjectCore".to_string(),
+            generic_type_params: vec![],
+        }
+    }
+
+    fn entry_function_id(address: &str, name: &str) -> EntryFunctionId {
+        EntryFunctionId {
+            module: Some(MoveModuleId {
+                address: address.to_string(),
+                name: "canvas_token".to_string(),
+            }),
+            name: name.to_string(),
+        }
+    }
+
+    fn entry_function_txn(function_id: EntryFunctionId) -> Transaction {
+        Transaction {
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    payload: Some(TransactionPayload {
+                        payload: Some(Payload::EntryFunctionPayload(EntryFunctionPayload {
+                            function: Some(function_id),
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        let expected = Some(LONG[2..].to_string());
+        assert_eq!(normalize_address(SHORT), expected);
+        assert_eq!(normalize_address(LONG), expected);
+        assert_eq!(normalize_address(ZERO_PADDED), expected);
+        assert_eq!(normalize_address("1"), expected);
+        assert_eq!(
+            normalize_address("0xABC"),
+            normalize_address("0x0000000000000000000000000000000000000000000000000000000000000abc")
+        );
+        assert_eq!(normalize_address(""), None);
+        assert_eq!(normalize_address("0x"), None);
+        assert_eq!(normalize_address("0xzz"), None);
+        assert_eq!(normalize_address(&format!("0x{}", "1".repeat(65))), None);
+    }
+
+    #[test]
+    fn test_addresses_match() {
+        assert!(addresses_match(SHORT, LONG));
+        assert!(addresses_match(SHORT, ZERO_PADDED));
+        assert!(addresses_match(LONG, ZERO_PADDED));
+        assert!(addresses_match(CONTRACT_SHORT, CONTRACT_LONG));
+        assert!(!addresses_match(SHORT, "0x2"));
+        assert!(!addresses_match(SHORT, "not an address"));
+    }
+
+    #[test]
+    fn test_struct_tags_match() {
+        assert!(struct_tags_match(&struct_tag(SHORT), &struct_tag(LONG)));
+        assert!(struct_tags_match(
+            &struct_tag(ZERO_PADDED),
+            &struct_tag(SHORT)
+        ));
+        assert!(!struct_tags_match(&struct_tag(SHORT), &struct_tag("0x4")));
+
+        let mut other_name = struct_tag(SHORT);
+        other_name.name = "Object".to_string();
+        assert!(!struct_tags_match(&struct_tag(SHORT), &other_name));
+    }
+
+    #[test]
+    fn test_entry_function_id_matches() {
+        let expected = entry_function_id(CONTRACT_LONG, "draw");
+        for address in [CONTRACT_SHORT, CONTRACT_LONG] {
+            let txn = entry_function_txn(entry_function_id(address, "draw"));
+            assert!(entry_function_id_matches(&txn, &expected));
+        }
+
+        let txn = entry_function_txn(entry_function_id(CONTRACT_SHORT, "create"));
+        assert!(!entry_function_id_matches(&txn, &expected));
+
+        let txn = entry_function_txn(entry_function_id(SHORT, "draw"));
+        assert!(!entry_function_id_matches(&txn, &expected));
+
+        assert!(!entry_function_id_matches(
+            &Transaction::default(),
+            &expected
+        ));
+    }
+}
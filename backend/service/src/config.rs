@@ -1,14 +1,14 @@
 use anyhow::Context as AnyhowContext;
-use api::ApiConfig;
-use clap::Parser;
+use api::{ApiConfig, ObjectStorePixelApiConfig, ReplicaConfig, ReplicationFeedConfig};
+use clap::{Parser, Subcommand};
 use figment::{
     providers::{Env, Format, Yaml},
     Figment,
 };
-use flusher::{GcsFlusherConfig, LocalFlusherConfig};
+use flusher::{GcsFlusherConfig, LocalFlusherConfig, SnapshotSchedulerConfig};
 use metadata_storage::PostgresMetadataStorageConfig;
 use pixel_storage::MmapPixelStorageConfig;
-use processor::RunConfig;
+use processor::{RunConfig, TransactionFileFormat};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -16,6 +16,82 @@ use std::path::PathBuf;
 pub struct Args {
     #[clap(short, long)]
     pub config_path: PathBuf,
+
+    /// Delete all the data derived from txns (canvases, metadata, checkpoints and
+    /// the chain ID) before starting the service. This is necessary to point the
+    /// processor at a different chain, otherwise it refuses to start.
+    #[clap(long)]
+    pub reset: bool,
+
+    /// If not given, we run the service as described by the config.
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Read a range of txns from the txn stream service in the processor config and
+    /// write them to a file, which can then be replayed by setting
+    /// `transaction_file_config` in the processor config.
+    Record {
+        /// The first version to record.
+        #[clap(long)]
+        start_version: u64,
+
+        /// The last version to record (inclusive).
+        #[clap(long)]
+        end_version: u64,
+
+        #[clap(long)]
+        output_path: PathBuf,
+
+        /// If not given, we use JSON if the output path ends in .json or .jsonl and
+        /// binary otherwise.
+        #[clap(long, value_parser = parse_transaction_file_format)]
+        format: Option<TransactionFileFormat>,
+    },
+    /// Delete the given canvases and rebuild them by reprocessing their txns, from
+    /// the txn stream service or `transaction_file_config` if set. Stop the processor
+    /// before running this.
+    Rebuild {
+        /// The canvases to rebuild. Can be given multiple times.
+        #[clap(long = "canvas", required = true)]
+        canvases: Vec<String>,
+
+        /// The version to start reprocessing from. This must be at or before the
+        /// version each canvas was created at.
+        #[clap(long)]
+        from_version: u64,
+
+        /// The contract deployment the canvases belong to. Only necessary if there
+        /// are multiple in the config.
+        #[clap(long)]
+        contract_address: Option<String>,
+    },
+    /// Write fresh canvas files from a snapshot of the on-chain state and set the
+    /// processor's checkpoint to the version of the snapshot. Use this when there is
+    /// no txn history to rebuild from. Stop the processor before running this.
+    Bootstrap {
+        /// A JSON file containing the Canvas resources and pixels table items.
+        #[clap(long)]
+        snapshot_path: PathBuf,
+
+        /// The contract deployment the canvases belong to. Only necessary if there
+        /// are multiple in the config.
+        #[clap(long)]
+        contract_address: Option<String>,
+    },
+}
+
+fn parse_transaction_file_format(format: &str) -> Result<TransactionFileFormat, String> {
+    match format {
+        "json" => Ok(TransactionFileFormat::Json),
+        "binary" => Ok(TransactionFileFormat::Binary),
+        _ => Err(format!(
+            "Unknown format {}, expected json or binary",
+            format
+        )),
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -25,11 +101,10 @@ pub enum Config {
     AllInOne(AllInOneConfig),
     ProcessorOnly(ProcessorOnlyConfig),
     MetadataApiOnly(MetadataApiOnlyConfig),
+    PixelApiReplica(PixelApiReplicaConfig),
 }
 
-impl TryFrom<Args> for Config {
-    type Error = anyhow::Error;
-
+impl Config {
     /// This function uses Figment to read the config. In short, it reads the config
     /// first from the file and then environment variables.
     ///
@@ -50,9 +125,9 @@ impl TryFrom<Args> for Config {
     /// You could set that whole "path" with this env var:
     ///
     /// GRAFFIO___METADATA_STORAGE_CONFIG___CONNECTION_STRING=postgres://dport@localhost:5432/postgres
-    fn try_from(args: Args) -> Result<Self, Self::Error> {
+    pub fn from_path(config_path: PathBuf) -> anyhow::Result<Self> {
         Figment::new()
-            .merge(Yaml::file(args.config_path))
+            .merge(Yaml::file(config_path))
             .merge(Env::prefixed("GRAFFIO___").split("___"))
             .extract()
             .context("Failed to load config")
@@ -70,6 +145,13 @@ pub struct AllInOneConfig {
     pub api_config: ApiConfig,
     // For testing purposes you may choose to also run a LocalFlusher.
     pub local_flusher_config: Option<LocalFlusherConfig>,
+    /// If set, we also archive snapshots of the canvases on a schedule.
+    #[serde(default)]
+    pub snapshot_scheduler_config: Option<SnapshotSchedulerConfig>,
+    /// If set, we serve a feed of changes to the canvases for read replicas to
+    /// follow, see PixelApiReplicaConfig.
+    #[serde(default)]
+    pub replication_feed_config: Option<ReplicationFeedConfig>,
 }
 
 /// Config for running just the processor.
@@ -84,14 +166,34 @@ pub struct ProcessorOnlyConfig {
     // we can respond to health checks.
     pub api_config: ApiConfig,
     pub gcs_flusher_config: GcsFlusherConfig,
+    /// If set, we also archive snapshots of the canvases on a schedule.
+    #[serde(default)]
+    pub snapshot_scheduler_config: Option<SnapshotSchedulerConfig>,
+    /// If set, we serve a feed of changes to the canvases for read replicas to
+    /// follow, see PixelApiReplicaConfig.
+    #[serde(default)]
+    pub replication_feed_config: Option<ReplicationFeedConfig>,
 }
 
 /// Config for running just the metadata storage and metadata API. In this setup
-/// it is expected that serving pixel data is handled elsewhere. Generally this
-/// means that the processor is running somewhere else and using the GcsFlusher.
+/// it is expected that the processor is running somewhere else and using the
+/// GcsFlusher. If `object_store_pixel_api_config` is set, the pixel API serves the
+/// images the flusher wrote, otherwise serving pixel data is handled elsewhere.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct MetadataApiOnlyConfig {
     pub metadata_storage_config: PostgresMetadataStorageConfig,
     pub api_config: ApiConfig,
+    #[serde(default)]
+    pub object_store_pixel_api_config: Option<ObjectStorePixelApiConfig>,
+}
+
+/// Config for running a read replica of the pixel API. It follows the feed of a
+/// processor with `replication_feed_config` set, keeping its own copy of the canvases
+/// in memory, so the pixel API can be scaled out separately from the processor.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PixelApiReplicaConfig {
+    pub replica_config: ReplicaConfig,
+    pub api_config: ApiConfig,
 }
@@ -0,0 +1,122 @@
+//! Health checks for the components that live outside of the api crate. Both are
+//! based on timestamps the components record in their metrics.
+
+use anyhow::{bail, Context, Result};
+use api::{HealthCheck, HealthCheckKind};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+fn now_secs() -> Result<f64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("Time went backwards")?
+        .as_secs_f64())
+}
+
+/// Check that everything in `timestamps` happened within `max_age` of now. If there
+/// are no timestamps yet we give the components until `max_age` after startup.
+fn check_timestamps(
+    what: &str,
+    timestamps: HashMap<String, f64>,
+    max_age: Duration,
+    started_at: SystemTime,
+) -> Result<String> {
+    if timestamps.is_empty() {
+        let since_startup = started_at.elapsed().unwrap_or_default();
+        if since_startup > max_age {
+            bail!(
+                "Nothing has {} in the {}s since startup",
+                what,
+                since_startup.as_secs()
+            );
+        }
+        return Ok("Starting up".to_string());
+    }
+
+    let now_secs = now_secs()?;
+    let mut stale = vec![];
+    let mut messages = vec![];
+    let mut names: Vec<&String> = timestamps.keys().collect();
+    names.sort();
+    for name in names {
+        let age_secs = now_secs - timestamps[name];
+        messages.push(format!("{}: {:.0}s", name, age_secs));
+        if age_secs > max_age.as_secs_f64() {
+            stale.push(format!("{} ({:.0}s)", name, age_secs));
+        }
+    }
+    if !stale.is_empty() {
+        bail!(
+            "Last {} more than {}s ago: {}",
+            what,
+            max_age.as_secs(),
+            stale.join(", ")
+        );
+    }
+    Ok(messages.join(", "))
+}
+
+/// Checks each processor is keeping up with the chain, based on the timestamp of
+/// the last txn it processed.
+#[derive(Debug)]
+pub struct ProcessorLagHealthCheck {
+    pub max_lag: Duration,
+    pub started_at: SystemTime,
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for ProcessorLagHealthCheck {
+    fn name(&self) -> String {
+        "processor_lag".to_string()
+    }
+
+    fn kind(&self) -> HealthCheckKind {
+        HealthCheckKind::Readiness
+    }
+
+    async fn check(&self) -> Result<String> {
+        check_timestamps(
+            "processed a txn",
+            processor::get_last_transaction_timestamps(),
+            self.max_lag,
+            self.started_at,
+        )
+    }
+}
+
+/// Checks each flusher has flushed successfully recently and isn't degraded, i.e.
+/// failing repeatedly.
+#[derive(Debug)]
+pub struct FlushHealthCheck {
+    pub max_age: Duration,
+    pub started_at: SystemTime,
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for FlushHealthCheck {
+    fn name(&self) -> String {
+        "flush".to_string()
+    }
+
+    fn kind(&self) -> HealthCheckKind {
+        HealthCheckKind::Readiness
+    }
+
+    async fn check(&self) -> Result<String> {
+        let degraded = flusher::get_degraded_flushers();
+        if !degraded.is_empty() {
+            bail!(
+                "Flushers degraded after failing repeatedly: {}",
+                degraded.join(", ")
+            );
+        }
+        check_timestamps(
+            "flushed successfully",
+            flusher::get_last_successful_flush_timestamps(),
+            self.max_age,
+            self.started_at,
+        )
+    }
+}
@@ -1,21 +1,39 @@
 mod config;
+mod health;
+mod shutdown;
 
-// This crate combines three different modes of operation for the sake of convenience:
+// This crate combines four different modes of operation for the sake of convenience:
 // 1. All in one: Run the processor and API in the same machine.
 // 2. Processor: Run the processor only.
 // 3. Metadata API: Run the metadata API only.
+// 4. Pixel API replica: Serve the pixel API from a copy of the canvases of a processor.
 
-use crate::config::{Args, Config};
+use crate::{
+    config::{Args, Command, Config},
+    health::{FlushHealthCheck, ProcessorLagHealthCheck},
+    shutdown::{wait_for_shutdown_signal, ServiceTasks, Shutdown},
+};
 use anyhow::{Context as AnyhowContext, Result};
-use api::{build_full_route, start_api};
+use api::{
+    build_full_route, start_api, HealthChecks, MetadataStorageHealthCheck, PixelApi,
+    PixelStorageHealthCheck, ReplicaFollower, ReplicationApi, TaskHealthCheck,
+};
+use aptos_move_graphql_scalars::Address;
 use clap::Parser;
-use flusher::{FlusherTrait, GcsFlusher, LocalFlusher};
+use flusher::{FlusherTrait, GcsFlusher, LocalFlusher, SnapshotScheduler};
 use metadata_storage::PostgresMetadataStorage;
 use pixel_storage::MmapPixelStorage;
-use processor::run;
-use std::sync::Arc;
-use tokio::runtime::Builder;
-use tracing::Level;
+use processor::{
+    bootstrap_from_snapshot, rebuild_canvases, record_transactions, reset_storage, run,
+    TransactionFileFormat,
+};
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{runtime::Builder, task::JoinHandle};
+use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
 fn main() -> Result<()> {
@@ -31,7 +49,9 @@ fn main() -> Result<()> {
 
 async fn main_inner() -> Result<()> {
     let args = Args::parse();
-    let config = Config::try_from(args)?;
+    let command = args.command;
+    let reset = args.reset;
+    let config = Config::from_path(args.config_path)?;
 
     let subscriber = FmtSubscriber::builder()
         // All spans of this level or more severe will be written to stdout.
@@ -41,12 +61,28 @@ async fn main_inner() -> Result<()> {
     tracing::subscriber::set_global_default(subscriber)
         .context("Setting default tracing subscriber failed")?;
 
-    let tasks = match config {
+    if let Some(command) = command {
+        if reset {
+            anyhow::bail!("--reset can only be used when running the service");
+        }
+        return run_command(command, config).await;
+    }
+
+    let started_at = SystemTime::now();
+    let task_health_check = Arc::new(TaskHealthCheck::default());
+    let shutdown = Shutdown::default();
+    let mut tasks = ServiceTasks::default();
+    let (pixels_storage, metadata_storage): (
+        Option<Arc<MmapPixelStorage>>,
+        Option<Arc<PostgresMetadataStorage>>,
+    ) = match config {
         Config::AllInOne(config) => {
             // Build pixels storage, which is what lets us read and write to the
             // representation of the canvas on disk.
-            let pixels_storage =
-                Arc::new(MmapPixelStorage::new(config.pixel_storage_config.clone()));
+            let pixels_storage = Arc::new(
+                MmapPixelStorage::new(config.pixel_storage_config.clone())
+                    .context("Failed to initialize pixel storage")?,
+            );
 
             // Build the metadata storage, which is what lets us read and write to the
             // DB. This is generally necessary for all processors since they need
@@ -57,83 +93,404 @@ async fn main_inner() -> Result<()> {
                     .context("Failed to initialize Postgres storage")?,
             );
 
-            // Run the processor. This returns handles to the processor tasks.
-            let mut tasks = run(
-                config.processor_config,
-                metadata_storage.clone(),
-                pixels_storage.clone(),
-            )
-            .await?;
+            if reset {
+                reset_storage(metadata_storage.as_ref(), pixels_storage.as_ref()).await?;
+            }
 
-            // Run the API.
-            let route =
-                build_full_route(Some(pixels_storage.clone()), Some(metadata_storage.clone()))?;
-            let api_task = tokio::spawn(async move {
-                let result = start_api(config.api_config, route).await;
-                eprintln!("API finished unexpectedly: {:?}", result);
-            });
+            // Run the processor. This returns handles to the processor tasks.
+            tasks.processor_tasks = watch_tasks(
+                &task_health_check,
+                "processor",
+                run(
+                    config.processor_config,
+                    metadata_storage.clone(),
+                    pixels_storage.clone(),
+                    shutdown.processors_signal(),
+                )
+                .await?,
+            );
 
             // Run the local flusher if configured (helpful for testing).
             if let Some(config) = &config.local_flusher_config {
                 let local_flusher =
                     LocalFlusher::new(config.clone(), pixels_storage.clone()).await?;
-                tasks.push(local_flusher.run());
+                tasks.flusher_tasks.push(task_health_check.watch(
+                    "local_flusher",
+                    local_flusher.run(shutdown.flushers_signal()),
+                ));
+            }
+
+            // Run the snapshot scheduler if configured.
+            if let Some(config) = &config.snapshot_scheduler_config {
+                let snapshot_scheduler = SnapshotScheduler::new(
+                    config.clone(),
+                    pixels_storage.clone(),
+                    metadata_storage.clone(),
+                )
+                .await?;
+                tasks.flusher_tasks.push(task_health_check.watch(
+                    "snapshot_scheduler",
+                    snapshot_scheduler.run(shutdown.flushers_signal()),
+                ));
+            }
+
+            // Build the health checks.
+            let health_config = &config.api_config.health_config;
+            let mut health_checks = HealthChecks::new(health_config.clone());
+            health_checks.add(task_health_check.clone());
+            health_checks.add(Arc::new(MetadataStorageHealthCheck {
+                metadata_storage: metadata_storage.clone(),
+            }));
+            health_checks.add(Arc::new(PixelStorageHealthCheck {
+                pixel_storage: pixels_storage.clone(),
+            }));
+            health_checks.add(Arc::new(ProcessorLagHealthCheck {
+                max_lag: Duration::from_secs(health_config.max_processor_lag_secs),
+                started_at,
+            }));
+            if config.local_flusher_config.is_some() || config.snapshot_scheduler_config.is_some() {
+                health_checks.add(Arc::new(FlushHealthCheck {
+                    max_age: Duration::from_secs(health_config.max_flush_age_secs),
+                    started_at,
+                }));
             }
 
-            // Return all the tasks.
-            tasks.push(api_task);
+            // Run the API.
+            let route = build_full_route(
+                Some(PixelApi::new(pixels_storage.clone())),
+                Some(metadata_storage.clone()),
+                config.replication_feed_config.map(|feed_config| {
+                    ReplicationApi::primary(pixels_storage.clone(), feed_config)
+                }),
+                Arc::new(health_checks),
+            )?;
+            let api_task = tokio::spawn(async move {
+                let result = start_api(config.api_config, route).await;
+                eprintln!("API finished unexpectedly: {:?}", result);
+            });
+
             tasks
+                .api_tasks
+                .push(task_health_check.watch("api", api_task));
+            (Some(pixels_storage), Some(metadata_storage))
         },
         Config::ProcessorOnly(config) => {
-            let pixels_storage =
-                Arc::new(MmapPixelStorage::new(config.pixel_storage_config.clone()));
+            let pixels_storage = Arc::new(
+                MmapPixelStorage::new(config.pixel_storage_config.clone())
+                    .context("Failed to initialize pixel storage")?,
+            );
             let metadata_storage = Arc::new(
                 PostgresMetadataStorage::new(config.metadata_storage_config.clone())
                     .await
                     .context("Failed to initialize Postgres storage")?,
             );
-            let mut tasks = run(
-                config.processor_config,
-                metadata_storage,
-                pixels_storage.clone(),
-            )
-            .await?;
+            if reset {
+                reset_storage(metadata_storage.as_ref(), pixels_storage.as_ref()).await?;
+            }
+            tasks.processor_tasks = watch_tasks(
+                &task_health_check,
+                "processor",
+                run(
+                    config.processor_config,
+                    metadata_storage.clone(),
+                    pixels_storage.clone(),
+                    shutdown.processors_signal(),
+                )
+                .await?,
+            );
 
             // Run the GCS flusher.
             let gcs_flusher =
                 GcsFlusher::new(config.gcs_flusher_config.clone(), pixels_storage.clone()).await?;
-            tasks.push(gcs_flusher.run());
+            tasks.flusher_tasks.push(
+                task_health_check.watch("gcs_flusher", gcs_flusher.run(shutdown.flushers_signal())),
+            );
+
+            // Run the snapshot scheduler if configured.
+            if let Some(config) = &config.snapshot_scheduler_config {
+                let snapshot_scheduler = SnapshotScheduler::new(
+                    config.clone(),
+                    pixels_storage.clone(),
+                    metadata_storage.clone(),
+                )
+                .await?;
+                tasks.flusher_tasks.push(task_health_check.watch(
+                    "snapshot_scheduler",
+                    snapshot_scheduler.run(shutdown.flushers_signal()),
+                ));
+            }
+
+            // Build the health checks.
+            let health_config = &config.api_config.health_config;
+            let mut health_checks = HealthChecks::new(health_config.clone());
+            health_checks.add(task_health_check.clone());
+            health_checks.add(Arc::new(MetadataStorageHealthCheck {
+                metadata_storage: metadata_storage.clone(),
+            }));
+            health_checks.add(Arc::new(PixelStorageHealthCheck {
+                pixel_storage: pixels_storage.clone(),
+            }));
+            health_checks.add(Arc::new(ProcessorLagHealthCheck {
+                max_lag: Duration::from_secs(health_config.max_processor_lag_secs),
+                started_at,
+            }));
+            health_checks.add(Arc::new(FlushHealthCheck {
+                max_age: Duration::from_secs(health_config.max_flush_age_secs),
+                started_at,
+            }));
 
-            // Run the API, but without the pixel or metadata APIs attached.
-            let route = build_full_route(None, None)?;
+            // Run the API, but without the pixel or metadata APIs attached. If
+            // configured we serve the feed for read replicas.
+            let replication_api = config
+                .replication_feed_config
+                .map(|feed_config| ReplicationApi::primary(pixels_storage.clone(), feed_config));
+            let route = build_full_route(None, None, replication_api, Arc::new(health_checks))?;
             let api_task = tokio::spawn(async move {
                 let result = start_api(config.api_config, route).await;
                 eprintln!("API finished unexpectedly: {:?}", result);
             });
-            tasks.push(api_task);
-
             tasks
+                .api_tasks
+                .push(task_health_check.watch("api", api_task));
+            (Some(pixels_storage), Some(metadata_storage))
         },
         Config::MetadataApiOnly(config) => {
+            if reset {
+                anyhow::bail!("--reset can only be used with a config that runs the processor");
+            }
             let metadata_storage = Arc::new(
                 PostgresMetadataStorage::new(config.metadata_storage_config.clone())
                     .await
                     .context("Failed to initialize Postgres storage")?,
             );
-            let route = build_full_route(None, Some(metadata_storage.clone()))?;
+            let mut health_checks = HealthChecks::new(config.api_config.health_config.clone());
+            health_checks.add(task_health_check.clone());
+            health_checks.add(Arc::new(MetadataStorageHealthCheck {
+                metadata_storage: metadata_storage.clone(),
+            }));
+            // Serve the images the flusher wrote to the object store if configured.
+            let pixel_api = match config.object_store_pixel_api_config.clone() {
+                Some(pixel_api_config) => Some(
+                    PixelApi::from_object_store(pixel_api_config)
+                        .await
+                        .context("Failed to initialize object store pixel API")?,
+                ),
+                None => None,
+            };
+            let route = build_full_route(
+                pixel_api,
+                Some(metadata_storage.clone()),
+                None,
+                Arc::new(health_checks),
+            )?;
             let api_task = tokio::spawn(async move {
                 let result = start_api(config.api_config, route).await;
                 eprintln!("API finished unexpectedly: {:?}", result);
             });
-            vec![api_task]
+            tasks
+                .api_tasks
+                .push(task_health_check.watch("api", api_task));
+            (None, Some(metadata_storage))
+        },
+        Config::PixelApiReplica(config) => {
+            if reset {
+                anyhow::bail!("--reset can only be used with a config that runs the processor");
+            }
+            // Follow the primary. The follower stops along with the processors on
+            // shutdown, since it plays the same role here.
+            let follower = ReplicaFollower::new(config.replica_config.clone())?;
+            let pixels_storage = follower.get_pixel_storage();
+            let mut health_checks = HealthChecks::new(config.api_config.health_config.clone());
+            health_checks.add(task_health_check.clone());
+            health_checks.add(Arc::new(follower.health_check()));
+            let route = build_full_route(
+                Some(PixelApi::new(pixels_storage)),
+                None,
+                Some(ReplicationApi::replica(&follower)),
+                Arc::new(health_checks),
+            )?;
+            tasks.processor_tasks.push(
+                task_health_check.watch("replica", follower.run(shutdown.processors_signal())),
+            );
+            let api_task = tokio::spawn(async move {
+                let result = start_api(config.api_config, route).await;
+                eprintln!("API finished unexpectedly: {:?}", result);
+            });
+            tasks
+                .api_tasks
+                .push(task_health_check.watch("api", api_task));
+            (None, None)
         },
     };
 
-    // Wait for all the tasks.
-    let result = futures::future::select_all(tasks).await;
+    // Run until one of the tasks ends, which none of them should, or we're told to
+    // shut down, in which case we shut everything down cleanly and exit 0.
+    tokio::select! {
+        error = tasks.wait_for_any() => return Err(error),
+        result = wait_for_shutdown_signal() => result?,
+    }
+    shutdown.run(tasks, pixels_storage, metadata_storage).await
+}
+
+/// Watch each of the given tasks for the task health check, naming them after what
+/// they're part of.
+fn watch_tasks(
+    task_health_check: &TaskHealthCheck,
+    name: &str,
+    tasks: Vec<JoinHandle<()>>,
+) -> Vec<JoinHandle<()>> {
+    tasks
+        .into_iter()
+        .enumerate()
+        .map(|(i, task)| task_health_check.watch(&format!("{}_{}", name, i), task))
+        .collect()
+}
 
-    Err(anyhow::anyhow!(
-        "One of the futures finished unexpectedly: {:#?}",
-        result
-    ))
+/// Run one of the one off commands rather than the service.
+async fn run_command(command: Command, config: Config) -> Result<()> {
+    match command {
+        Command::Record {
+            start_version,
+            end_version,
+            output_path,
+            format,
+        } => {
+            let processor_config = match config {
+                Config::AllInOne(config) => config.processor_config,
+                Config::ProcessorOnly(config) => config.processor_config,
+                Config::MetadataApiOnly(_) | Config::PixelApiReplica(_) => {
+                    anyhow::bail!("The config must contain a processor config to record txns")
+                },
+            };
+            let format = format.unwrap_or_else(|| TransactionFileFormat::from_path(&output_path));
+            let num_written = record_transactions(
+                processor_config.stream_subscriber_config,
+                start_version,
+                end_version,
+                &output_path,
+                format,
+            )
+            .await
+            .context("Failed to record txns")?;
+            info!(
+                "Recorded {} txns from {} to {} to {}",
+                num_written,
+                start_version,
+                end_version,
+                output_path.display()
+            );
+            Ok(())
+        },
+        Command::Rebuild {
+            canvases,
+            from_version,
+            contract_address,
+        } => {
+            let (processor_config, pixel_storage_config, metadata_storage_config) = match config {
+                Config::AllInOne(config) => (
+                    config.processor_config,
+                    config.pixel_storage_config,
+                    config.metadata_storage_config,
+                ),
+                Config::ProcessorOnly(config) => (
+                    config.processor_config,
+                    config.pixel_storage_config,
+                    config.metadata_storage_config,
+                ),
+                Config::MetadataApiOnly(_) | Config::PixelApiReplica(_) => {
+                    anyhow::bail!("The config must contain a processor config to rebuild canvases")
+                },
+            };
+            let canvas_addresses = canvases
+                .iter()
+                .map(|canvas| {
+                    Address::from_str(canvas)
+                        .context(format!("Failed to parse canvas address {}", canvas))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let contract_address = contract_address
+                .map(|contract_address| {
+                    Address::from_str(&contract_address).context(format!(
+                        "Failed to parse contract address {}",
+                        contract_address
+                    ))
+                })
+                .transpose()?;
+            let pixels_storage = Arc::new(
+                MmapPixelStorage::new(pixel_storage_config)
+                    .context("Failed to initialize pixel storage")?,
+            );
+            let metadata_storage = Arc::new(
+                PostgresMetadataStorage::new(metadata_storage_config)
+                    .await
+                    .context("Failed to initialize Postgres storage")?,
+            );
+            rebuild_canvases(
+                processor_config,
+                canvas_addresses,
+                from_version,
+                contract_address,
+                metadata_storage,
+                pixels_storage,
+            )
+            .await
+            .context("Failed to rebuild canvases")?;
+            info!("Rebuilt canvases {}", canvases.join(", "));
+            Ok(())
+        },
+        Command::Bootstrap {
+            snapshot_path,
+            contract_address,
+        } => {
+            let (processor_config, pixel_storage_config, metadata_storage_config) = match config {
+                Config::AllInOne(config) => (
+                    config.processor_config,
+                    config.pixel_storage_config,
+                    config.metadata_storage_config,
+                ),
+                Config::ProcessorOnly(config) => (
+                    config.processor_config,
+                    config.pixel_storage_config,
+                    config.metadata_storage_config,
+                ),
+                Config::MetadataApiOnly(_) | Config::PixelApiReplica(_) => {
+                    anyhow::bail!(
+                        "The config must contain a processor config to bootstrap canvases"
+                    )
+                },
+            };
+            let contract_address = contract_address
+                .map(|contract_address| {
+                    Address::from_str(&contract_address).context(format!(
+                        "Failed to parse contract address {}",
+                        contract_address
+                    ))
+                })
+                .transpose()?;
+            let pixels_storage = Arc::new(
+                MmapPixelStorage::new(pixel_storage_config)
+                    .context("Failed to initialize pixel storage")?,
+            );
+            let metadata_storage = Arc::new(
+                PostgresMetadataStorage::new(metadata_storage_config)
+                    .await
+                    .context("Failed to initialize Postgres storage")?,
+            );
+            let num_canvases = bootstrap_from_snapshot(
+                processor_config,
+                &snapshot_path,
+                contract_address,
+                metadata_storage,
+                pixels_storage,
+            )
+            .await
+            .context("Failed to bootstrap canvases from snapshot")?;
+            info!(
+                "Bootstrapped {} canvases from {}",
+                num_canvases,
+                snapshot_path.display()
+            );
+            Ok(())
+        },
+    }
 }
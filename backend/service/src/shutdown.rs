@@ -0,0 +1,144 @@
+//! On SIGTERM or ctrl-c we shut down in an order that means nothing the processors
+//! have done is lost: first the processors stop taking txns from the stream and
+//! finish the ones they already have, which updates their checkpoints. Then we flush
+//! the mmaps to disk, have each flusher flush one last time so remote storage
+//! matches the checkpoints, and close the DB connections.
+
+use anyhow::{Context, Result};
+use metadata_storage::PostgresMetadataStorage;
+use pixel_storage::{MmapPixelStorage, PixelStorageTrait};
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+    task::JoinHandle,
+};
+use tracing::{error, info, warn};
+
+/// How long we wait for the processors to finish the txns they already have.
+const PROCESSORS_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long we wait for the flushers to do their final flush.
+const FLUSHERS_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The long running tasks of the service, grouped by how they get shut down.
+#[derive(Debug, Default)]
+pub struct ServiceTasks {
+    pub processor_tasks: Vec<JoinHandle<()>>,
+    pub flusher_tasks: Vec<JoinHandle<()>>,
+    /// These just stop when the process exits.
+    pub api_tasks: Vec<JoinHandle<()>>,
+}
+
+impl ServiceTasks {
+    /// Wait for any of the tasks to finish. None of them should until we shut down,
+    /// so this returns an error saying which one did.
+    pub async fn wait_for_any(&mut self) -> anyhow::Error {
+        let tasks = self
+            .processor_tasks
+            .iter_mut()
+            .chain(self.flusher_tasks.iter_mut())
+            .chain(self.api_tasks.iter_mut());
+        let (result, index, _) = futures::future::select_all(tasks).await;
+        anyhow::anyhow!(
+            "One of the futures (index {}) finished unexpectedly: {:#?}",
+            index,
+            result
+        )
+    }
+}
+
+/// The signals that tell each group of tasks to shut down.
+#[derive(Debug)]
+pub struct Shutdown {
+    processors: watch::Sender<bool>,
+    flushers: watch::Sender<bool>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self {
+            processors: watch::channel(false).0,
+            flushers: watch::channel(false).0,
+        }
+    }
+}
+
+impl Shutdown {
+    /// Pass this to the processors, they stop once it is set.
+    pub fn processors_signal(&self) -> watch::Receiver<bool> {
+        self.processors.subscribe()
+    }
+
+    /// Pass this to the flushers, they flush one last time and stop once it is set.
+    pub fn flushers_signal(&self) -> watch::Receiver<bool> {
+        self.flushers.subscribe()
+    }
+
+    /// Shut everything down in order. We keep going if a step fails, since the later
+    /// steps are still worth doing, but return an error at the end if any did.
+    pub async fn run(
+        self,
+        tasks: ServiceTasks,
+        pixels_storage: Option<Arc<MmapPixelStorage>>,
+        metadata_storage: Option<Arc<PostgresMetadataStorage>>,
+    ) -> Result<()> {
+        info!("Shutting down");
+        let mut failed = false;
+
+        self.processors.send_replace(true);
+        failed |= !wait_for_tasks("processors", tasks.processor_tasks, PROCESSORS_TIMEOUT).await;
+
+        if let Some(pixels_storage) = pixels_storage {
+            if let Err(e) = pixels_storage.flush_to_disk().await {
+                error!("Failed to flush pixel storage to disk: {:#}", e);
+                failed = true;
+            }
+        }
+
+        self.flushers.send_replace(true);
+        failed |= !wait_for_tasks("flushers", tasks.flusher_tasks, FLUSHERS_TIMEOUT).await;
+
+        if let Some(metadata_storage) = metadata_storage {
+            if let Err(e) = metadata_storage.close().await {
+                error!("Failed to close metadata storage: {:#}", e);
+                failed = true;
+            }
+        }
+
+        if failed {
+            anyhow::bail!("Failed to shut down cleanly, see the errors above");
+        }
+        info!("Shut down cleanly");
+        Ok(())
+    }
+}
+
+/// Wait for the given tasks to end. Returns whether they all ended in time.
+async fn wait_for_tasks(what: &str, tasks: Vec<JoinHandle<()>>, timeout: Duration) -> bool {
+    info!("Waiting for {} {} to finish", tasks.len(), what);
+    match tokio::time::timeout(timeout, futures::future::join_all(tasks)).await {
+        Ok(_) => true,
+        Err(_) => {
+            warn!(
+                "Timed out after {}s waiting for {} to finish",
+                timeout.as_secs(),
+                what
+            );
+            false
+        },
+    }
+}
+
+/// Wait for SIGTERM, e.g. from k8s, or ctrl-c.
+pub async fn wait_for_shutdown_signal() -> Result<()> {
+    let mut sigterm = signal(SignalKind::terminate()).context("Failed to listen for SIGTERM")?;
+    tokio::select! {
+        _ = sigterm.recv() => info!("Received SIGTERM"),
+        result = tokio::signal::ctrl_c() => {
+            result.context("Failed to listen for ctrl-c")?;
+            info!("Received ctrl-c");
+        },
+    }
+    Ok(())
+}